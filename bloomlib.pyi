@@ -6,17 +6,31 @@ from typing import List, Any
 class BloomFilter:
     """
     A class representing a bloom filter that you can use as a set
+    Items are hashed by value: None, bool, int, float, str, bytes, lists, tuples, dicts, sets, dates and times
+    hash the same in every process; other objects raise TypeError
 
     :param expected_number_of_items: the number of items you expect to store; used to optimize the filter size
     :param desired_false_positive_rate: the percentage of false positives you accept expressed as a float between 0 and 1
+    :param overflow_policy: what to do when more than expected_number_of_items are added:
+        "ignore", "warn" (emits a RuntimeWarning once) or "error" (add raises ValueError)
+    :param count_new_items_only: only count inserts that set at least one new bit towards the inserted count
+    :param double_hashing: hash every item once and derive all indices from that hash; faster, at a nearly identical false positive rate
+    :param hash_algorithm: "murmur3_128" (default), "murmur3_32", "xxh3", "siphash13", "fnv1a" or "crc32c"; the 32-bit
+        "murmur3_32" and "crc32c" raise ValueError for filters of more than 2**32 bits
+    :param seed: secret 128-bit key (16 bytes or an int below 2**128, not all zeros); items are then hashed with keyed SipHash-1-3
+        so that nobody without the seed can craft items that saturate chosen bits
+    :param persist_seed: write the seed into to_bytes/save output; by default it is left out and the filter
+        can only be loaded again by passing the seed to from_bytes/load. Pickles always include the seed, so
+        seeded filters can be sent to other processes
     """
-    def __init__(self, expected_number_of_items: int, desired_false_positive_rate: float) -> None: ...
+    def __init__(self, expected_number_of_items: int, desired_false_positive_rate: float, overflow_policy: str = "ignore", count_new_items_only: bool = False, double_hashing: bool = False, hash_algorithm: typing.Optional[str] = None, seed: typing.Union[bytes, int, None] = None, persist_seed: bool = False) -> None: ...
     def add(self, values: Any) -> None:
         """
         Adds a value to the bloomset
     
         :param values: List of values that you want added to the bloomset
         :return: void
+        :raises ValueError: when the filter is full and overflow_policy is "error"
         """
     def add_bulk(self, items: typing.Iterable[Any]) -> None:
         """
@@ -41,8 +55,1031 @@ class BloomFilter:
         Memory size
         :return: int representing the number of bits that the Bloom filter's memory uses
         """
+    def get_hash_algorithm(self) -> str:
+        """
+        :return: str naming the hash algorithm this Bloom filter uses
+        """
     def estimate_false_positive_rate(self) -> float:
         """
         Estimates the False Positive rate
         :return: float representing the estimated false positive rate (between 0 and 1)
-        """
\ No newline at end of file
+        """
+    def get_inserted_count(self) -> int:
+        """
+        :return: int representing the number of inserts so far (see count_new_items_only)
+        """
+    def remaining_capacity(self) -> int:
+        """
+        :return: int representing how many more items fit before expected_number_of_items is exceeded
+        """
+    def current_false_positive_rate(self) -> float:
+        """
+        Calculates the false positive rate from the bits that are actually set: (set bits / bits) ^ hashes
+        :return: float representing the current false positive rate (between 0 and 1)
+        """
+    def fill_ratio(self) -> float:
+        """
+        :return: float representing the fraction of bits that are set (around 0.5 at design capacity)
+        """
+    def is_saturated(self, threshold: float = 0.6) -> bool:
+        """
+        Checks whether the filter is overloaded
+        :param threshold: fill ratio from which the filter counts as saturated; the default sits above the fill ratio
+                          of a filter that holds its expected number of items
+        :return: True if fill_ratio() >= threshold
+        """
+    def estimate_cardinality(self) -> float:
+        """
+        Estimates the number of distinct items that were added, based on the number of set bits
+        :return: float representing the estimated number of items; inf when all bits are set
+        """
+    def estimate_union_size(self, other: "BloomFilter") -> float:
+        """
+        Estimates the number of distinct items in the union of both filters
+        :param other: filter with the same number of bits, hashes and hash algorithm
+        :return: float representing the estimated number of items
+        """
+    def estimate_intersection_size(self, other: "BloomFilter") -> float:
+        """
+        Estimates the number of distinct items that were added to both filters
+        :param other: filter with the same number of bits, hashes and hash algorithm
+        :return: float representing the estimated number of items
+        """
+    def __len__(self) -> int:
+        """
+        Approximate number of distinct items that were added (estimate_cardinality, rounded)
+        :raises OverflowError: when all bits are set
+        """
+    def __bool__(self) -> bool:
+        """
+        A filter is always truthy, also when it is empty or saturated
+        """
+    def to_bytes(self) -> bytes:
+        """
+        Serializes the Bloom filter to a versioned binary format
+        :return: bytes that can be loaded again with BloomFilter.from_bytes
+        """
+    @staticmethod
+    def from_bytes(data: bytes, seed: typing.Union[bytes, int, None] = None) -> "BloomFilter":
+        """
+        Loads a Bloom filter that was serialized with to_bytes
+        :param data: serialized Bloom filter
+        :param seed: the seed of a seeded filter that was serialized without it
+        :return: the restored BloomFilter
+        :raises ValueError: when the data is truncated or not a serialized BloomFilter, or the seed is missing or wrong
+        """
+    def save(self, path: str) -> None:
+        """
+        Writes the serialized Bloom filter to a file
+        :param path: location of the file
+        :return: void
+        """
+    @staticmethod
+    def load(path: str, seed: typing.Union[bytes, int, None] = None) -> "BloomFilter":
+        """
+        Reads a Bloom filter from a file written with save
+        :param path: location of the file
+        :param seed: the seed of a seeded filter that was saved without it
+        :return: the restored BloomFilter
+        :raises ValueError: when the file does not contain a serialized BloomFilter
+        """
+    def __or__(self, other: "BloomFilter") -> "BloomFilter":
+        """
+        Union: a new filter containing the items of both filters
+        :raises ValueError: when the filters differ in number of bits, hashes or hash algorithm
+        """
+    def __and__(self, other: "BloomFilter") -> "BloomFilter":
+        """
+        Intersection: a new filter containing the items that were added to both filters
+        :raises ValueError: when the filters differ in number of bits, hashes or hash algorithm
+        """
+    def __ior__(self, other: "BloomFilter") -> "BloomFilter": ...
+    def __iand__(self, other: "BloomFilter") -> "BloomFilter": ...
+    def __getstate__(self) -> bytes: ...
+    def __setstate__(self, state: bytes) -> None: ...
+    def __reduce__(self) -> typing.Tuple[typing.Any, ...]: ...
+
+
+class CountingBloomFilter:
+    """
+    A Bloom filter that keeps a counter per slot so that items can be removed again.
+    Counters that reach their maximum value saturate and are never decremented again.
+
+    :param expected_number_of_items: the number of items you expect to store; used to optimize the filter size
+    :param desired_false_positive_rate: the percentage of false positives you accept expressed as a float between 0 and 1
+    :param counter_bits: number of bits per counter; 4, 8, 16 or 32
+    :param conservative_update: only increment the counters holding the current minimum; gives tighter
+        estimate_count results, but items can no longer be removed
+    :raises ValueError: when expected_number_of_items is 0, the false positive rate is not between 0 and 1 or
+        counter_bits is not supported
+    """
+    def __init__(self, expected_number_of_items: int, desired_false_positive_rate: float, counter_bits: int = 4, conservative_update: bool = False) -> None: ...
+    def add(self, item: Any) -> None:
+        """
+        Adds an item to the filter
+        :param item: item to add
+        :return: void
+        """
+    def add_bulk(self, items: typing.Iterable[Any]) -> None:
+        """
+        Add items in bulk to the filter
+        :param items: List of items
+        :return: void
+        """
+    def remove(self, item: Any) -> bool:
+        """
+        Removes an item that was added before
+        :param item: item to remove
+        :return: False (and nothing is changed) when the item cannot have been in the filter
+        :raises ValueError: when the filter uses conservative update
+        """
+    def estimate_count(self, item: Any) -> int:
+        """
+        Estimates how many times an item was added (the minimum of its counters)
+        :param item: item to count
+        :return: int that is never lower than the real count unless counters saturated; may be higher
+        """
+    def contains(self, item: Any) -> bool:
+        """
+        Looks up whether an item is contained
+        :param item: lookup if the filter contains this item
+        :return: bool representing that the item is definitely not contained (false) or maybe (true)
+        """
+    def clear(self) -> None:
+        """
+        Removes all items from the filter
+        :return: void
+        """
+    def get_number_of_hashes(self) -> int:
+        """
+        :return: int representing the number of hashes this filter uses
+        """
+    def get_number_of_counters(self) -> int:
+        """
+        :return: int representing the number of counters this filter uses
+        """
+    def get_counter_bits(self) -> int:
+        """
+        :return: int representing the number of bits per counter
+        """
+    def get_number_of_saturated_counters(self) -> int:
+        """
+        :return: int representing the number of counters that reached their maximum value
+        """
+    def to_bloom_filter(self) -> BloomFilter:
+        """
+        Converts to a plain BloomFilter; every counter above zero becomes a set bit
+        :return: BloomFilter that contains exactly the same items
+        """
+
+
+class BlockedBloomFilter:
+    """
+    A Bloom filter that keeps all bits of an item in one 512-bit block, so every lookup touches a single cache line.
+    Uses a little more memory than BloomFilter to reach the same false positive rate.
+
+    :param expected_number_of_items: the number of items you expect to store; used to optimize the filter size
+    :param desired_false_positive_rate: the percentage of false positives you accept expressed as a float between 0 and 1;
+        rates below about 1e-7 cost blocking too much memory and are rejected
+    :raises ValueError: when expected_number_of_items is 0 or the false positive rate is out of range
+    """
+    def __init__(self, expected_number_of_items: int, desired_false_positive_rate: float) -> None: ...
+    def add(self, item: Any) -> None:
+        """
+        Adds an item to the filter
+        :param item: item to add
+        :return: void
+        """
+    def add_bulk(self, items: typing.Iterable[Any]) -> None:
+        """
+        Add items in bulk to the filter
+        :param items: List of items
+        :return: void
+        """
+    def contains(self, item: Any) -> bool:
+        """
+        Looks up whether an item is contained
+        :param item: lookup if the filter contains this item
+        :return: bool representing that the item is definitely not contained (false) or maybe (true)
+        """
+    def clear(self) -> None:
+        """
+        Removes all items from the filter
+        :return: void
+        """
+    def get_number_of_hashes(self) -> int:
+        """
+        :return: int representing the number of bits every item sets within its block
+        """
+    def get_number_of_bits(self) -> int:
+        """
+        :return: int representing the number of bits that the filter's memory uses (a multiple of 512)
+        """
+    def get_number_of_blocks(self) -> int:
+        """
+        :return: int representing the number of 512-bit blocks
+        """
+    def estimate_false_positive_rate(self) -> float:
+        """
+        Estimates the False Positive rate at expected_number_of_items, taking the uneven load of the blocks into account
+        :return: float representing the estimated false positive rate (between 0 and 1)
+        """
+
+
+class SplitBlockBloomFilter:
+    """
+    The split-block Bloom filter of the Apache Parquet format; use it to probe the bloom filters of Parquet column
+    chunks or to build ones that Parquet writers can embed.
+    Values are hashed in their Parquet PLAIN encoding: bytes as-is, str as UTF-8, int as INT64 and float as DOUBLE.
+    Pass the little-endian bytes yourself for INT32 and FLOAT columns.
+
+    :param expected_number_of_items: the number of distinct values you expect to store; used to size the bitset
+    :param desired_false_positive_rate: the percentage of false positives you accept expressed as a float between 0 and 1
+    """
+    def __init__(self, expected_number_of_items: int, desired_false_positive_rate: float) -> None: ...
+    def add(self, item: typing.Union[bytes, str, int, float]) -> None:
+        """
+        Adds a value to the filter
+        :param item: value to add
+        :return: void
+        :raises TypeError: when the value is not bytes, str, int or float
+        """
+    def add_bulk(self, items: typing.Iterable[typing.Union[bytes, str, int, float]]) -> None:
+        """
+        Add values in bulk to the filter
+        :param items: List of values
+        :return: void
+        """
+    def contains(self, item: typing.Union[bytes, str, int, float]) -> bool:
+        """
+        Looks up whether a value is contained
+        :param item: lookup if the filter contains this value
+        :return: bool representing that the value is definitely not contained (false) or maybe (true)
+        """
+    def add_hash(self, hash: int) -> None:
+        """
+        Adds a value by its 64-bit xxHash64 (seed 0)
+        :param hash: hash of the PLAIN encoded value
+        :return: void
+        """
+    def contains_hash(self, hash: int) -> bool:
+        """
+        Looks up a value by its 64-bit xxHash64 (seed 0)
+        :param hash: hash of the PLAIN encoded value
+        :return: bool representing that the value is definitely not contained (false) or maybe (true)
+        """
+    def clear(self) -> None:
+        """
+        Removes all values from the filter
+        :return: void
+        """
+    def get_number_of_bytes(self) -> int:
+        """
+        :return: int representing the size of the bitset in bytes (a power of two)
+        """
+    def get_number_of_blocks(self) -> int:
+        """
+        :return: int representing the number of 256-bit blocks
+        """
+    def false_positive_rate(self, number_of_items: int) -> float:
+        """
+        :param number_of_items: number of distinct values in the filter
+        :return: float representing the false positive rate at that number of values (between 0 and 1)
+        """
+    def to_bytes(self) -> bytes:
+        """
+        :return: bytes of the bitset exactly as Parquet stores it
+        """
+    @staticmethod
+    def from_bytes(data: bytes) -> "SplitBlockBloomFilter":
+        """
+        Loads a bitset written by to_bytes or read from a Parquet file
+        :param data: the bitset, without header
+        :return: the restored SplitBlockBloomFilter
+        :raises ValueError: when the size is not a multiple of 32 bytes
+        """
+    def to_parquet_bytes(self) -> bytes:
+        """
+        :return: bytes of the Thrift BloomFilterHeader followed by the bitset, as embedded in a Parquet file
+        """
+    @staticmethod
+    def from_parquet_bytes(data: bytes) -> "SplitBlockBloomFilter":
+        """
+        Loads the bytes at a column chunk's bloom_filter_offset; bytes after the bitset are ignored
+        :param data: BloomFilterHeader followed by the bitset
+        :return: the restored SplitBlockBloomFilter
+        :raises ValueError: when the header is malformed or describes another algorithm, hash or compression
+        """
+
+
+class ScalableBloomFilter:
+    """
+    A Bloom filter that adds a larger stage whenever the newest one is full (Almeida et al.), so you don't need to
+    know the number of items up front. The false positive rate of the whole filter stays below the requested rate.
+
+    :param initial_capacity: the number of items the first stage is sized for
+    :param desired_false_positive_rate: upper bound of the false positive rate, expressed as a float between 0 and 1
+    :param growth_factor: every stage holds this many times more items than the previous one (>= 1)
+    :param tightening_ratio: every stage has this many times the false positive rate of the previous one (between 0 and 1)
+    """
+    def __init__(self, initial_capacity: int, desired_false_positive_rate: float, growth_factor: float = 2.0, tightening_ratio: float = 0.85) -> None: ...
+    def add(self, item: Any) -> None:
+        """
+        Adds an item to the newest stage; items that seem to be contained already are skipped
+        :param item: item to add
+        :return: void
+        """
+    def add_bulk(self, items: typing.Iterable[Any]) -> None:
+        """
+        Add items in bulk to the filter
+        :param items: List of items
+        :return: void
+        """
+    def contains(self, item: Any) -> bool:
+        """
+        Looks up whether an item is contained in any of the stages
+        :param item: lookup if the filter contains this item
+        :return: bool representing that the item is definitely not contained (false) or maybe (true)
+        """
+    def clear(self) -> None:
+        """
+        Removes all items and shrinks the filter back to its first stage
+        :return: void
+        """
+    def get_number_of_stages(self) -> int:
+        """
+        :return: int representing the number of stages
+        """
+    def get_number_of_bits(self) -> int:
+        """
+        :return: int representing the number of bits of all stages together
+        """
+    def get_inserted_count(self) -> int:
+        """
+        :return: int representing the number of items added; repeated items are counted once
+        """
+    def get_capacity(self) -> int:
+        """
+        :return: int representing the number of items the filter holds before it adds another stage
+        """
+    def current_false_positive_rate(self) -> float:
+        """
+        Calculates the false positive rate of all stages together from the bits that are set
+        :return: float representing the current false positive rate (between 0 and 1)
+        """
+    def to_bytes(self) -> bytes:
+        """
+        :return: bytes that can be loaded again with ScalableBloomFilter.from_bytes
+        """
+    @staticmethod
+    def from_bytes(data: bytes) -> "ScalableBloomFilter":
+        """
+        :param data: serialized ScalableBloomFilter
+        :return: the restored ScalableBloomFilter
+        :raises ValueError: when the data is truncated or not a serialized ScalableBloomFilter
+        """
+    def save(self, path: str) -> None:
+        """
+        Writes the serialized filter to a file
+        :param path: location of the file
+        :return: void
+        """
+    @staticmethod
+    def load(path: str) -> "ScalableBloomFilter":
+        """
+        Reads a filter from a file written with save
+        :param path: location of the file
+        :return: the restored ScalableBloomFilter
+        """
+    def __getstate__(self) -> bytes: ...
+    def __setstate__(self, state: bytes) -> None: ...
+    def __reduce__(self) -> typing.Tuple[typing.Any, ...]: ...
+
+
+class SlidingWindowBloomFilter:
+    """
+    A Bloom filter that forgets items: it keeps a ring of generations, new items go into the newest one and
+    advancing the window clears the oldest one. An item is remembered for between generations - 1 and generations
+    advances after it was added.
+
+    :param expected_items_per_generation: the number of items you expect to add during one generation
+    :param desired_false_positive_rate: the false positive rate of every generation, a float between 0 and 1
+    :param generations: number of generations in the window
+    :param generation_seconds: advance the window automatically every this many seconds; None advances only on advance()
+    :param clock: function returning the current time in seconds (default: a monotonic clock); pass your own to
+        control time in tests; it is called once before every operation, and an exception it raises aborts the
+        operation without changing the filter
+    """
+    def __init__(self, expected_items_per_generation: int, desired_false_positive_rate: float, generations: int, generation_seconds: typing.Optional[float] = None, clock: typing.Optional[typing.Callable[[], float]] = None) -> None: ...
+    def add(self, item: Any) -> None:
+        """
+        Adds an item to the newest generation, after expiring generations whose time is up
+        :param item: item to add
+        :return: void
+        """
+    def add_bulk(self, items: typing.Iterable[Any]) -> None:
+        """
+        Add items in bulk to the filter
+        :param items: List of items
+        :return: void
+        """
+    def contains(self, item: Any) -> bool:
+        """
+        Looks up whether an item was added within the window
+        :param item: lookup if the filter contains this item
+        :return: bool representing that the item is definitely not contained or expired (false) or maybe (true)
+        """
+    def advance(self) -> None:
+        """
+        Moves the window by one generation; the oldest generation is cleared
+        :return: void
+        """
+    def expire(self) -> None:
+        """
+        Clears the generations whose time is up; add does this too, so it's only needed to release an idle filter
+        :return: void
+        """
+    def clear(self) -> None:
+        """
+        Removes all items from all generations
+        :return: void
+        """
+    def get_number_of_generations(self) -> int:
+        """
+        :return: int representing the number of generations in the window
+        """
+    def get_number_of_bits(self) -> int:
+        """
+        :return: int representing the number of bits of all generations together
+        """
+    def current_false_positive_rate(self) -> float:
+        """
+        Calculates the false positive rate of the live generations together from the bits that are set
+        :return: float representing the current false positive rate (between 0 and 1)
+        """
+
+
+class StableBloomFilter:
+    """
+    A Bloom filter for never-ending streams (Deng & Rafiei): every add first decrements a few random cells, so old
+    items fade out and the false positive rate settles at a fixed level instead of growing towards 1.
+    Items that were added long ago can be reported as not contained (false negatives).
+
+    :param number_of_cells: number of cells; more cells remember more recent items
+    :param desired_false_positive_rate: the false positive rate the filter settles at, a float between 0 and 1
+    :param max_value: value the cells of an item are set to; higher values let items fade out more gradually
+    :param seed: seed for the random choice of cells to decrement; makes the filter deterministic
+    """
+    def __init__(self, number_of_cells: int, desired_false_positive_rate: float, max_value: int = 3, seed: typing.Optional[int] = None) -> None: ...
+    def add(self, item: Any) -> None:
+        """
+        Adds an item to the filter
+        :param item: item to add
+        :return: void
+        """
+    def add_bulk(self, items: typing.Iterable[Any]) -> None:
+        """
+        Add items in bulk to the filter
+        :param items: List of items
+        :return: void
+        """
+    def contains(self, item: Any) -> bool:
+        """
+        Looks up whether an item was added recently
+        :param item: lookup if the filter contains this item
+        :return: bool representing that the item is not contained or faded out (false) or maybe contained (true)
+        """
+    def clear(self) -> None:
+        """
+        Resets all cells
+        :return: void
+        """
+    def get_number_of_cells(self) -> int:
+        """
+        :return: int representing the number of cells
+        """
+    def get_number_of_hashes(self) -> int:
+        """
+        :return: int representing the number of cells every item sets
+        """
+    def get_max_value(self) -> int:
+        """
+        :return: int representing the value the cells of an item are set to
+        """
+    def get_decrements_per_insert(self) -> int:
+        """
+        :return: int representing the number of random cells decremented by every add
+        """
+    def stable_false_positive_rate(self) -> float:
+        """
+        :return: float representing the false positive rate the filter settles at (between 0 and 1)
+        """
+    def current_false_positive_rate(self) -> float:
+        """
+        Calculates the false positive rate from the cells that are currently non-zero
+        :return: float representing the current false positive rate (between 0 and 1)
+        """
+
+class CuckooFilter:
+    """
+    A cuckoo filter (Fan et al.): stores a short fingerprint of every item in one of two buckets. Supports removing
+    items, and below a false positive rate of about 3% it needs fewer bits per item than a Bloom filter.
+
+    :param expected_number_of_items: number of items the filter should hold
+    :param desired_false_positive_rate: the accepted false positive rate, a float between 0 and 1
+    :param fingerprint_bits: bits per fingerprint (1 to 32); derived from the false positive rate when left out
+    :param bucket_size: number of fingerprints per bucket (1 to 8)
+    :param max_kicks: number of fingerprints `add` relocates before it reports the filter as full
+    :param seed: seed for the random choice of fingerprints to relocate; makes the filter deterministic
+    """
+    def __init__(self, expected_number_of_items: int, desired_false_positive_rate: float, fingerprint_bits: typing.Optional[int] = None, bucket_size: int = 4, max_kicks: int = 500, seed: typing.Optional[int] = None) -> None: ...
+    def add(self, item: Any) -> None:
+        """
+        Adds an item to the filter; adding an item twice stores it twice
+        :param item: item to add
+        :return: void
+        :raises ValueError: when the filter is full; the filter is left unchanged
+        """
+    def add_bulk(self, items: typing.Iterable[Any]) -> None:
+        """
+        Add items in bulk to the filter
+        :param items: List of items
+        :return: void
+        :raises ValueError: when the filter is full
+        """
+    def remove(self, item: Any) -> bool:
+        """
+        Removes an item that was added before; removing an item that was never added may remove another item
+        :param item: item to remove
+        :return: bool representing whether the item was found and removed
+        """
+    def contains(self, item: Any) -> bool:
+        """
+        Looks up whether an item was added
+        :param item: lookup if the filter contains this item
+        :return: bool representing that the item is definitely not contained (false) or maybe contained (true)
+        """
+    def clear(self) -> None:
+        """
+        Removes all items
+        :return: void
+        """
+    def get_number_of_bits(self) -> int:
+        """
+        :return: int representing the number of bits in the filter
+        """
+    def get_number_of_buckets(self) -> int:
+        """
+        :return: int representing the number of buckets
+        """
+    def get_bucket_size(self) -> int:
+        """
+        :return: int representing the number of fingerprints per bucket
+        """
+    def get_fingerprint_bits(self) -> int:
+        """
+        :return: int representing the number of bits per fingerprint
+        """
+    def get_inserted_count(self) -> int:
+        """
+        :return: int representing the number of items in the filter
+        """
+    def get_capacity(self) -> int:
+        """
+        :return: int representing the number of fingerprint slots
+        """
+    def load_factor(self) -> float:
+        """
+        :return: float representing the fraction of slots that are in use (between 0 and 1)
+        """
+    def estimate_false_positive_rate(self) -> float:
+        """
+        :return: float representing the false positive rate once every slot is in use (between 0 and 1)
+        """
+    def to_bytes(self) -> bytes:
+        """
+        Serializes the filter
+        :return: bytes that `from_bytes` accepts
+        """
+    @staticmethod
+    def from_bytes(data: bytes) -> "CuckooFilter":
+        """
+        Deserializes a filter written by `to_bytes`
+        :param data: serialized filter
+        :return: CuckooFilter
+        :raises ValueError: when the data is not a valid serialized cuckoo filter
+        """
+    def save(self, path: str) -> None:
+        """
+        Writes the serialized filter to a file
+        :param path: file to write to
+        :return: void
+        """
+    @staticmethod
+    def load(path: str) -> "CuckooFilter":
+        """
+        Reads a filter written by `save`
+        :param path: file to read from
+        :return: CuckooFilter
+        :raises ValueError: when the file is not a valid serialized cuckoo filter
+        """
+    def __len__(self) -> int: ...
+
+class QuotientFilter:
+    """
+    A quotient filter (Bender et al.): stores a short fingerprint of every item in a compact hash table. Supports
+    removing items, doubling its size and merging two filters without needing the original items.
+
+    :param expected_number_of_items: number of items the filter should hold
+    :param desired_false_positive_rate: the accepted false positive rate, a float between 0 and 1
+    """
+    def __init__(self, expected_number_of_items: int, desired_false_positive_rate: float) -> None: ...
+    def add(self, item: Any) -> None:
+        """
+        Adds an item to the filter; adding an item twice stores it twice
+        :param item: item to add
+        :return: void
+        :raises ValueError: when every slot is in use; call `double` to make room
+        """
+    def add_bulk(self, items: typing.Iterable[Any]) -> None:
+        """
+        Add items in bulk to the filter
+        :param items: List of items
+        :return: void
+        :raises ValueError: when every slot is in use
+        """
+    def remove(self, item: Any) -> bool:
+        """
+        Removes an item that was added before; removing an item that was never added may remove another item
+        :param item: item to remove
+        :return: bool representing whether the item was found and removed
+        """
+    def contains(self, item: Any) -> bool:
+        """
+        Looks up whether an item was added
+        :param item: lookup if the filter contains this item
+        :return: bool representing that the item is definitely not contained (false) or maybe contained (true)
+        """
+    def clear(self) -> None:
+        """
+        Removes all items
+        :return: void
+        """
+    def double(self) -> None:
+        """
+        Doubles the number of slots; the false positive rate of the stored items stays the same
+        :return: void
+        :raises ValueError: when the remainders have a single bit left
+        """
+    def merge(self, other: "QuotientFilter") -> "QuotientFilter":
+        """
+        Creates a filter that contains the items of both filters
+        :param other: filter to merge with
+        :return: QuotientFilter
+        :raises ValueError: when the filters use a different fingerprint size or hash algorithm
+        """
+    def get_number_of_bits(self) -> int:
+        """
+        :return: int representing the number of bits in the filter, including three metadata bits per slot
+        """
+    def get_number_of_slots(self) -> int:
+        """
+        :return: int representing the number of slots
+        """
+    def get_quotient_bits(self) -> int:
+        """
+        :return: int representing the number of fingerprint bits that pick the slot
+        """
+    def get_remainder_bits(self) -> int:
+        """
+        :return: int representing the number of fingerprint bits stored in the slot
+        """
+    def get_inserted_count(self) -> int:
+        """
+        :return: int representing the number of items in the filter
+        """
+    def load_factor(self) -> float:
+        """
+        :return: float representing the fraction of slots that are in use (between 0 and 1)
+        """
+    def current_false_positive_rate(self) -> float:
+        """
+        :return: float representing the false positive rate for the items currently in the filter (between 0 and 1)
+        """
+    def to_bytes(self) -> bytes:
+        """
+        Serializes the filter
+        :return: bytes that `from_bytes` accepts
+        """
+    @staticmethod
+    def from_bytes(data: bytes) -> "QuotientFilter":
+        """
+        Deserializes a filter written by `to_bytes`
+        :param data: serialized filter
+        :return: QuotientFilter
+        :raises ValueError: when the data is not a valid serialized quotient filter
+        """
+    def save(self, path: str) -> None:
+        """
+        Writes the serialized filter to a file
+        :param path: file to write to
+        :return: void
+        """
+    @staticmethod
+    def load(path: str) -> "QuotientFilter":
+        """
+        Reads a filter written by `save`
+        :param path: file to read from
+        :return: QuotientFilter
+        :raises ValueError: when the file is not a valid serialized quotient filter
+        """
+    def __len__(self) -> int: ...
+
+class BinaryFuseFilter:
+    """
+    A binary fuse filter (Graf & Lemire): an immutable filter built once from all of its items. With 8-bit
+    fingerprints it needs about 9 bits per item for a false positive rate of 0.4%.
+
+    :param items: all items the filter should contain; duplicates are allowed
+    :param fingerprint_bits: 8, 16 or 32; the false positive rate is 1 / 2^fingerprint_bits
+    :param max_attempts: number of seeds to try before construction gives up
+    :raises ValueError: when the fingerprint size isn't supported or no seed works
+    """
+    def __init__(self, items: typing.Iterable[Any], fingerprint_bits: int = 8, max_attempts: int = 100) -> None: ...
+    def contains(self, item: Any) -> bool:
+        """
+        Looks up whether the filter was built with an item
+        :param item: lookup if the filter contains this item
+        :return: bool representing that the item is definitely not contained (false) or maybe contained (true)
+        """
+    def get_number_of_bits(self) -> int:
+        """
+        :return: int representing the number of bits in the filter
+        """
+    def get_fingerprint_bits(self) -> int:
+        """
+        :return: int representing the number of bits per fingerprint
+        """
+    def get_item_count(self) -> int:
+        """
+        :return: int representing the number of distinct items the filter was built from
+        """
+    def bits_per_item(self) -> float:
+        """
+        :return: float representing the number of bits the filter uses per distinct item
+        """
+    def estimate_false_positive_rate(self) -> float:
+        """
+        :return: float representing the false positive rate (between 0 and 1)
+        """
+    def to_bytes(self) -> bytes:
+        """
+        Serializes the filter
+        :return: bytes that `from_bytes` accepts
+        """
+    @staticmethod
+    def from_bytes(data: bytes) -> "BinaryFuseFilter":
+        """
+        Deserializes a filter written by `to_bytes`
+        :param data: serialized filter
+        :return: BinaryFuseFilter
+        :raises ValueError: when the data is not a valid serialized binary fuse filter
+        """
+    def save(self, path: str) -> None:
+        """
+        Writes the serialized filter to a file
+        :param path: file to write to
+        :return: void
+        """
+    @staticmethod
+    def load(path: str) -> "BinaryFuseFilter":
+        """
+        Reads a filter written by `save`
+        :param path: file to read from
+        :return: BinaryFuseFilter
+        :raises ValueError: when the file is not a valid serialized binary fuse filter
+        """
+    def __len__(self) -> int: ...
+
+class RibbonFilter:
+    """
+    A standard Ribbon filter (Dillinger & Walzer), as used by RocksDB: an immutable filter built once from all of its
+    items that needs only a few percent more space than the log2(1 / p) bits per item minimum.
+
+    :param items: all items the filter should contain; duplicates are allowed
+    :param desired_false_positive_rate: the accepted false positive rate, a float between 0 and 1; rounded down to a
+        power of 1/2
+    :raises ValueError: when the false positive rate is out of range or the filter cannot be built
+    """
+    def __init__(self, items: typing.Iterable[Any], desired_false_positive_rate: float) -> None: ...
+    def contains(self, item: Any) -> bool:
+        """
+        Looks up whether the filter was built with an item
+        :param item: lookup if the filter contains this item
+        :return: bool representing that the item is definitely not contained (false) or maybe contained (true)
+        """
+    def get_number_of_bits(self) -> int:
+        """
+        :return: int representing the number of bits in the filter
+        """
+    def get_number_of_slots(self) -> int:
+        """
+        :return: int representing the number of slots
+        """
+    def get_result_bits(self) -> int:
+        """
+        :return: int representing the number of bits per slot
+        """
+    def get_item_count(self) -> int:
+        """
+        :return: int representing the number of items the filter was built from
+        """
+    def bits_per_item(self) -> float:
+        """
+        :return: float representing the number of bits the filter uses per item
+        """
+    def estimate_false_positive_rate(self) -> float:
+        """
+        :return: float representing the false positive rate (between 0 and 1)
+        """
+    def to_bytes(self) -> bytes:
+        """
+        Serializes the filter
+        :return: bytes that `from_bytes` accepts
+        """
+    @staticmethod
+    def from_bytes(data: bytes) -> "RibbonFilter":
+        """
+        Deserializes a filter written by `to_bytes`
+        :param data: serialized filter
+        :return: RibbonFilter
+        :raises ValueError: when the data is not a valid serialized ribbon filter
+        """
+    def save(self, path: str) -> None:
+        """
+        Writes the serialized filter to a file
+        :param path: file to write to
+        :return: void
+        """
+    @staticmethod
+    def load(path: str) -> "RibbonFilter":
+        """
+        Reads a filter written by `save`
+        :param path: file to read from
+        :return: RibbonFilter
+        :raises ValueError: when the file is not a valid serialized ribbon filter
+        """
+    def __len__(self) -> int: ...
+
+class InvertibleBloomFilter:
+    """
+    An invertible Bloom lookup table (IBLT): subtracting the filter of one set from that of another cancels the keys
+    they share, after which the keys that differ can be listed, as long as there are at most about
+    `expected_difference` of them. Used to sync two sets by transferring only the difference.
+
+    :param expected_difference: number of keys that are in one set but not the other
+    :param number_of_hashes: number of cells every key is added to (2 to 8)
+    """
+    def __init__(self, expected_difference: int, number_of_hashes: int = 4) -> None: ...
+    def insert(self, key: bytes) -> None:
+        """
+        Inserts a key
+        :param key: key to insert
+        :return: void
+        """
+    def delete(self, key: bytes) -> None:
+        """
+        Deletes a key; deleting a key that was never inserted records it as a deleted entry
+        :param key: key to delete
+        :return: void
+        """
+    def subtract(self, other: "InvertibleBloomFilter") -> "InvertibleBloomFilter":
+        """
+        Subtracts the filter of the other set; keys in both sets cancel out
+        :param other: filter of the other set, created with the same parameters
+        :return: InvertibleBloomFilter holding the difference
+        :raises ValueError: when the filters have a different number of cells, hashes or hash algorithm
+        """
+    def list_entries(self) -> typing.Tuple[typing.List[bytes], typing.List[bytes]]:
+        """
+        Lists the entries; after `a.subtract(b)` these are the keys only in `a` and the keys only in `b`
+        :return: tuple of the inserted keys and the deleted keys
+        :raises ValueError: when the filter holds more entries than it was sized for
+        """
+    def is_empty(self) -> bool:
+        """
+        :return: bool representing whether the filter holds no entries, e.g. after subtracting an equal set
+        """
+    def clear(self) -> None:
+        """
+        Removes all entries
+        :return: void
+        """
+    def get_number_of_cells(self) -> int:
+        """
+        :return: int representing the number of cells
+        """
+    def get_number_of_hashes(self) -> int:
+        """
+        :return: int representing the number of cells every key is added to
+        """
+    def to_bytes(self) -> bytes:
+        """
+        Serializes the filter
+        :return: bytes that `from_bytes` accepts
+        """
+    @staticmethod
+    def from_bytes(data: bytes) -> "InvertibleBloomFilter":
+        """
+        Deserializes a filter written by `to_bytes`
+        :param data: serialized filter
+        :return: InvertibleBloomFilter
+        :raises ValueError: when the data is not a valid serialized invertible Bloom filter
+        """
+    def save(self, path: str) -> None:
+        """
+        Writes the serialized filter to a file
+        :param path: file to write to
+        :return: void
+        """
+    @staticmethod
+    def load(path: str) -> "InvertibleBloomFilter":
+        """
+        Reads a filter written by `save`
+        :param path: file to read from
+        :return: InvertibleBloomFilter
+        :raises ValueError: when the file is not a valid serialized invertible Bloom filter
+        """
+
+class BloomierFilter:
+    """
+    An immutable approximate map from items to small non-negative integers, e.g. the shard an item lives on.
+    Looking up an item the filter was built with returns its value; looking up any other item returns an arbitrary
+    value. The filter doesn't store the items, so it has no `in` and can't be iterated.
+    Usage: `shards = BloomierFilter({"user-1": 3, "user-2": 7})`, then `shards["user-1"] == 3`.
+
+    :param items: a mapping, or an iterable of (item, value) pairs, with the same item encoding as `BloomFilter`
+    :param value_bits: 8, 16 or 32; defaults to the smallest size that fits the largest value
+    :param max_attempts: number of seeds to try before construction gives up
+    :raises ValueError: when an item is given two different values, a value doesn't fit or construction fails
+    """
+    def __init__(self, items: typing.Union[typing.Mapping[typing.Any, int], typing.Iterable[typing.Tuple[typing.Any, int]]], value_bits: typing.Optional[int] = None, max_attempts: int = 100) -> None: ...
+    def __getitem__(self, item: typing.Any) -> int:
+        """
+        Looks up the value of an item; arbitrary for items the filter wasn't built with
+        :param item: item to look up
+        :return: int
+        """
+    def get(self, item: typing.Any) -> int:
+        """
+        Looks up the value of an item; the same as `bf[item]`
+        :param item: item to look up
+        :return: int
+        """
+    def __len__(self) -> int:
+        """
+        :return: int representing the number of distinct items the filter was built from
+        """
+    def get_number_of_bits(self) -> int:
+        """
+        :return: int representing the number of bits in the filter
+        """
+    def get_value_bits(self) -> int:
+        """
+        :return: int representing the number of bits per value
+        """
+    def get_item_count(self) -> int:
+        """
+        :return: int representing the number of distinct items the filter was built from
+        """
+    def bits_per_item(self) -> float:
+        """
+        :return: float representing the number of bits the filter uses per item
+        """
+    def to_bytes(self) -> bytes:
+        """
+        Serializes the filter
+        :return: bytes that `from_bytes` accepts
+        """
+    @staticmethod
+    def from_bytes(data: bytes) -> "BloomierFilter":
+        """
+        Deserializes a filter written by `to_bytes`
+        :param data: serialized filter
+        :return: BloomierFilter
+        :raises ValueError: when the data is not a valid serialized Bloomier filter
+        """
+    def save(self, path: str) -> None:
+        """
+        Writes the serialized filter to a file
+        :param path: file to write to
+        :return: void
+        """
+    @staticmethod
+    def load(path: str) -> "BloomierFilter":
+        """
+        Reads a filter written by `save`
+        :param path: file to read from
+        :return: BloomierFilter
+        :raises ValueError: when the file is not a valid serialized Bloomier filter
+        """
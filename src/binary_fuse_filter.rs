@@ -0,0 +1,623 @@
+//! Binary fuse filter in Rust (Graf & Lemire, "Binary Fuse Filters: Fast and Smaller Than Xor Filters", 2022)
+//! An immutable filter that is built once from all of its keys. Every key maps to three slots in three consecutive
+//! segments, and the slots are filled such that the XOR of a key's three fingerprints equals the key's own
+//! fingerprint. With 8-bit fingerprints this takes about 9 bits per key for a false positive rate of 0.4%.
+//! Finding such an assignment (peeling) can fail; construction then retries with another seed.
+
+use serde::Serialize;
+use std::ops::BitXor;
+use crate::error::BloomError;
+use crate::hashing::{splitmix64, AnyHasher, BloomHasher, HashAlgorithm};
+use crate::serialization::{self, ByteReader, ByteWriter};
+
+
+/// Magic number that every serialized BinaryFuseFilterRS starts with
+const MAGIC: &[u8; 4] = b"BLMB";
+/// Version of the binary format that `to_bytes` writes
+const FORMAT_VERSION: u16 = 1;
+/// Number of seeds `from_keys` tries before it gives up; a single attempt fails with a small probability
+pub const DEFAULT_MAX_ATTEMPTS: usize = 100;
+/// Largest segment; longer segments stop improving the space usage
+pub(crate) const MAX_SEGMENT_LENGTH: usize = 1 << 18;
+/// Number of slots, and segments, every key maps to
+pub(crate) const ARITY: usize = 3;
+
+
+/// A fingerprint type that a binary fuse filter can store
+pub trait FuseFingerprint: Copy + Default + PartialEq + BitXor<Output = Self> {
+    /// Number of bits in the fingerprint; the false positive rate is `1 / 2^BITS`
+    const BITS: usize;
+
+    /// Derives the fingerprint of a (seeded) key hash
+    fn from_hash(hash: u64) -> Self;
+
+    /// Appends the fingerprint to a serialized filter
+    fn write(self, writer: &mut ByteWriter);
+
+    /// Reads a fingerprint written by `write`
+    fn read(reader: &mut ByteReader) -> Result<Self, BloomError>;
+}
+
+impl FuseFingerprint for u8 {
+    const BITS: usize = 8;
+
+    fn from_hash(hash: u64) -> Self {
+        (hash ^ (hash >> 32)) as u8
+    }
+
+    fn write(self, writer: &mut ByteWriter) {
+        writer.write_u8(self);
+    }
+
+    fn read(reader: &mut ByteReader) -> Result<Self, BloomError> {
+        reader.read_u8()
+    }
+}
+
+impl FuseFingerprint for u16 {
+    const BITS: usize = 16;
+
+    fn from_hash(hash: u64) -> Self {
+        (hash ^ (hash >> 32)) as u16
+    }
+
+    fn write(self, writer: &mut ByteWriter) {
+        writer.write_u16(self);
+    }
+
+    fn read(reader: &mut ByteReader) -> Result<Self, BloomError> {
+        reader.read_u16()
+    }
+}
+
+impl FuseFingerprint for u32 {
+    const BITS: usize = 32;
+
+    fn from_hash(hash: u64) -> Self {
+        (hash ^ (hash >> 32)) as u32
+    }
+
+    fn write(self, writer: &mut ByteWriter) {
+        writer.write_bytes(&self.to_le_bytes());
+    }
+
+    fn read(reader: &mut ByteReader) -> Result<Self, BloomError> {
+        let bytes = reader.read_bytes(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+}
+
+
+/// Calculates the segment length, the number of slots the segments start in and the total number of slots for a
+/// number of keys, following the reference implementation
+/// Returns `(segment_length, segment_count_length, array_length)`
+///
+/// # Arguments
+/// * `number_of_keys` - number of distinct keys the filter is built from
+pub fn calc_fuse_layout(number_of_keys: usize) -> (usize, usize, usize) {
+    let segment_length = match number_of_keys {
+        0 => 4,
+        n => (1usize << ((n as f64).ln() / 3.33_f64.ln() + 2.25).floor() as u32).min(MAX_SEGMENT_LENGTH),
+    };
+    let capacity = match number_of_keys {
+        0 | 1 => 0,
+        n => {
+            let size_factor = (0.875 + 0.25 * 1_000_000_f64.ln() / (n as f64).ln()).max(1.125);
+            (n as f64 * size_factor).round() as usize
+        }
+    };
+    let segment_count = match capacity.div_ceil(segment_length) {
+        count if count < ARITY => 1,
+        count => count - (ARITY - 1),
+    };
+    let array_length = (segment_count + ARITY - 1) * segment_length;
+    (segment_length, segment_count * segment_length, array_length)
+}
+
+/// Finalizer of murmur3; spreads the seeded key hash over all 64 bits
+pub(crate) fn mix(hash: u64) -> u64 {
+    let mut hash = hash;
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xff51afd7ed558ccd);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xc4ceb9fe1a85ec53);
+    hash ^ (hash >> 33)
+}
+
+
+/// A Binary Fuse Filter; an immutable approximate set built from all of its keys at once
+#[derive(Clone, Debug, PartialEq)]
+pub struct BinaryFuseFilterRS<F: FuseFingerprint = u8> {
+    /// One fingerprint per slot; a key is contained when its three slots XOR to its fingerprint
+    fingerprints: Vec<F>,
+    /// Seed that the successful construction attempt mixed into every key hash
+    seed: u64,
+    /// Number of slots per segment; a power of two
+    segment_length: usize,
+    /// Number of slots in which the first of a key's three segments can start
+    segment_count_length: usize,
+    /// Number of distinct keys the filter was built from
+    item_count: usize,
+    /// The hash function that turns a key into the 64-bit hash the filter works on
+    hasher: AnyHasher,
+}
+
+impl<F: FuseFingerprint> BinaryFuseFilterRS<F> {
+    /// Builds a filter from keys that are serialized the same way `BloomFilterRS::add` does
+    /// Returns `BloomError::ConstructionFailed` if no seed out of `DEFAULT_MAX_ATTEMPTS` works
+    ///
+    /// # Arguments
+    /// * `keys` - all keys the filter should contain; duplicates are allowed
+    pub fn from_keys<T: Serialize>(keys: &[T]) -> Result<Self, BloomError> {
+        let hasher = AnyHasher::default();
+        let key_hashes = keys.iter().map(|key| hasher.hash_pair(&serialization::serialize(key)).0).collect();
+        BinaryFuseFilterRS::from_key_hashes(key_hashes, hasher, DEFAULT_MAX_ATTEMPTS)
+    }
+
+    /// Builds a filter from keys as bytes, the same bytes `BloomFilterRS::add_bytes` takes
+    /// Returns `BloomError::ConstructionFailed` if no seed out of `DEFAULT_MAX_ATTEMPTS` works
+    ///
+    /// # Arguments
+    /// * `keys` - all keys the filter should contain; duplicates are allowed
+    pub fn from_byte_keys<K: AsRef<[u8]>>(keys: &[K]) -> Result<Self, BloomError> {
+        BinaryFuseFilterRS::from_byte_keys_with_attempts(keys, DEFAULT_MAX_ATTEMPTS)
+    }
+
+    /// Builds a filter from keys as bytes, trying at most `max_attempts` seeds
+    /// Returns `BloomError::InvalidParameter` if `max_attempts` is 0 and `BloomError::ConstructionFailed` if no seed
+    /// works
+    ///
+    /// # Arguments
+    /// * `keys` - all keys the filter should contain; duplicates are allowed
+    /// * `max_attempts` - number of seeds to try before giving up
+    pub fn from_byte_keys_with_attempts<K: AsRef<[u8]>>(keys: &[K], max_attempts: usize) -> Result<Self, BloomError> {
+        let hasher = AnyHasher::default();
+        let key_hashes = keys.iter().map(|key| hasher.hash_pair(key.as_ref()).0).collect();
+        BinaryFuseFilterRS::from_key_hashes(key_hashes, hasher, max_attempts)
+    }
+
+    /// Checks if given bytes may be contained by the filter
+    /// Returns boolean: False means that the item definitely isn't contained.
+    /// True means that the item may be contained in the filter
+    ///
+    /// # Arguments
+    /// * `hash_bytes` - bytes to check for membership
+    pub fn contains_bytes(&self, hash_bytes: &[u8]) -> bool {
+        let hash = mix(self.hasher.hash_pair(hash_bytes).0.wrapping_add(self.seed));
+        let [h0, h1, h2] = self.positions(hash);
+        F::from_hash(hash) == self.fingerprints[h0] ^ self.fingerprints[h1] ^ self.fingerprints[h2]
+    }
+
+    /// Checks if a given item may be contained by the filter
+    ///
+    /// # Arguments
+    /// * `item` - item to be hashed and checked for membership
+    pub fn contains<T: Serialize>(&self, item: &T) -> bool {
+        self.contains_bytes(&serialization::serialize(item))
+    }
+
+    /// Calculates the false positive rate, `1 / 2^BITS`
+    pub fn estimate_false_positive_rate(&self) -> f64 {
+        0.5_f64.powi(F::BITS as i32)
+    }
+
+    /// Calculates the number of bits the filter uses per distinct key
+    pub fn bits_per_item(&self) -> f64 {
+        self.get_bit_count() as f64 / self.item_count.max(1) as f64
+    }
+
+    /// Retrieve the number of distinct keys the filter was built from
+    pub fn get_item_count(&self) -> usize {
+        self.item_count
+    }
+
+    /// Retrieve the number of bits per fingerprint
+    pub fn get_fingerprint_bits(&self) -> usize {
+        F::BITS
+    }
+
+    /// Retrieve the number of bits in the filter
+    pub fn get_bit_count(&self) -> usize {
+        self.fingerprints.len() * F::BITS
+    }
+
+    /// Serializes the filter to bytes: magic number, version, hash algorithm, fingerprint size, seed, layout and
+    /// the fingerprints
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = ByteWriter::with_capacity(40 + self.get_bit_count() / 8);
+        writer.write_bytes(MAGIC);
+        writer.write_u16(FORMAT_VERSION);
+        writer.write_u8(self.hasher.algorithm().id());
+        writer.write_u8(F::BITS as u8);
+        writer.write_u64(self.seed);
+        writer.write_u64(self.segment_length as u64);
+        writer.write_u64(self.segment_count_length as u64);
+        writer.write_u64(self.item_count as u64);
+        for fingerprint in &self.fingerprints {
+            fingerprint.write(&mut writer);
+        }
+        writer.into_bytes()
+    }
+
+    /// Deserializes a filter written by `to_bytes`
+    /// Returns an error if the payload is truncated, has trailing bytes, a header that doesn't match or a different
+    /// fingerprint size than `F`
+    ///
+    /// # Arguments
+    /// * `bytes` - serialized binary fuse filter
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BloomError> {
+        let mut reader = ByteReader::new(bytes);
+        if reader.read_bytes(MAGIC.len())? != MAGIC {
+            return Err(BloomError::InvalidMagic);
+        }
+        let version = reader.read_u16()?;
+        if version != FORMAT_VERSION {
+            return Err(BloomError::UnsupportedVersion(version));
+        }
+        let hash_algorithm = HashAlgorithm::from_id(reader.read_u8()?)?;
+        let fingerprint_bits = reader.read_u8()? as usize;
+        if fingerprint_bits != F::BITS {
+            return Err(BloomError::InvalidPayload(format!(
+                "payload has {}-bit fingerprints, expected {}", fingerprint_bits, F::BITS
+            )));
+        }
+        let seed = reader.read_u64()?;
+        let segment_length = reader.read_usize()?;
+        let segment_count_length = reader.read_usize()?;
+        let item_count = reader.read_usize()?;
+        if !segment_length.is_power_of_two() || segment_length > MAX_SEGMENT_LENGTH {
+            return Err(BloomError::InvalidPayload(format!("invalid segment length {}", segment_length)));
+        }
+        if segment_count_length == 0 || !segment_count_length.is_multiple_of(segment_length) {
+            return Err(BloomError::InvalidPayload(format!(
+                "segments start in {} slots, which is not a multiple of the segment length", segment_count_length
+            )));
+        }
+        let array_length = segment_count_length + (ARITY - 1) * segment_length;
+        let num_of_bytes = array_length.saturating_mul(F::BITS / 8);
+        if num_of_bytes > reader.remaining() {
+            return Err(BloomError::Truncated { expected: num_of_bytes, actual: reader.remaining() });
+        }
+        let fingerprints = (0..array_length).map(|_| F::read(&mut reader)).collect::<Result<Vec<F>, BloomError>>()?;
+        reader.finish()?;
+        Ok(BinaryFuseFilterRS {
+            fingerprints,
+            seed,
+            segment_length,
+            segment_count_length,
+            item_count,
+            hasher: AnyHasher::from(hash_algorithm),
+        })
+    }
+
+    /// Builds the filter from 64-bit key hashes, retrying with a new seed whenever peeling fails
+    fn from_key_hashes(mut key_hashes: Vec<u64>, hasher: AnyHasher, max_attempts: usize) -> Result<Self, BloomError> {
+        if max_attempts == 0 {
+            return Err(BloomError::InvalidParameter("max attempts must be at least 1".to_string()));
+        }
+        // equal keys hash equally under every seed and would never peel
+        key_hashes.sort_unstable();
+        key_hashes.dedup();
+        let (segment_length, segment_count_length, array_length) = calc_fuse_layout(key_hashes.len());
+        let mut fuse_filter = BinaryFuseFilterRS {
+            fingerprints: vec![F::default(); array_length],
+            seed: 0,
+            segment_length,
+            segment_count_length,
+            item_count: key_hashes.len(),
+            hasher,
+        };
+
+        let mut peeler = FusePeeler::new(array_length, key_hashes.len());
+        let mut random_state: u64 = 0x726b2b9d438b9d4d;
+        for _ in 0..max_attempts {
+            fuse_filter.seed = splitmix64(&mut random_state);
+            let Some(peeled) = peeler.peel(&key_hashes, fuse_filter.seed, segment_length, segment_count_length) else {
+                continue;
+            };
+
+            // assign in reverse peeling order: a key's free slot is written after its other two are final
+            for (hash, position) in peeled.iter().rev() {
+                let positions = fuse_filter.positions(*hash);
+                let fingerprint = F::from_hash(*hash)
+                    ^ fuse_filter.fingerprints[positions[(position + 1) % ARITY]]
+                    ^ fuse_filter.fingerprints[positions[(position + 2) % ARITY]];
+                fuse_filter.fingerprints[positions[*position]] = fingerprint;
+            }
+            return Ok(fuse_filter);
+        }
+        Err(BloomError::ConstructionFailed { attempts: max_attempts })
+    }
+
+    /// The three slots of a seeded key hash, one in each of three consecutive segments
+    fn positions(&self, hash: u64) -> [usize; ARITY] {
+        fuse_positions(hash, self.segment_length, self.segment_count_length)
+    }
+}
+
+
+/// The three slots of a seeded key hash, one in each of three consecutive segments
+///
+/// # Arguments
+/// * `hash` - seeded key hash, see `mix`
+/// * `segment_length` - number of slots per segment; a power of two
+/// * `segment_count_length` - number of slots in which the first segment can start
+pub(crate) fn fuse_positions(hash: u64, segment_length: usize, segment_count_length: usize) -> [usize; ARITY] {
+    let first = ((hash as u128 * segment_count_length as u128) >> 64) as usize;
+    let segment_mask = segment_length - 1;
+    [
+        first,
+        (first + segment_length) ^ ((hash >> 18) as usize & segment_mask),
+        (first + 2 * segment_length) ^ (hash as usize & segment_mask),
+    ]
+}
+
+/// Scratch space for peeling keys out of a fuse layout; reused across construction attempts
+pub(crate) struct FusePeeler {
+    /// Per slot: 4 * number of keys | XOR of the key's position (0, 1 or 2)
+    slot_counts: Vec<u8>,
+    /// Per slot: XOR of the seeded hashes of the keys in the slot
+    slot_hashes: Vec<u64>,
+    /// Slots that hold a single key
+    alone: Vec<usize>,
+    /// Seeded key hashes in peeling order, with the position of the slot that is free to satisfy the key
+    peeled: Vec<(u64, usize)>,
+}
+
+impl FusePeeler {
+    /// Allocates scratch space for `number_of_keys` keys in `array_length` slots
+    pub(crate) fn new(array_length: usize, number_of_keys: usize) -> Self {
+        FusePeeler {
+            slot_counts: vec![0u8; array_length],
+            slot_hashes: vec![0u64; array_length],
+            alone: Vec::with_capacity(array_length),
+            peeled: Vec::with_capacity(number_of_keys),
+        }
+    }
+
+    /// Tries to peel all keys with a seed
+    /// Returns the seeded key hashes with their free slot position in peeling order, or `None` if peeling fails;
+    /// assigning slots in reverse order satisfies every key
+    ///
+    /// # Arguments
+    /// * `key_hashes` - distinct, unseeded 64-bit key hashes
+    /// * `seed` - seed to mix into every key hash
+    /// * `segment_length` - number of slots per segment; a power of two
+    /// * `segment_count_length` - number of slots in which the first segment can start
+    pub(crate) fn peel(&mut self, key_hashes: &[u64], seed: u64, segment_length: usize, segment_count_length: usize) -> Option<&[(u64, usize)]> {
+        self.slot_counts.fill(0);
+        self.slot_hashes.fill(0);
+        self.peeled.clear();
+
+        let mut overflow = false;
+        for key_hash in key_hashes {
+            let hash = mix(key_hash.wrapping_add(seed));
+            for (position, slot) in fuse_positions(hash, segment_length, segment_count_length).into_iter().enumerate() {
+                self.slot_counts[slot] = self.slot_counts[slot].wrapping_add(4) ^ position as u8;
+                self.slot_hashes[slot] ^= hash;
+                overflow |= self.slot_counts[slot] < 4;
+            }
+        }
+        if overflow {
+            return None;
+        }
+
+        // repeatedly remove a key that is alone in one of its slots; that slot is then free to satisfy it
+        self.alone.clear();
+        self.alone.extend((0..self.slot_counts.len()).filter(|slot| self.slot_counts[*slot] >> 2 == 1));
+        while let Some(slot) = self.alone.pop() {
+            if self.slot_counts[slot] >> 2 != 1 {
+                continue;
+            }
+            let hash = self.slot_hashes[slot];
+            let position = (self.slot_counts[slot] & 3) as usize;
+            self.peeled.push((hash, position));
+            let positions = fuse_positions(hash, segment_length, segment_count_length);
+            for other_position in [(position + 1) % ARITY, (position + 2) % ARITY] {
+                let other_slot = positions[other_position];
+                self.slot_counts[other_slot] = (self.slot_counts[other_slot] - 4) ^ other_position as u8;
+                self.slot_hashes[other_slot] ^= hash;
+                if self.slot_counts[other_slot] >> 2 == 1 {
+                    self.alone.push(other_slot);
+                }
+            }
+            self.slot_counts[slot] = 0;
+        }
+        if self.peeled.len() < key_hashes.len() {
+            return None;
+        }
+        Some(&self.peeled)
+    }
+}
+
+
+/// A binary fuse filter with its fingerprint size chosen at runtime; this is what the Python wrapper holds
+#[derive(Clone, Debug, PartialEq)]
+pub enum AnyBinaryFuseFilter {
+    Fuse8(BinaryFuseFilterRS<u8>),
+    Fuse16(BinaryFuseFilterRS<u16>),
+    Fuse32(BinaryFuseFilterRS<u32>),
+}
+
+/// Forwards a call to the filter inside an `AnyBinaryFuseFilter`
+macro_rules! dispatch {
+    ($any_filter:expr, $filter:ident => $call:expr) => {
+        match $any_filter {
+            AnyBinaryFuseFilter::Fuse8($filter) => $call,
+            AnyBinaryFuseFilter::Fuse16($filter) => $call,
+            AnyBinaryFuseFilter::Fuse32($filter) => $call,
+        }
+    };
+}
+
+impl AnyBinaryFuseFilter {
+    /// Builds a filter from keys as bytes with 8-, 16- or 32-bit fingerprints
+    /// Returns an error if the fingerprint size isn't supported or construction fails
+    ///
+    /// # Arguments
+    /// * `keys` - all keys the filter should contain; duplicates are allowed
+    /// * `fingerprint_bits` - 8, 16 or 32
+    /// * `max_attempts` - number of seeds to try before giving up
+    pub fn from_byte_keys<K: AsRef<[u8]>>(keys: &[K], fingerprint_bits: usize, max_attempts: usize) -> Result<Self, BloomError> {
+        match fingerprint_bits {
+            8 => Ok(AnyBinaryFuseFilter::Fuse8(BinaryFuseFilterRS::from_byte_keys_with_attempts(keys, max_attempts)?)),
+            16 => Ok(AnyBinaryFuseFilter::Fuse16(BinaryFuseFilterRS::from_byte_keys_with_attempts(keys, max_attempts)?)),
+            32 => Ok(AnyBinaryFuseFilter::Fuse32(BinaryFuseFilterRS::from_byte_keys_with_attempts(keys, max_attempts)?)),
+            _ => Err(BloomError::InvalidParameter(format!("fingerprint bits must be 8, 16 or 32, got {}", fingerprint_bits))),
+        }
+    }
+
+    /// Checks if given bytes may be contained by the filter
+    ///
+    /// # Arguments
+    /// * `hash_bytes` - bytes to check for membership
+    pub fn contains_bytes(&self, hash_bytes: &[u8]) -> bool {
+        dispatch!(self, filter => filter.contains_bytes(hash_bytes))
+    }
+
+    /// Calculates the false positive rate, `1 / 2^fingerprint_bits`
+    pub fn estimate_false_positive_rate(&self) -> f64 {
+        dispatch!(self, filter => filter.estimate_false_positive_rate())
+    }
+
+    /// Calculates the number of bits the filter uses per distinct key
+    pub fn bits_per_item(&self) -> f64 {
+        dispatch!(self, filter => filter.bits_per_item())
+    }
+
+    /// Retrieve the number of distinct keys the filter was built from
+    pub fn get_item_count(&self) -> usize {
+        dispatch!(self, filter => filter.get_item_count())
+    }
+
+    /// Retrieve the number of bits per fingerprint
+    pub fn get_fingerprint_bits(&self) -> usize {
+        dispatch!(self, filter => filter.get_fingerprint_bits())
+    }
+
+    /// Retrieve the number of bits in the filter
+    pub fn get_bit_count(&self) -> usize {
+        dispatch!(self, filter => filter.get_bit_count())
+    }
+
+    /// Serializes the filter; see `BinaryFuseFilterRS::to_bytes`
+    pub fn to_bytes(&self) -> Vec<u8> {
+        dispatch!(self, filter => filter.to_bytes())
+    }
+
+    /// Deserializes a filter of any fingerprint size written by `BinaryFuseFilterRS::to_bytes`
+    ///
+    /// # Arguments
+    /// * `bytes` - serialized binary fuse filter
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BloomError> {
+        match bytes.get(7) {
+            Some(16) => Ok(AnyBinaryFuseFilter::Fuse16(BinaryFuseFilterRS::from_bytes(bytes)?)),
+            Some(32) => Ok(AnyBinaryFuseFilter::Fuse32(BinaryFuseFilterRS::from_bytes(bytes)?)),
+            _ => Ok(AnyBinaryFuseFilter::Fuse8(BinaryFuseFilterRS::from_bytes(bytes)?)),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_contains_every_key() {
+        let keys: Vec<u64> = (0..10_000).collect();
+        let fuse_filter = BinaryFuseFilterRS::<u8>::from_keys(&keys).unwrap();
+        assert!(keys.iter().all(|key| fuse_filter.contains(key)), "Binary fuse filter should have no false negatives");
+        assert_eq!(fuse_filter.get_item_count(), 10_000);
+    }
+
+    #[test]
+    fn test_false_positive_rate_and_size() {
+        let keys: Vec<u64> = (0..100_000).collect();
+        let fuse_filter = BinaryFuseFilterRS::<u8>::from_keys(&keys).unwrap();
+        let false_positives = (100_000..1_100_000u64).filter(|key| fuse_filter.contains(key)).count();
+        let false_positive_rate = false_positives as f64 / 1_000_000.0;
+        assert!((false_positive_rate - 1.0 / 256.0).abs() < 0.001, "false positive rate {}", false_positive_rate);
+        assert!(fuse_filter.bits_per_item() < 9.6, "{} bits per item", fuse_filter.bits_per_item());
+    }
+
+    #[test]
+    fn test_wider_fingerprints() {
+        let keys: Vec<u64> = (0..10_000).collect();
+        let fuse_16 = BinaryFuseFilterRS::<u16>::from_keys(&keys).unwrap();
+        let fuse_32 = BinaryFuseFilterRS::<u32>::from_keys(&keys).unwrap();
+        assert!(keys.iter().all(|key| fuse_16.contains(key) && fuse_32.contains(key)));
+        assert!((10_000..110_000u64).filter(|key| fuse_16.contains(key)).count() < 10);
+        assert_eq!((10_000..110_000u64).filter(|key| fuse_32.contains(key)).count(), 0);
+        assert_eq!(fuse_32.get_bit_count(), 2 * fuse_16.get_bit_count());
+    }
+
+    #[test]
+    fn test_duplicates_and_small_key_sets() {
+        let fuse_filter = BinaryFuseFilterRS::<u8>::from_keys(&[1, 2, 2, 3, 3, 3]).unwrap();
+        assert_eq!(fuse_filter.get_item_count(), 3);
+        assert!([1, 2, 3].iter().all(|key| fuse_filter.contains(key)));
+        for size in 0..20u64 {
+            let keys: Vec<u64> = (0..size).collect();
+            let fuse_filter = BinaryFuseFilterRS::<u16>::from_keys(&keys).unwrap();
+            assert!(keys.iter().all(|key| fuse_filter.contains(key)));
+        }
+        assert!(!BinaryFuseFilterRS::<u32>::from_keys::<u64>(&[]).unwrap().contains(&1));
+    }
+
+    #[test]
+    fn test_retries_failed_attempts() {
+        // find a key set whose first seed fails to peel; more attempts must then succeed
+        let failing_keys = (0..1_000u64)
+            .map(|offset| (offset * 100..offset * 100 + 100).map(|key| key.to_le_bytes()).collect::<Vec<_>>())
+            .find(|keys| BinaryFuseFilterRS::<u8>::from_byte_keys_with_attempts(keys, 1).is_err())
+            .expect("some key set fails on its first attempt");
+        assert_eq!(
+            BinaryFuseFilterRS::<u8>::from_byte_keys_with_attempts(&failing_keys, 1),
+            Err(BloomError::ConstructionFailed { attempts: 1 })
+        );
+        let fuse_filter = BinaryFuseFilterRS::<u8>::from_byte_keys(&failing_keys).unwrap();
+        assert!(failing_keys.iter().all(|key| fuse_filter.contains_bytes(key)));
+        assert!(BinaryFuseFilterRS::<u8>::from_byte_keys_with_attempts(&failing_keys, 0).is_err());
+    }
+
+    #[test]
+    fn test_layout() {
+        for size in [0, 1, 2, 10, 1_000, 1_000_000] {
+            let (segment_length, segment_count_length, array_length) = calc_fuse_layout(size);
+            assert!(segment_length.is_power_of_two());
+            assert!(segment_count_length.is_multiple_of(segment_length));
+            assert_eq!(array_length, segment_count_length + 2 * segment_length);
+            assert!(array_length >= size);
+        }
+        // the overhead shrinks towards 12.5% for large key sets, about 9 bits per key with 8-bit fingerprints
+        let (_, _, array_length) = calc_fuse_layout(10_000_000);
+        assert!(array_length as f64 / 10_000_000.0 < 1.14);
+    }
+
+    #[test]
+    fn test_serialization_roundtrip() {
+        let keys: Vec<u64> = (0..1_000).collect();
+        let fuse_filter = BinaryFuseFilterRS::<u16>::from_keys(&keys).unwrap();
+        let bytes = fuse_filter.to_bytes();
+        let restored = BinaryFuseFilterRS::<u16>::from_bytes(&bytes).unwrap();
+        assert_eq!(restored, fuse_filter);
+        assert!(keys.iter().all(|key| restored.contains(key)));
+        assert_eq!(AnyBinaryFuseFilter::from_bytes(&bytes).unwrap(), AnyBinaryFuseFilter::Fuse16(fuse_filter));
+    }
+
+    #[test]
+    fn test_rejects_invalid_payloads() {
+        let bytes = BinaryFuseFilterRS::<u8>::from_keys(&[1, 2, 3]).unwrap().to_bytes();
+        assert_eq!(BinaryFuseFilterRS::<u8>::from_bytes(b"BLMQ").err(), Some(BloomError::InvalidMagic));
+        assert!(matches!(BinaryFuseFilterRS::<u8>::from_bytes(&bytes[..bytes.len() - 1]), Err(BloomError::Truncated { .. })));
+        assert!(matches!(BinaryFuseFilterRS::<u16>::from_bytes(&bytes), Err(BloomError::InvalidPayload(_))));
+        let mut wrong_segment = bytes.clone();
+        wrong_segment[16..24].copy_from_slice(&3u64.to_le_bytes());
+        assert!(matches!(BinaryFuseFilterRS::<u8>::from_bytes(&wrong_segment), Err(BloomError::InvalidPayload(_))));
+        let mut huge_layout = bytes.clone();
+        huge_layout[24..32].copy_from_slice(&(1u64 << 40).to_le_bytes());
+        assert!(BinaryFuseFilterRS::<u8>::from_bytes(&huge_layout).is_err());
+        assert!(AnyBinaryFuseFilter::from_byte_keys(&[b"a"], 12, 10).is_err());
+    }
+}
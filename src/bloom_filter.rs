@@ -1,13 +1,26 @@
 //! BloomFilter implementation in Rust
 
-use std::io::Cursor;
-
-use serde::{Serialize, Deserialize};
+use serde::Serialize;
 use std::f64::consts::LN_2;
 use std::hash::{Hash};
-use murmur3;
+use std::sync::Arc;
 use bitvec::prelude::*;
-use crate::serialization;
+use crate::error::BloomError;
+use crate::hashing::{self, AnyHasher, BloomHasher, HashAlgorithm, IndexMode, SipHash13Hasher};
+use crate::serialization::{self, ByteReader, ByteWriter};
+
+
+/// Magic number that every serialized BloomFilterRS starts with
+const MAGIC: &[u8; 4] = b"BLMF";
+/// Version of the binary format that `to_bytes` writes; `from_bytes` also reads versions 1 to 3
+const FORMAT_VERSION: u16 = 4;
+/// Size of the header that precedes the packed bit array (in bytes), without a stored key
+const HEADER_SIZE: usize = 43;
+/// Size of a stored hash key (in bytes)
+const KEY_SIZE: usize = 16;
+/// Fill ratio from which `is_saturated` reports a filter as overloaded by default; a filter holding its expected
+/// number of items has about half of its bits set
+pub const DEFAULT_SATURATION_THRESHOLD: f64 = 0.6;
 
 
 /// Calculates optimal number of bits to use for the bloom filter
@@ -28,7 +41,7 @@ use crate::serialization;
 /// //let person = Person::new("name");
 /// ```
 pub fn calc_optimal_number_of_bits(expected_number_of_items: usize, desired_false_pos_rate:f64) -> usize {
-    let num = -1.0_f64 * expected_number_of_items as f64 * desired_false_pos_rate.ln();
+    let num = -(expected_number_of_items as f64) * desired_false_pos_rate.ln();
     let denominator = 2.0_f64.ln().powf(2.0);
     (num / denominator).ceil() as usize
 }
@@ -57,54 +70,351 @@ pub fn calculate_optimal_number_of_hashes(bit_array_size:usize, expected_number_
 
 
 
+/// Estimates the number of items in a filter from its number of set bits
+/// This is calculated by `n* = -(m / k) * ln(1 - X / m)`
+///
+/// # Arguments
+/// * `set_bits` - number of bits that are set (X)
+/// * `num_of_bits` - total number of bits (m)
+/// * `count_of_hashes` - number of bits set per item (k)
+pub fn cardinality_from_set_bits(set_bits: usize, num_of_bits: usize, count_of_hashes: usize) -> f64 {
+    let m = num_of_bits as f64;
+    let k = count_of_hashes as f64;
+    let x = set_bits as f64;
+    -(m / k) * (1.0 - x / m).ln()
+}
+
+/// Checks that `hasher` reaches every one of `num_of_bits` bits; bits it can't reach would never be set
+fn check_addressable<H: BloomHasher>(hasher: &H, num_of_bits: usize) -> Result<(), BloomError> {
+    if num_of_bits > hasher.max_slots() {
+        return Err(BloomError::InvalidParameter(format!(
+            "{} can address at most {} bits, the filter needs {}", hasher.algorithm().name(), hasher.max_slots(), num_of_bits
+        )));
+    }
+    Ok(())
+}
+
+
+
+/// What happens when an insert pushes a Bloom Filter past the number of items it was sized for
+#[derive(Clone)]
+pub enum OverflowPolicy {
+    /// Keep inserting; the false positive rate rises above the design rate
+    Ignore,
+    /// Keep inserting, but call the callback once, on the first insert while the filter is at or beyond its
+    /// capacity, with the inserted count and the capacity
+    Warn(Arc<dyn Fn(usize, usize) + Send + Sync>),
+    /// Refuse the insert and return `BloomError::CapacityExceeded`
+    Error,
+}
+
+impl OverflowPolicy {
+    /// Id of the policy in the binary format; the callback of `Warn` is not serialized
+    fn id(&self) -> u8 {
+        match self {
+            OverflowPolicy::Ignore => 0,
+            OverflowPolicy::Warn(_) => 1,
+            OverflowPolicy::Error => 2,
+        }
+    }
+
+    /// Policy for an id written by `id`; `Warn` gets a callback that does nothing until the caller sets its own
+    /// with `set_overflow_policy`
+    fn from_id(id: u8) -> Result<Self, BloomError> {
+        match id {
+            0 => Ok(OverflowPolicy::Ignore),
+            1 => Ok(OverflowPolicy::Warn(Arc::new(|_, _| {}))),
+            2 => Ok(OverflowPolicy::Error),
+            other => Err(BloomError::InvalidPayload(format!("unknown overflow policy {}", other))),
+        }
+    }
+}
+
+/// Which inserts count towards the inserted count of a Bloom Filter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InsertionCounting {
+    /// Every call to `add`/`add_bytes`
+    EveryInsert,
+    /// Only inserts that set at least one bit that wasn't set yet; repeated items aren't counted
+    NewItemsOnly,
+}
+
+impl InsertionCounting {
+    /// Id of the insertion counting in the binary format
+    fn id(&self) -> u8 {
+        match self {
+            InsertionCounting::EveryInsert => 0,
+            InsertionCounting::NewItemsOnly => 1,
+        }
+    }
+
+    /// Insertion counting for an id written by `id`
+    fn from_id(id: u8) -> Result<Self, BloomError> {
+        match id {
+            0 => Ok(InsertionCounting::EveryInsert),
+            1 => Ok(InsertionCounting::NewItemsOnly),
+            other => Err(BloomError::InvalidPayload(format!("unknown insertion counting {}", other))),
+        }
+    }
+}
+
+
+/// Whether `to_bytes` writes the key of a keyed hasher
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KeyPersistence {
+    /// Leave the key out; only whoever knows the key can load the filter again
+    #[default]
+    Omit,
+    /// Write the key, in plain text, after the header
+    Store,
+}
+
+/// What the serialized header says about the hash key
+enum StoredKey {
+    Unkeyed,
+    Omitted,
+    Stored([u8; KEY_SIZE]),
+}
+
+impl StoredKey {
+    /// Returns the id under which this state is stored in a serialized filter
+    fn id(&self) -> u8 {
+        match self {
+            StoredKey::Unkeyed => 0,
+            StoredKey::Omitted => 1,
+            StoredKey::Stored(_) => 2,
+        }
+    }
+}
+
+
 /// A struct representing a BloomFilter
-// #[derive(Serialize, Deserialize)]
-pub struct BloomFilterRS {
+/// Generic over the hash function; the default `AnyHasher` picks one of the built-in hashers at runtime
+#[derive(Clone)]
+pub struct BloomFilterRS<H: BloomHasher = AnyHasher> {
     /// Memory size; number of bits; array
     bit_array: BitVec,
     /// The number of time an item should be hashed with different types of hash functions or seeds
     count_of_hashes: usize,
     /// The expected number of items this Bloom Filter should hold
     expected_n_items:usize,
+    /// The hash function used to derive the bit indices
+    hasher: H,
+    /// Whether the item is hashed once per index or once in total
+    index_mode: IndexMode,
+    /// The number of inserts so far, counted according to `insertion_counting`
+    inserted_count: usize,
+    /// Which inserts count towards `inserted_count`
+    insertion_counting: InsertionCounting,
+    /// What to do when `inserted_count` exceeds `expected_n_items`
+    overflow_policy: OverflowPolicy,
+    /// Whether `OverflowPolicy::Warn` called back since the filter last went over its capacity
+    overflow_warned: bool,
+    /// Whether the key of a keyed hasher is serialized
+    key_persistence: KeyPersistence,
 }
 
-impl BloomFilterRS {
+impl BloomFilterRS<AnyHasher> {
     pub fn new(expected_number_of_items: usize, desired_false_positive_rate: f64) -> Self {
+        BloomFilterRS::with_index_mode(expected_number_of_items, desired_false_positive_rate, IndexMode::default())
+    }
+
+    /// Creates a Bloom Filter sized like `new` that derives its indices in the given mode
+    /// `IndexMode::DoubleHashing` hashes every item once instead of once per index, which makes inserts and
+    /// lookups considerably faster for larger `k` or longer items
+    ///
+    /// # Arguments
+    /// * `expected_number_of_items` - Estimated number of items that the BloomFilter should accommodate
+    /// * `desired_false_positive_rate` - Desired/accepted false positive rate
+    /// * `index_mode` - how the indices of an item are derived from its hash
+    pub fn with_index_mode(expected_number_of_items: usize, desired_false_positive_rate: f64, index_mode: IndexMode) -> Self {
+        BloomFilterRS::with_hasher(expected_number_of_items, desired_false_positive_rate, index_mode, AnyHasher::default())
+            .expect("the default hasher addresses any number of bits")
+    }
+
+    /// Creates a Bloom Filter sized like `new` that hashes with one of the built-in algorithms
+    /// Returns an error if the algorithm can't address every bit of the filter (see `BloomHasher::max_slots`)
+    ///
+    /// # Arguments
+    /// * `expected_number_of_items` - Estimated number of items that the BloomFilter should accommodate
+    /// * `desired_false_positive_rate` - Desired/accepted false positive rate
+    /// * `index_mode` - how the indices of an item are derived from its hash
+    /// * `hash_algorithm` - algorithm to hash with; SipHash-1-3 gets an all-zero key
+    pub fn with_hash_algorithm(expected_number_of_items: usize, desired_false_positive_rate: f64, index_mode: IndexMode, hash_algorithm: HashAlgorithm) -> Result<Self, BloomError> {
+        BloomFilterRS::with_hasher(expected_number_of_items, desired_false_positive_rate, index_mode, AnyHasher::from(hash_algorithm))
+    }
+
+    /// Creates a Bloom Filter sized like `new` that hashes with SipHash-1-3 under a secret key
+    /// Without the key, nobody can craft items that set chosen bits. The key is not serialized unless
+    /// `set_key_persistence(KeyPersistence::Store)` is called
+    ///
+    /// # Arguments
+    /// * `expected_number_of_items` - Estimated number of items that the BloomFilter should accommodate
+    /// * `desired_false_positive_rate` - Desired/accepted false positive rate
+    /// * `index_mode` - how the indices of an item are derived from its hash
+    /// * `key` - secret 128 bit key; should come from a cryptographically secure source
+    pub fn with_key(expected_number_of_items: usize, desired_false_positive_rate: f64, index_mode: IndexMode, key: [u8; KEY_SIZE]) -> Self {
+        BloomFilterRS::with_hasher(expected_number_of_items, desired_false_positive_rate, index_mode, AnyHasher::SipHash13(SipHash13Hasher::new(key)))
+            .expect("SipHash-1-3 addresses any number of bits")
+    }
+
+    /// Deserializes a Bloom Filter written by `to_bytes`, hashing with the algorithm named in its header
+    /// Version 1 payloads don't store the inserted count; it is estimated from the set bits
+    /// Returns an error if the payload is truncated, has trailing bytes or a header that doesn't match,
+    /// or `BloomError::MissingKey` if it was written without its key
+    ///
+    /// # Arguments
+    /// * `bytes` - serialized Bloom Filter
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BloomError> {
+        BloomFilterRS::decode(bytes, |hash_algorithm, stored_key| match (hash_algorithm, stored_key) {
+            (HashAlgorithm::SipHash13, Some(key)) => Ok(AnyHasher::SipHash13(SipHash13Hasher::new(key))),
+            _ => Ok(AnyHasher::from(hash_algorithm)),
+        })
+    }
+
+    /// Deserializes a keyed Bloom Filter written by `to_bytes`
+    /// Returns an error if the payload isn't keyed or stores a different key, or see `from_bytes`
+    ///
+    /// # Arguments
+    /// * `bytes` - serialized Bloom Filter
+    /// * `key` - key the filter was built with
+    pub fn from_bytes_with_key(bytes: &[u8], key: [u8; KEY_SIZE]) -> Result<Self, BloomError> {
+        BloomFilterRS::decode(bytes, |hash_algorithm, _| match hash_algorithm {
+            HashAlgorithm::SipHash13 => Ok(AnyHasher::SipHash13(SipHash13Hasher::new(key))),
+            _ => Err(BloomError::Incompatible(format!("payload was hashed with {:?}, which takes no key", hash_algorithm))),
+        })
+    }
+}
+
+impl<H: BloomHasher> BloomFilterRS<H> {
+    /// Creates a Bloom Filter sized like `new` that hashes with the given hasher
+    /// Returns an error if the hasher can't address every bit of the filter (see `BloomHasher::max_slots`)
+    ///
+    /// # Arguments
+    /// * `expected_number_of_items` - Estimated number of items that the BloomFilter should accommodate
+    /// * `desired_false_positive_rate` - Desired/accepted false positive rate
+    /// * `index_mode` - how the indices of an item are derived from its hash
+    /// * `hasher` - hash function to derive the indices with
+    pub fn with_hasher(expected_number_of_items: usize, desired_false_positive_rate: f64, index_mode: IndexMode, hasher: H) -> Result<Self, BloomError> {
         let num_of_bits = calc_optimal_number_of_bits(expected_number_of_items, desired_false_positive_rate);
         let num_of_hashes = calculate_optimal_number_of_hashes(num_of_bits, expected_number_of_items);
+        check_addressable(&hasher, num_of_bits)?;
 
-        BloomFilterRS {
+        Ok(BloomFilterRS {
             bit_array: BitVec::repeat(false, num_of_bits),
             count_of_hashes: num_of_hashes,
-            expected_n_items: expected_number_of_items
-        }
+            expected_n_items: expected_number_of_items,
+            hasher,
+            index_mode,
+            inserted_count: 0,
+            insertion_counting: InsertionCounting::EveryInsert,
+            overflow_policy: OverflowPolicy::Ignore,
+            overflow_warned: false,
+            key_persistence: KeyPersistence::default(),
+        })
     }
 
     /// Adds bytes to theh BLoom Filter
-    /// Returns void
+    /// Returns an error only if the filter is full and its overflow policy is `OverflowPolicy::Error`;
+    /// the filter is left untouched in that case
     ///
     /// # Arguments
     /// * `hash_bytes` - item to insert into the filter
-    pub fn add_bytes(&mut self, hash_bytes: &[u8]) {
-        for i in 0..self.count_of_hashes {
-            // let hash_value = fasthash::murmur3::hash32_with_seed(hash_bytes, i as u32);
-            let mut reader = Cursor::new(hash_bytes);
-            let hash_value = murmur3::murmur3_32(&mut reader, i as u32).unwrap();
+    pub fn add_bytes(&mut self, hash_bytes: &[u8]) -> Result<(), BloomError> {
+        let counts_as_insert = match self.insertion_counting {
+            InsertionCounting::EveryInsert => true,
+            InsertionCounting::NewItemsOnly => !self.contains_bytes(hash_bytes),
+        };
+        if counts_as_insert && self.inserted_count >= self.expected_n_items {
+            match &self.overflow_policy {
+                OverflowPolicy::Ignore => {}
+                OverflowPolicy::Warn(callback) => {
+                    if !self.overflow_warned {
+                        self.overflow_warned = true;
+                        callback(self.inserted_count + 1, self.expected_n_items);
+                    }
+                }
+                OverflowPolicy::Error => {
+                    return Err(BloomError::CapacityExceeded { capacity: self.expected_n_items });
+                }
+            }
+        }
 
-            let index = hash_value % (self.bit_array.len() as u32);
-            self.bit_array.set(index as usize, true);
+        for index in self.indices(hash_bytes) {
+            self.bit_array.set(index, true);
         }
+        if counts_as_insert {
+            self.inserted_count += 1;
+        }
+        Ok(())
     }
 
 
+    /// Builds a Bloom Filter from an existing bit array, e.g. one derived from a counting filter
+    ///
+    /// # Arguments
+    /// * `bit_array` - the bits; its length is the size of the filter
+    /// * `count_of_hashes` - number of indices per item
+    /// * `expected_n_items` - number of items the filter was sized for
+    /// * `hasher` - hash function the bits were set with
+    /// * `inserted_count` - number of items that were inserted into the bits
+    pub(crate) fn from_parts(bit_array: BitVec, count_of_hashes: usize, expected_n_items: usize, hasher: H, inserted_count: usize) -> Self {
+        BloomFilterRS {
+            bit_array,
+            count_of_hashes,
+            expected_n_items,
+            hasher,
+            index_mode: IndexMode::Rehash,
+            inserted_count,
+            insertion_counting: InsertionCounting::EveryInsert,
+            overflow_policy: OverflowPolicy::Ignore,
+            overflow_warned: false,
+            key_persistence: KeyPersistence::default(),
+        }
+    }
+
     /// Hashes an item to the Bloom Filter
-    /// Returns void
+    /// Returns an error only if the filter is full and its overflow policy is `OverflowPolicy::Error`
     ///
     /// # Arguments
     /// * `item` - item to insert into the filter
-    pub fn add<T: Serialize + Hash>(&mut self, item: &T) {
+    pub fn add<T: Serialize + Hash>(&mut self, item: &T) -> Result<(), BloomError> {
         let serialized_item = serialization::serialize(item);
-        self.add_bytes(&serialized_item);
+        self.add_bytes(&serialized_item)
+    }
+
+    /// Sets what happens when an insert pushes the filter past its expected number of items
+    ///
+    /// # Arguments
+    /// * `overflow_policy` - ignore, warn via a callback or return an error
+    pub fn set_overflow_policy(&mut self, overflow_policy: OverflowPolicy) {
+        self.overflow_policy = overflow_policy;
+        self.overflow_warned = false;
+    }
+
+    /// Retrieve what happens when an insert pushes the filter past its expected number of items
+    pub fn get_overflow_policy(&self) -> &OverflowPolicy {
+        &self.overflow_policy
+    }
+
+    /// Sets which inserts count towards the inserted count; affects future inserts only
+    ///
+    /// # Arguments
+    /// * `insertion_counting` - count every insert or only inserts that set a new bit
+    pub fn set_insertion_counting(&mut self, insertion_counting: InsertionCounting) {
+        self.insertion_counting = insertion_counting;
+    }
+
+    /// Retrieve the number of inserts so far
+    /// Returns usize: count
+    pub fn get_inserted_count(&self) -> usize {
+        self.inserted_count
+    }
+
+    /// Retrieve how many more items fit before the filter exceeds its design capacity
+    /// Returns usize: count; 0 once the filter is full
+    pub fn remaining_capacity(&self) -> usize {
+        self.expected_n_items.saturating_sub(self.inserted_count)
     }
 
     /// Checks if a given item may be contained by the BloomFilter
@@ -114,19 +424,8 @@ impl BloomFilterRS {
     /// # Arguments
     /// * `item` - bytes to check for membership
     pub fn contains_bytes(&self, hash_bytes: &[u8]) -> bool {
-        for i in 0..self.count_of_hashes {
-            let mut reader = Cursor::new(hash_bytes);
-            let hash_value = murmur3::murmur3_32(&mut reader, i as u32).unwrap();
-
-            let index = hash_value % (self.bit_array.len() as u32);
-            if !self.bit_array[index as usize] {
-                return false;
-            }
-        }
-
         // If all bits are set, the item might be in the filter
-        return true;
-
+        self.indices(hash_bytes).all(|index| self.bit_array[index])
     }
 
     /// Checks if a given item may be contained by the BloomFilter
@@ -139,13 +438,15 @@ impl BloomFilterRS {
         let serialized_item = serialization::serialize(item);
         // println!("c val: {:?}", &serialized_item);
 
-        return self.contains_bytes(&serialized_item);
+        self.contains_bytes(&serialized_item)
     }
 
     /// CLears the Bloom Filter
     pub fn clear(& mut self) {
         let filter_len = self.bit_array.len();
         self.bit_array = BitVec::repeat(false, filter_len);
+        self.inserted_count = 0;
+        self.overflow_warned = false;
     }
 
     /// Estimates the false positive rate.
@@ -167,6 +468,57 @@ impl BloomFilterRS {
 
     }
 
+    /// Fraction of bits that are set
+    /// Returns a value between 0 and 1; a filter at its design capacity sits around 0.5
+    pub fn fill_ratio(&self) -> f64 {
+        self.bit_array.count_ones() as f64 / self.bit_array.len() as f64
+    }
+
+    /// Calculates the false positive rate from the bits that are actually set, instead of the design capacity
+    /// This is calculated by `p = (X / m)^k`
+    ///     - X     number of set bits      (integer)
+    ///     - m     number of bits          (integer)
+    ///     - k     number of hashes        (integer)
+    pub fn current_false_positive_rate(&self) -> f64 {
+        self.fill_ratio().powf(self.count_of_hashes as f64)
+    }
+
+    /// Checks whether the share of set bits reached a threshold, i.e. the filter is overloaded
+    ///
+    /// # Arguments
+    /// * `threshold` - fill ratio between 0 and 1 from which the filter counts as saturated
+    pub fn is_saturated(&self, threshold: f64) -> bool {
+        self.fill_ratio() >= threshold
+    }
+
+    /// Estimates the number of distinct items that were added, from the number of set bits (Swamidass & Baldi)
+    /// This is calculated by `n* = -(m / k) * ln(1 - X / m)`
+    ///     - m     number of bits          (integer)
+    ///     - k     number of hashes        (integer)
+    ///     - X     number of set bits      (integer)
+    /// Returns infinity when every bit is set
+    pub fn estimate_cardinality(&self) -> f64 {
+        cardinality_from_set_bits(self.bit_array.count_ones(), self.bit_array.len(), self.count_of_hashes)
+    }
+
+    /// Estimates the number of distinct items in the union of this filter and another
+    ///
+    /// # Arguments
+    /// * `other` - filter with the same number of bits, hashes and hash algorithm
+    pub fn estimate_union_size(&self, other: &BloomFilterRS<H>) -> Result<f64, BloomError> {
+        Ok(self.union(other)?.estimate_cardinality())
+    }
+
+    /// Estimates the number of distinct items that were added to both this filter and another
+    /// This is calculated by `n(A) + n(B) - n(A ∪ B)`, which is more accurate than the cardinality of the AND-ed bits
+    ///
+    /// # Arguments
+    /// * `other` - filter with the same number of bits, hashes and hash algorithm
+    pub fn estimate_intersection_size(&self, other: &BloomFilterRS<H>) -> Result<f64, BloomError> {
+        let union_size = self.estimate_union_size(other)?;
+        Ok((self.estimate_cardinality() + other.estimate_cardinality() - union_size).max(0.0))
+    }
+
     /// Retrieve the number of hashes this Bloom Filter uses
     /// Returns usize: count
     pub fn get_hash_count(&self) -> usize {
@@ -178,6 +530,275 @@ impl BloomFilterRS {
     pub fn get_bit_count(&self) -> usize {
         self.bit_array.len()
     }
+
+    /// Retrieve the number of items this Bloom Filter was sized for
+    /// Returns usize: count
+    pub fn get_expected_item_count(&self) -> usize {
+        self.expected_n_items
+    }
+
+    /// Retrieve the hash algorithm this Bloom Filter uses
+    pub fn get_hash_algorithm(&self) -> HashAlgorithm {
+        self.hasher.algorithm()
+    }
+
+    /// Retrieve the hasher this Bloom Filter uses
+    pub fn get_hasher(&self) -> &H {
+        &self.hasher
+    }
+
+    /// Sets whether `to_bytes` writes the key of a keyed hasher
+    ///
+    /// # Arguments
+    /// * `key_persistence` - store or omit the key
+    pub fn set_key_persistence(&mut self, key_persistence: KeyPersistence) {
+        self.key_persistence = key_persistence;
+    }
+
+    /// Retrieve whether `to_bytes` writes the key of a keyed hasher
+    pub fn get_key_persistence(&self) -> KeyPersistence {
+        self.key_persistence
+    }
+
+    /// Retrieve how this Bloom Filter derives its indices
+    pub fn get_index_mode(&self) -> IndexMode {
+        self.index_mode
+    }
+
+    /// Checks whether another Bloom Filter can be combined with this one
+    /// Returns an error naming the first parameter that differs
+    ///
+    /// # Arguments
+    /// * `other` - filter to compare with
+    pub fn check_compatible(&self, other: &BloomFilterRS<H>) -> Result<(), BloomError> {
+        if self.bit_array.len() != other.bit_array.len() {
+            return Err(BloomError::Incompatible(format!(
+                "number of bits differs ({} vs {})", self.bit_array.len(), other.bit_array.len()
+            )));
+        }
+        if self.count_of_hashes != other.count_of_hashes {
+            return Err(BloomError::Incompatible(format!(
+                "number of hashes differs ({} vs {})", self.count_of_hashes, other.count_of_hashes
+            )));
+        }
+        if self.hasher.algorithm() != other.hasher.algorithm() {
+            return Err(BloomError::Incompatible(format!(
+                "hash algorithm differs ({:?} vs {:?})", self.hasher.algorithm(), other.hasher.algorithm()
+            )));
+        }
+        if self.hasher != other.hasher {
+            return Err(BloomError::Incompatible("hashers are configured differently".to_string()));
+        }
+        if self.index_mode != other.index_mode {
+            return Err(BloomError::Incompatible(format!(
+                "index mode differs ({:?} vs {:?})", self.index_mode, other.index_mode
+            )));
+        }
+        Ok(())
+    }
+
+    /// Adds all items of another Bloom Filter to this one (bitwise OR)
+    /// The inserted counts are added up; the overflow policy is not applied
+    /// Returns an error, leaving this filter untouched, if the filters are not compatible
+    ///
+    /// # Arguments
+    /// * `other` - filter with the same number of bits, hashes and hash algorithm
+    pub fn union_inplace(&mut self, other: &BloomFilterRS<H>) -> Result<(), BloomError> {
+        self.check_compatible(other)?;
+        for (word, other_word) in self.bit_array.as_raw_mut_slice().iter_mut().zip(other.bit_array.as_raw_slice()) {
+            *word |= *other_word;
+        }
+        self.inserted_count = self.inserted_count.saturating_add(other.inserted_count);
+        Ok(())
+    }
+
+    /// Creates a Bloom Filter that contains the items of both filters (bitwise OR)
+    /// The result keeps the expected number of items of `self`
+    ///
+    /// # Arguments
+    /// * `other` - filter with the same number of bits, hashes and hash algorithm
+    pub fn union(&self, other: &BloomFilterRS<H>) -> Result<BloomFilterRS<H>, BloomError> {
+        let mut result = self.clone();
+        result.union_inplace(other)?;
+        Ok(result)
+    }
+
+    /// Keeps only the bits that are also set in another Bloom Filter (bitwise AND)
+    /// The result contains every item that was added to both filters; its false positive rate can be
+    /// higher than that of a filter built from the intersection directly.
+    /// The inserted count becomes the smaller of both counts
+    ///
+    /// # Arguments
+    /// * `other` - filter with the same number of bits, hashes and hash algorithm
+    pub fn intersect_inplace(&mut self, other: &BloomFilterRS<H>) -> Result<(), BloomError> {
+        self.check_compatible(other)?;
+        for (word, other_word) in self.bit_array.as_raw_mut_slice().iter_mut().zip(other.bit_array.as_raw_slice()) {
+            *word &= *other_word;
+        }
+        self.inserted_count = self.inserted_count.min(other.inserted_count);
+        Ok(())
+    }
+
+    /// Creates a Bloom Filter that contains the items that were added to both filters (bitwise AND)
+    /// The result keeps the expected number of items of `self`
+    ///
+    /// # Arguments
+    /// * `other` - filter with the same number of bits, hashes and hash algorithm
+    pub fn intersect(&self, other: &BloomFilterRS<H>) -> Result<BloomFilterRS<H>, BloomError> {
+        let mut result = self.clone();
+        result.intersect_inplace(other)?;
+        Ok(result)
+    }
+
+    /// Serializes the Bloom Filter to a self-describing binary format
+    /// Returns the bytes; load them again with `BloomFilterRS::from_bytes`.
+    /// The overflow policy and insertion counting are written, but not the callback of `OverflowPolicy::Warn`
+    /// The key of a keyed hasher is only written with `KeyPersistence::Store`
+    ///
+    /// Layout (all integers little-endian):
+    ///     - 4 bytes   magic number `BLMF`
+    ///     - 2 bytes   format version
+    ///     - 1 byte    hash algorithm id
+    ///     - 1 byte    index mode id (reserved and 0 before double hashing was added)
+    ///     - 8 bytes   number of bits
+    ///     - 8 bytes   number of hashes
+    ///     - 8 bytes   expected number of items
+    ///     - 8 bytes   inserted count (since version 2)
+    ///     - 1 byte    key: 0 unkeyed, 1 keyed but omitted, 2 stored (since version 3)
+    ///     - 1 byte    overflow policy: 0 ignore, 1 warn, 2 error (since version 4)
+    ///     - 1 byte    insertion counting: 0 every insert, 1 new items only (since version 4)
+    ///     - 16 bytes  the key, only if it is stored
+    ///     - the bit array, packed LSB first in `ceil(bits / 8)` bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes_with_key_persistence(self.key_persistence)
+    }
+
+    /// Serializes the Bloom Filter like `to_bytes`, but stores or omits the key regardless of the filter's own
+    /// key persistence, e.g. to hand a keyed filter to another process
+    ///
+    /// # Arguments
+    /// * `key_persistence` - store or omit the key
+    pub fn to_bytes_with_key_persistence(&self, key_persistence: KeyPersistence) -> Vec<u8> {
+        let stored_key = match (self.hasher.key(), key_persistence) {
+            (None, _) => StoredKey::Unkeyed,
+            (Some(_), KeyPersistence::Omit) => StoredKey::Omitted,
+            (Some(key), KeyPersistence::Store) => StoredKey::Stored(key),
+        };
+        let packed_bits = serialization::pack_bits(&self.bit_array);
+        let mut writer = ByteWriter::with_capacity(HEADER_SIZE + KEY_SIZE + packed_bits.len());
+        writer.write_bytes(MAGIC);
+        writer.write_u16(FORMAT_VERSION);
+        writer.write_u8(self.hasher.algorithm().id());
+        writer.write_u8(self.index_mode.id());
+        writer.write_u64(self.bit_array.len() as u64);
+        writer.write_u64(self.count_of_hashes as u64);
+        writer.write_u64(self.expected_n_items as u64);
+        writer.write_u64(self.inserted_count as u64);
+        writer.write_u8(stored_key.id());
+        writer.write_u8(self.overflow_policy.id());
+        writer.write_u8(self.insertion_counting.id());
+        if let StoredKey::Stored(key) = stored_key {
+            writer.write_bytes(&key);
+        }
+        writer.write_bytes(&packed_bits);
+        writer.into_bytes()
+    }
+
+    /// Deserializes a Bloom Filter written by `to_bytes` with a known hasher
+    /// Returns an error if the payload was written with another hash algorithm or key, or see `from_bytes`
+    ///
+    /// # Arguments
+    /// * `bytes` - serialized Bloom Filter
+    /// * `hasher` - hasher the filter was built with
+    pub fn from_bytes_with_hasher(bytes: &[u8], hasher: H) -> Result<Self, BloomError> {
+        BloomFilterRS::decode(bytes, |hash_algorithm, _| match hash_algorithm == hasher.algorithm() {
+            true => Ok(hasher),
+            false => Err(BloomError::Incompatible(format!(
+                "payload was hashed with {:?}, not {:?}", hash_algorithm, hasher.algorithm()
+            ))),
+        })
+    }
+
+    /// Parses a serialized Bloom Filter; `make_hasher` provides the hasher for the algorithm and stored key in
+    /// the header, and the key of that hasher is checked against the header
+    fn decode(bytes: &[u8], make_hasher: impl FnOnce(HashAlgorithm, Option<[u8; KEY_SIZE]>) -> Result<H, BloomError>) -> Result<Self, BloomError> {
+        let mut reader = ByteReader::new(bytes);
+        if reader.read_bytes(MAGIC.len())? != MAGIC {
+            return Err(BloomError::InvalidMagic);
+        }
+        let version = reader.read_u16()?;
+        if version == 0 || version > FORMAT_VERSION {
+            return Err(BloomError::UnsupportedVersion(version));
+        }
+        let hash_algorithm = HashAlgorithm::from_id(reader.read_u8()?)?;
+        let index_mode = IndexMode::from_id(reader.read_u8()?)?;
+        let num_of_bits = reader.read_usize()?;
+        let count_of_hashes = reader.read_usize()?;
+        let expected_n_items = reader.read_usize()?;
+        let inserted_count = match version {
+            1 => None,
+            _ => Some(reader.read_usize()?),
+        };
+        let key_state = match version {
+            1 | 2 => 0,
+            _ => reader.read_u8()?,
+        };
+        let (overflow_policy, insertion_counting) = match version {
+            1..=3 => (OverflowPolicy::Ignore, InsertionCounting::EveryInsert),
+            _ => (OverflowPolicy::from_id(reader.read_u8()?)?, InsertionCounting::from_id(reader.read_u8()?)?),
+        };
+        let stored_key = match key_state {
+            0 => StoredKey::Unkeyed,
+            1 => StoredKey::Omitted,
+            2 => StoredKey::Stored(reader.read_bytes(KEY_SIZE)?.try_into().unwrap()),
+            other => return Err(BloomError::InvalidPayload(format!("unknown key state {}", other))),
+        };
+        if num_of_bits == 0 || count_of_hashes == 0 {
+            return Err(BloomError::InvalidPayload("number of bits and hashes must be > 0".to_string()));
+        }
+
+        let hasher = match stored_key {
+            StoredKey::Stored(key) => make_hasher(hash_algorithm, Some(key))?,
+            _ => make_hasher(hash_algorithm, None)?,
+        };
+        check_addressable(&hasher, num_of_bits).map_err(|err| BloomError::InvalidPayload(err.to_string()))?;
+
+        let packed_len = num_of_bits.div_ceil(8);
+        if reader.remaining() < packed_len {
+            let header_size = bytes.len() - reader.remaining();
+            return Err(BloomError::Truncated { expected: header_size + packed_len, actual: bytes.len() });
+        }
+        let bit_array = serialization::unpack_bits(reader.read_bytes(packed_len)?, num_of_bits)?;
+        reader.finish()?;
+
+        let inserted_count = inserted_count.unwrap_or_else(|| {
+            let estimate = cardinality_from_set_bits(bit_array.count_ones(), num_of_bits, count_of_hashes);
+            estimate.round().min(usize::MAX as f64) as usize
+        });
+        match (&stored_key, hasher.key()) {
+            (StoredKey::Unkeyed, None) | (StoredKey::Omitted, Some(_)) => {}
+            (StoredKey::Stored(stored), Some(key)) if *stored == key => {}
+            (StoredKey::Omitted, None) => return Err(BloomError::MissingKey),
+            (StoredKey::Unkeyed, Some(_)) => return Err(BloomError::Incompatible("payload was not hashed with a key".to_string())),
+            (StoredKey::Stored(_), _) => return Err(BloomError::Incompatible("payload was hashed with another key".to_string())),
+        }
+
+        let mut bloom_filter = BloomFilterRS::from_parts(bit_array, count_of_hashes, expected_n_items, hasher, inserted_count);
+        bloom_filter.index_mode = index_mode;
+        bloom_filter.overflow_policy = overflow_policy;
+        bloom_filter.insertion_counting = insertion_counting;
+        if let StoredKey::Stored(_) = stored_key {
+            bloom_filter.key_persistence = KeyPersistence::Store;
+        }
+        Ok(bloom_filter)
+    }
+
+    fn indices<'a>(&self, hash_bytes: &'a [u8]) -> impl Iterator<Item = usize> + 'a
+    where
+        H: 'a,
+    {
+        hashing::indices(self.hasher.clone(), self.index_mode, hash_bytes, self.count_of_hashes, self.bit_array.len())
+    }
 }
 
 
@@ -193,11 +814,12 @@ mod tests_insert_and_get {
     }
 
     #[test]
+    #[allow(clippy::len_zero)]
     fn test_add_and_contains() {
         let mut bf = BloomFilterRS::new(10, 0.01);
 
-        bf.add(&"test");
-        bf.add(&1);
+        bf.add(&"test").unwrap();
+        bf.add(&1).unwrap();
 
         assert!(bf.bit_array.len() > 0, "Bloom filter memory should be >0 bits");
         assert!(bf.get_bit_count() > 0, "Bloom filter memory should be >0 bits");
@@ -219,7 +841,7 @@ mod tests_insert_and_get {
         // Item should not be in the filter initially
         assert!(!bloom_filter.contains(&item), "Item should not be in the filter yet");
 
-        bloom_filter.add(&item);
+        bloom_filter.add(&item).unwrap();
 
         // Item should be in the filter after adding
         assert!(bloom_filter.contains(&item), "Item should be in the filter after adding");
@@ -229,9 +851,9 @@ mod tests_insert_and_get {
     fn test_add_multiple_items() {
         let mut bf = BloomFilterRS::new(3, 0.01);
 
-        bf.add(&"een");
-        bf.add(&"twee");
-        bf.add(&"drie");
+        bf.add(&"een").unwrap();
+        bf.add(&"twee").unwrap();
+        bf.add(&"drie").unwrap();
 
 
         println!("bitsize: {}", bf.bit_array.len());
@@ -249,8 +871,8 @@ mod tests_insert_and_get {
     fn test_insert_and_contains() {
         let mut bf = BloomFilterRS::new(3, 0.01);
 
-        bf.add(&"test");
-        bf.add(&1);
+        bf.add(&"test").unwrap();
+        bf.add(&1).unwrap();
 
         println!("bitsize: {}", bf.bit_array.len());
         println!("n hashes: {}", bf.count_of_hashes);
@@ -268,7 +890,7 @@ mod tests_insert_and_get {
         let mut bloom_filter = BloomFilterRS::new(100, 0.01);
         let item = TestItem { key: 1, value: "test".to_string() };
         assert!(!bloom_filter.contains(&item), "Item should not be in the filter yet");
-        bloom_filter.add(&item);
+        bloom_filter.add(&item).unwrap();
         assert!(bloom_filter.contains(&item), "Item should be in the filter after adding");
         bloom_filter.clear();
         assert!(!bloom_filter.contains(&item), "Item shouldnt be in filter since it's cleared");
@@ -284,22 +906,399 @@ mod tests_insert_and_get {
         // Ensure the bytes are not in the filter initially
         assert!(!bloom_filter.contains_bytes(&some_bytes), "Bytes should not be in the filter yet");
 
-        bloom_filter.add_bytes(&some_bytes);
+        bloom_filter.add_bytes(&some_bytes).unwrap();
 
         // Now the bytes should be in the filter
         assert!(bloom_filter.contains_bytes(&some_bytes), "Bytes should be in the filter after adding");
     }
-    // #[test]
-    // fn test_serialization() {
-    //     let mut bloom_filter = BloomFilterRS::new(100, 0.01);
-    //     bloom_filter.add(&"test item");
-    //
-    //     let serialized = serde_json::to_string(&bloom_filter).expect("Failed to serialize");
-    //     // println!("serialized: {}", serialized);
-    //     let deserialized: BloomFilterRS = serde_json::from_str(&serialized).expect("Failed to deserialize");
-    //
-    //     assert!(deserialized.contains(&"test item"), "Deserialized filter should contain the item");
-    // }
+    #[test]
+    fn test_serialization() {
+        let mut bloom_filter = BloomFilterRS::new(100, 0.01);
+        bloom_filter.add(&"test item").unwrap();
+
+        let serialized = bloom_filter.to_bytes();
+        let deserialized = BloomFilterRS::from_bytes(&serialized).expect("Failed to deserialize");
+
+        assert!(deserialized.contains(&"test item"), "Deserialized filter should contain the item");
+        assert!(!deserialized.contains(&"other item"), "Deserialized filter should not contain other items");
+        assert_eq!(deserialized.get_bit_count(), bloom_filter.get_bit_count());
+        assert_eq!(deserialized.get_hash_count(), bloom_filter.get_hash_count());
+        assert_eq!(deserialized.get_expected_item_count(), 100);
+        assert_eq!(deserialized.get_hash_algorithm(), HashAlgorithm::Murmur3_128);
+        assert_eq!(deserialized.to_bytes(), serialized, "Round trip should be byte-for-byte identical");
+    }
+}
+
+#[cfg(test)]
+mod tests_double_hashing {
+    use super::*;
+
+    #[test]
+    fn test_add_and_contains() {
+        let mut bloom_filter = BloomFilterRS::with_index_mode(1000, 0.01, IndexMode::DoubleHashing);
+        assert_eq!(bloom_filter.get_index_mode(), IndexMode::DoubleHashing);
+        for i in 0..1000 {
+            bloom_filter.add(&i).unwrap();
+        }
+        assert!((0..1000).all(|i| bloom_filter.contains(&i)), "Double hashing should have no false negatives");
+    }
+
+    #[test]
+    fn test_false_positive_rate_matches_rehashing() {
+        let mut rehashing = BloomFilterRS::new(10_000, 0.01);
+        let mut double_hashing = BloomFilterRS::with_index_mode(10_000, 0.01, IndexMode::DoubleHashing);
+        for i in 0..10_000 {
+            rehashing.add(&i).unwrap();
+            double_hashing.add(&i).unwrap();
+        }
+        let false_positives = |bloom_filter: &BloomFilterRS| (10_000..110_000).filter(|i| bloom_filter.contains(i)).count() as f64 / 100_000.0;
+        let rehashing_fpr = false_positives(&rehashing);
+        let double_hashing_fpr = false_positives(&double_hashing);
+        assert!(double_hashing_fpr < 0.015, "Double hashing FPR {} should stay near 1%", double_hashing_fpr);
+        assert!((double_hashing_fpr - rehashing_fpr).abs() < 0.005, "{} vs {}", double_hashing_fpr, rehashing_fpr);
+    }
+
+    #[test]
+    fn test_index_mode_is_serialized() {
+        let mut bloom_filter = BloomFilterRS::with_index_mode(100, 0.01, IndexMode::DoubleHashing);
+        bloom_filter.add(&"item").unwrap();
+        let bytes = bloom_filter.to_bytes();
+        assert_eq!(bytes[7], IndexMode::DoubleHashing.id());
+
+        let deserialized = BloomFilterRS::from_bytes(&bytes).unwrap();
+        assert_eq!(deserialized.get_index_mode(), IndexMode::DoubleHashing);
+        assert!(deserialized.contains(&"item"));
+
+        let mut unknown_mode = bytes.clone();
+        unknown_mode[7] = 9;
+        assert_eq!(BloomFilterRS::from_bytes(&unknown_mode).err(), Some(BloomError::UnknownIndexMode(9)));
+    }
+
+    #[test]
+    fn test_modes_cannot_be_combined() {
+        let rehashing = BloomFilterRS::new(100, 0.01);
+        let double_hashing = BloomFilterRS::with_index_mode(100, 0.01, IndexMode::DoubleHashing);
+        assert!(matches!(rehashing.union(&double_hashing), Err(BloomError::Incompatible(_))));
+    }
+}
+
+#[cfg(test)]
+mod tests_hashers {
+    use super::*;
+    use crate::hashing::{Crc32cHasher, Fnv1aHasher, Murmur3_32Hasher, SipHash13Hasher, Xxh3Hasher};
+
+    fn check_hasher<H: BloomHasher>(hasher: H) {
+        let mut bloom_filter = BloomFilterRS::with_hasher(1000, 0.01, IndexMode::Rehash, hasher.clone()).unwrap();
+        for i in 0..1000 {
+            bloom_filter.add(&i).unwrap();
+        }
+        assert!((0..1000).all(|i| bloom_filter.contains(&i)), "{:?} should have no false negatives", hasher);
+        let false_positives = (1000..21_000).filter(|i| bloom_filter.contains(i)).count();
+        assert!(false_positives < 250, "{:?} gave {} false positives in 20000", hasher, false_positives);
+
+        let bytes = bloom_filter.to_bytes();
+        assert_eq!(bytes[6], hasher.algorithm().id());
+        let deserialized = BloomFilterRS::from_bytes_with_hasher(&bytes, hasher.clone()).unwrap();
+        assert!((0..1000).all(|i| deserialized.contains(&i)));
+        assert_eq!(deserialized.to_bytes(), bytes);
+    }
+
+    #[test]
+    fn test_built_in_hashers() {
+        check_hasher(Murmur3_32Hasher);
+        check_hasher(Xxh3Hasher);
+        check_hasher(SipHash13Hasher::new([7; 16]));
+        check_hasher(Fnv1aHasher);
+        check_hasher(Crc32cHasher);
+    }
+
+    #[test]
+    fn test_false_positive_rate_of_every_hash_algorithm_and_index_mode() {
+        let algorithms = [
+            HashAlgorithm::Murmur3_32,
+            HashAlgorithm::Murmur3_128,
+            HashAlgorithm::Xxh3,
+            HashAlgorithm::SipHash13,
+            HashAlgorithm::Fnv1a,
+            HashAlgorithm::Crc32c,
+        ];
+        for algorithm in algorithms {
+            for index_mode in [IndexMode::Rehash, IndexMode::DoubleHashing] {
+                let mut bloom_filter = BloomFilterRS::with_hash_algorithm(50_000, 0.01, index_mode, algorithm).unwrap();
+                for i in 0..50_000u64 {
+                    bloom_filter.add(&i).unwrap();
+                }
+                let false_positives = (50_000..150_000u64).filter(|i| bloom_filter.contains(i)).count();
+                let false_positive_rate = false_positives as f64 / 100_000.0;
+                assert!(
+                    false_positive_rate < 0.0125,
+                    "{:?} with {:?} has a false positive rate of {}", algorithm, index_mode, false_positive_rate
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_concrete_hasher_matches_any_hasher() {
+        let mut xxh3_filter = BloomFilterRS::with_hasher(100, 0.01, IndexMode::DoubleHashing, Xxh3Hasher).unwrap();
+        let mut any_filter = BloomFilterRS::with_hash_algorithm(100, 0.01, IndexMode::DoubleHashing, HashAlgorithm::Xxh3).unwrap();
+        for i in 0..100 {
+            xxh3_filter.add(&i).unwrap();
+            any_filter.add(&i).unwrap();
+        }
+        assert_eq!(xxh3_filter.to_bytes(), any_filter.to_bytes());
+
+        let loaded = BloomFilterRS::from_bytes(&xxh3_filter.to_bytes()).unwrap();
+        assert_eq!(loaded.get_hash_algorithm(), HashAlgorithm::Xxh3);
+        assert_eq!(*loaded.get_hasher(), AnyHasher::Xxh3(Xxh3Hasher));
+    }
+
+    #[test]
+    fn test_rejects_other_hasher() {
+        let bytes = BloomFilterRS::with_hasher(100, 0.01, IndexMode::Rehash, Fnv1aHasher).unwrap().to_bytes();
+        assert!(matches!(BloomFilterRS::from_bytes_with_hasher(&bytes, Xxh3Hasher), Err(BloomError::Incompatible(_))));
+
+        let murmur = BloomFilterRS::new(100, 0.01);
+        let xxh3 = BloomFilterRS::with_hash_algorithm(100, 0.01, IndexMode::Rehash, HashAlgorithm::Xxh3).unwrap();
+        assert!(matches!(murmur.union(&xxh3), Err(BloomError::Incompatible(_))));
+
+        let one_key = BloomFilterRS::with_hasher(100, 0.01, IndexMode::Rehash, SipHash13Hasher::new([1; 16])).unwrap();
+        let other_key = BloomFilterRS::with_hasher(100, 0.01, IndexMode::Rehash, SipHash13Hasher::new([2; 16])).unwrap();
+        assert!(matches!(one_key.union(&other_key), Err(BloomError::Incompatible(_))));
+    }
+}
+
+#[cfg(test)]
+mod tests_keyed {
+    use super::*;
+
+    const KEY: [u8; 16] = *b"0123456789abcdef";
+
+    fn keyed_filter() -> BloomFilterRS {
+        let mut bloom_filter = BloomFilterRS::with_key(100, 0.01, IndexMode::DoubleHashing, KEY);
+        for i in 0..100 {
+            bloom_filter.add(&i).unwrap();
+        }
+        bloom_filter
+    }
+
+    #[test]
+    fn test_key_changes_the_bits() {
+        let bloom_filter = keyed_filter();
+        assert!((0..100).all(|i| bloom_filter.contains(&i)));
+        assert_eq!(bloom_filter.get_hash_algorithm(), HashAlgorithm::SipHash13);
+
+        let mut other_key = BloomFilterRS::with_key(100, 0.01, IndexMode::DoubleHashing, [1; 16]);
+        for i in 0..100 {
+            other_key.add(&i).unwrap();
+        }
+        assert_ne!(bloom_filter.bit_array, other_key.bit_array, "Another key should set other bits");
+        assert!(matches!(bloom_filter.union(&other_key), Err(BloomError::Incompatible(_))));
+        assert!(bloom_filter.union(&keyed_filter()).is_ok());
+    }
+
+    #[test]
+    fn test_key_is_omitted_by_default() {
+        let bytes = keyed_filter().to_bytes();
+        assert_eq!(bytes[40], 1);
+        assert!(!bytes.windows(KEY.len()).any(|window| window == KEY), "The key should not be in the payload");
+        assert_eq!(bytes.len(), HEADER_SIZE + keyed_filter().get_bit_count().div_ceil(8));
+
+        assert_eq!(BloomFilterRS::from_bytes(&bytes).err(), Some(BloomError::MissingKey));
+        let deserialized = BloomFilterRS::from_bytes_with_key(&bytes, KEY).unwrap();
+        assert!((0..100).all(|i| deserialized.contains(&i)));
+        assert_eq!(deserialized.to_bytes(), bytes);
+    }
+
+    #[test]
+    fn test_key_can_be_stored() {
+        let mut bloom_filter = keyed_filter();
+        bloom_filter.set_key_persistence(KeyPersistence::Store);
+        let bytes = bloom_filter.to_bytes();
+        assert_eq!(bytes[40], 2);
+        assert_eq!(&bytes[HEADER_SIZE..HEADER_SIZE + KEY_SIZE], &KEY);
+
+        let deserialized = BloomFilterRS::from_bytes(&bytes).unwrap();
+        assert!((0..100).all(|i| deserialized.contains(&i)));
+        assert_eq!(deserialized.to_bytes(), bytes, "A stored key should stay stored");
+        assert!(BloomFilterRS::from_bytes_with_key(&bytes, KEY).is_ok());
+        assert!(matches!(BloomFilterRS::from_bytes_with_key(&bytes, [1; 16]), Err(BloomError::Incompatible(_))));
+        assert!(matches!(BloomFilterRS::from_bytes(&bytes[..HEADER_SIZE + 8]), Err(BloomError::Truncated { .. })));
+    }
+
+    #[test]
+    fn test_key_is_rejected_for_unkeyed_payload() {
+        let bytes = BloomFilterRS::new(100, 0.01).to_bytes();
+        assert_eq!(bytes[40], 0);
+        assert!(matches!(BloomFilterRS::from_bytes_with_key(&bytes, KEY), Err(BloomError::Incompatible(_))));
+
+        let unkeyed_siphash = BloomFilterRS::with_hash_algorithm(100, 0.01, IndexMode::Rehash, HashAlgorithm::SipHash13).unwrap().to_bytes();
+        assert!(BloomFilterRS::from_bytes(&unkeyed_siphash).is_ok());
+        assert!(matches!(BloomFilterRS::from_bytes_with_key(&unkeyed_siphash, KEY), Err(BloomError::Incompatible(_))));
+    }
+
+    #[test]
+    fn test_zero_key_counts_as_keyed() {
+        let mut bloom_filter = BloomFilterRS::with_key(100, 0.01, IndexMode::DoubleHashing, [0; 16]);
+        bloom_filter.add(&"item").unwrap();
+        let bytes = bloom_filter.to_bytes();
+        assert_eq!(bytes[40], 1, "An explicit zero key should still be treated as a secret");
+        assert_eq!(BloomFilterRS::from_bytes(&bytes).err(), Some(BloomError::MissingKey));
+        assert!(BloomFilterRS::from_bytes_with_key(&bytes, [0; 16]).unwrap().contains(&"item"));
+
+        let unkeyed = BloomFilterRS::with_hash_algorithm(100, 0.01, IndexMode::DoubleHashing, HashAlgorithm::SipHash13).unwrap();
+        assert!(matches!(bloom_filter.union(&unkeyed), Err(BloomError::Incompatible(_))));
+    }
+
+    #[test]
+    fn test_reads_version_2_payload() {
+        let mut bloom_filter = BloomFilterRS::new(100, 0.01);
+        bloom_filter.add(&"item").unwrap();
+        // a version 2 payload is the version 3 payload without the key byte
+        let bytes = bloom_filter.to_bytes();
+        let mut version_2 = bytes[..40].to_vec();
+        version_2[4..6].copy_from_slice(&2u16.to_le_bytes());
+        version_2.extend_from_slice(&bytes[HEADER_SIZE..]);
+
+        let deserialized = BloomFilterRS::from_bytes(&version_2).expect("Version 2 payloads should load");
+        assert!(deserialized.contains(&"item"));
+        assert_eq!(deserialized.get_inserted_count(), 1);
+    }
+}
+
+#[cfg(test)]
+mod tests_large_filter {
+    use super::*;
+
+    #[test]
+    fn test_indices_cover_the_range_beyond_4_gbit() {
+        let num_of_slots: usize = 5 << 30;
+        assert_eq!(hashing::reduce(u64::MAX, num_of_slots), num_of_slots - 1);
+        for index_mode in [IndexMode::Rehash, IndexMode::DoubleHashing] {
+            let indices: Vec<usize> = (0..10_000u64)
+                .flat_map(|i| hashing::indices(AnyHasher::default(), index_mode, &serialization::serialize(&i), 7, num_of_slots).collect::<Vec<usize>>())
+                .collect();
+            assert!(indices.iter().all(|index| *index < num_of_slots));
+            // a fifth of the slots lies beyond 2^32, so expect about a fifth of the 70000 indices there
+            let beyond_u32 = indices.iter().filter(|index| **index > u32::MAX as usize).count();
+            assert!((12_000..16_000).contains(&beyond_u32), "{:?}: {} indices beyond 2^32", index_mode, beyond_u32);
+        }
+    }
+
+    #[test]
+    fn test_32_bit_hashers_are_rejected_beyond_4_gbit() {
+        // about 5.8 Gbit; every rejection happens before the bits are allocated
+        let (expected_number_of_items, desired_false_positive_rate) = (600_000_000, 0.01);
+        assert!(calc_optimal_number_of_bits(expected_number_of_items, desired_false_positive_rate) > u32::MAX as usize);
+        for algorithm in [HashAlgorithm::Murmur3_32, HashAlgorithm::Crc32c] {
+            assert_eq!(AnyHasher::from(algorithm).max_slots(), 1 << 32);
+            let bloom_filter = BloomFilterRS::with_hash_algorithm(expected_number_of_items, desired_false_positive_rate, IndexMode::Rehash, algorithm);
+            assert!(matches!(bloom_filter, Err(BloomError::InvalidParameter(_))), "{:?} should be rejected", algorithm);
+        }
+        for algorithm in [HashAlgorithm::Murmur3_128, HashAlgorithm::Xxh3, HashAlgorithm::SipHash13, HashAlgorithm::Fnv1a] {
+            assert_eq!(AnyHasher::from(algorithm).max_slots(), usize::MAX);
+        }
+        assert!(BloomFilterRS::with_hash_algorithm(100, 0.01, IndexMode::Rehash, HashAlgorithm::Crc32c).is_ok());
+
+        // a payload claiming more bits than its hasher reaches is rejected before the bits are read
+        let mut bytes = BloomFilterRS::with_hash_algorithm(100, 0.01, IndexMode::Rehash, HashAlgorithm::Murmur3_32).unwrap().to_bytes();
+        bytes[8..16].copy_from_slice(&(5u64 << 30).to_le_bytes());
+        assert!(matches!(BloomFilterRS::from_bytes(&bytes), Err(BloomError::InvalidPayload(_))));
+    }
+
+    #[test]
+    #[ignore = "allocates a 5 Gbit (640 MB) filter"]
+    fn test_filter_larger_than_4_gbit_uses_full_range() {
+        let num_of_bits: usize = 5 << 30;
+        let mut bloom_filter = BloomFilterRS::from_parts(BitVec::repeat(false, num_of_bits), 7, 500_000_000, AnyHasher::default(), 0);
+        for i in 0..10_000 {
+            bloom_filter.add(&i).unwrap();
+        }
+        assert!((0..10_000).all(|i| bloom_filter.contains(&i)));
+        let bits_beyond_u32 = bloom_filter.bit_array[(u32::MAX as usize + 1)..].count_ones();
+        // a fifth of the bits lies beyond 2^32, so expect about a fifth of the 70000 set bits there
+        assert!((12_000..16_000).contains(&bits_beyond_u32), "{} bits set beyond 2^32", bits_beyond_u32);
+    }
+}
+
+#[cfg(test)]
+mod tests_serialization {
+    use super::*;
+
+    fn serialized_filter() -> Vec<u8> {
+        let mut bloom_filter = BloomFilterRS::new(50, 0.05);
+        for i in 0..50 {
+            bloom_filter.add(&i).unwrap();
+        }
+        bloom_filter.to_bytes()
+    }
+
+    #[test]
+    fn test_header_layout() {
+        let bloom_filter = BloomFilterRS::new(50, 0.05);
+        let bytes = bloom_filter.to_bytes();
+
+        assert_eq!(&bytes[0..4], b"BLMF");
+        assert_eq!(u16::from_le_bytes([bytes[4], bytes[5]]), FORMAT_VERSION);
+        assert_eq!(bytes[6], HashAlgorithm::Murmur3_128.id());
+        assert_eq!(bytes.len(), HEADER_SIZE + bloom_filter.get_bit_count().div_ceil(8));
+    }
+
+    #[test]
+    fn test_reads_murmur3_32_filter() {
+        let mut bloom_filter = BloomFilterRS::from_parts(BitVec::repeat(false, 1000), 7, 100, AnyHasher::from(HashAlgorithm::Murmur3_32), 0);
+        for i in 0..100 {
+            bloom_filter.add(&i).unwrap();
+        }
+        let bytes = bloom_filter.to_bytes();
+        assert_eq!(bytes[6], HashAlgorithm::Murmur3_32.id());
+
+        let deserialized = BloomFilterRS::from_bytes(&bytes).unwrap();
+        assert_eq!(deserialized.get_hash_algorithm(), HashAlgorithm::Murmur3_32);
+        assert!((0..100).all(|i| deserialized.contains(&i)));
+    }
+
+    #[test]
+    fn test_rejects_truncated_payload() {
+        let bytes = serialized_filter();
+        for len in [0, 3, 10, HEADER_SIZE - 1, HEADER_SIZE, bytes.len() - 1] {
+            let result = BloomFilterRS::from_bytes(&bytes[..len]);
+            assert!(matches!(result, Err(BloomError::Truncated { .. })), "Length {} should be rejected as truncated", len);
+        }
+    }
+
+    #[test]
+    fn test_rejects_trailing_bytes() {
+        let mut bytes = serialized_filter();
+        bytes.push(0);
+        assert!(matches!(BloomFilterRS::from_bytes(&bytes), Err(BloomError::InvalidPayload(_))));
+    }
+
+    #[test]
+    fn test_rejects_mismatched_header() {
+        let bytes = serialized_filter();
+
+        let mut wrong_magic = bytes.clone();
+        wrong_magic[0] = b'X';
+        assert_eq!(BloomFilterRS::from_bytes(&wrong_magic).err(), Some(BloomError::InvalidMagic));
+
+        let mut wrong_version = bytes.clone();
+        wrong_version[4] = 99;
+        assert_eq!(BloomFilterRS::from_bytes(&wrong_version).err(), Some(BloomError::UnsupportedVersion(99)));
+
+        let mut wrong_algorithm = bytes.clone();
+        wrong_algorithm[6] = 200;
+        assert_eq!(BloomFilterRS::from_bytes(&wrong_algorithm).err(), Some(BloomError::UnknownHashAlgorithm(200)));
+
+        // claim more bits than the payload holds
+        let mut wrong_bit_count = bytes.clone();
+        wrong_bit_count[8..16].copy_from_slice(&(1_000_000u64).to_le_bytes());
+        assert!(matches!(BloomFilterRS::from_bytes(&wrong_bit_count), Err(BloomError::Truncated { .. })));
+
+        let mut zero_hashes = bytes;
+        zero_hashes[16..24].copy_from_slice(&0u64.to_le_bytes());
+        assert!(matches!(BloomFilterRS::from_bytes(&zero_hashes), Err(BloomError::InvalidPayload(_))));
+    }
 }
 
 #[cfg(test)]
@@ -307,6 +1306,7 @@ mod tests_false_positive_rate {
     use super::*;
     use serde::{Serialize, Deserialize};
 
+    #[allow(dead_code)]
     #[derive(Serialize, Deserialize, Hash)]
     struct TestItem {
         key: i32,
@@ -327,7 +1327,7 @@ mod tests_false_positive_rate {
 
         // Insert `n` items into the filter
         for i in 0..n {
-            bloom_filter.add(&i);
+            bloom_filter.add(&i).unwrap();
         }
 
         // Check `n` different items and count the false positives
@@ -337,6 +1337,7 @@ mod tests_false_positive_rate {
         }).count();
         let fp_count_expected = (n as f64 * p) as usize;
         let fp_count_deviation = (fp_count_expected as i64 - fp_count_observed as i64).abs();
+        #[allow(clippy::unnecessary_cast)]
         let allowed_fp_count_deviation = allowed_perc_deviation as f64 * fp_count_expected as f64;
         println!("observed {}", fp_count_observed);
         assert!(
@@ -350,9 +1351,338 @@ mod tests_false_positive_rate {
         let n = 1000; // Number of items to insert
         let p = 0.01; // Desired false positive probability
         let bloom_filter = BloomFilterRS::new(n, p);
+        #[allow(clippy::unnecessary_cast)]
         let estimate_deviation = (p - bloom_filter.estimate_false_positive_rate() as f64).abs();
 
         assert!(estimate_deviation != 0.0, "Estimated false positive rate cannot be 0");
         assert!(estimate_deviation < p / 10.0, "Estimated false positive rate is too large");
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests_set_operations {
+    use super::*;
+
+    #[test]
+    fn test_union() {
+        let mut monday = BloomFilterRS::new(100, 0.01);
+        let mut tuesday = BloomFilterRS::new(100, 0.01);
+        monday.add(&"een").unwrap();
+        tuesday.add(&"twee").unwrap();
+
+        let both = monday.union(&tuesday).expect("Filters should be compatible");
+        assert!(both.contains(&"een"));
+        assert!(both.contains(&"twee"));
+        assert!(!both.contains(&"drie"));
+
+        monday.union_inplace(&tuesday).expect("Filters should be compatible");
+        assert_eq!(monday.to_bytes(), both.to_bytes());
+    }
+
+    #[test]
+    fn test_union_equals_filter_with_all_items() {
+        let mut all_items = BloomFilterRS::new(1000, 0.01);
+        let mut even = BloomFilterRS::new(1000, 0.01);
+        let mut odd = BloomFilterRS::new(1000, 0.01);
+        for i in 0..1000 {
+            all_items.add(&i).unwrap();
+            if i % 2 == 0 { even.add(&i).unwrap() } else { odd.add(&i).unwrap() }
+        }
+        assert_eq!(even.union(&odd).unwrap().to_bytes(), all_items.to_bytes());
+    }
+
+    #[test]
+    fn test_intersect() {
+        let mut first = BloomFilterRS::new(100, 0.01);
+        let mut second = BloomFilterRS::new(100, 0.01);
+        first.add(&"shared").unwrap();
+        first.add(&"only first").unwrap();
+        second.add(&"shared").unwrap();
+        second.add(&"only second").unwrap();
+
+        let shared = first.intersect(&second).expect("Filters should be compatible");
+        assert!(shared.contains(&"shared"));
+        assert!(!shared.contains(&"only first"));
+        assert!(!shared.contains(&"only second"));
+
+        first.intersect_inplace(&second).expect("Filters should be compatible");
+        assert_eq!(first.to_bytes(), shared.to_bytes());
+    }
+
+    #[test]
+    fn test_incompatible_filters_are_rejected() {
+        let mut bloom_filter = BloomFilterRS::new(100, 0.01);
+        bloom_filter.add(&"een").unwrap();
+        let before = bloom_filter.to_bytes();
+
+        let other_size = BloomFilterRS::new(200, 0.01);
+        assert!(matches!(bloom_filter.union(&other_size), Err(BloomError::Incompatible(_))));
+        assert!(matches!(bloom_filter.union_inplace(&other_size), Err(BloomError::Incompatible(_))));
+        assert!(matches!(bloom_filter.intersect_inplace(&other_size), Err(BloomError::Incompatible(_))));
+
+        let mut other_hashes = BloomFilterRS::new(100, 0.01);
+        other_hashes.count_of_hashes += 1;
+        assert!(matches!(bloom_filter.intersect(&other_hashes), Err(BloomError::Incompatible(_))));
+
+        assert_eq!(bloom_filter.to_bytes(), before, "A failed operation should leave the filter untouched");
+    }
+}
+
+#[cfg(test)]
+mod tests_cardinality {
+    use super::*;
+
+    fn assert_close(estimate: f64, actual: f64, tolerance: f64) {
+        assert!(
+            (estimate - actual).abs() <= actual * tolerance,
+            "Estimate {} deviates more than {}% from {}", estimate, tolerance * 100.0, actual
+        );
+    }
+
+    #[test]
+    fn test_estimate_cardinality() {
+        let mut bloom_filter = BloomFilterRS::new(10_000, 0.01);
+        assert_eq!(bloom_filter.estimate_cardinality(), 0.0);
+
+        for i in 0..5_000 {
+            bloom_filter.add(&i).unwrap();
+        }
+        assert_close(bloom_filter.estimate_cardinality(), 5_000.0, 0.03);
+
+        // adding the same items again doesn't change the estimate
+        for i in 0..5_000 {
+            bloom_filter.add(&i).unwrap();
+        }
+        assert_close(bloom_filter.estimate_cardinality(), 5_000.0, 0.03);
+    }
+
+    #[test]
+    fn test_estimate_cardinality_of_full_filter() {
+        let mut bloom_filter = BloomFilterRS::new(1, 0.5);
+        for i in 0..100 {
+            bloom_filter.add(&i).unwrap();
+        }
+        assert_eq!(bloom_filter.estimate_cardinality(), f64::INFINITY);
+    }
+
+    #[test]
+    fn test_estimate_union_and_intersection_size() {
+        let mut first = BloomFilterRS::new(10_000, 0.01);
+        let mut second = BloomFilterRS::new(10_000, 0.01);
+        for i in 0..4_000 {
+            first.add(&i).unwrap();
+        }
+        for i in 2_000..6_000 {
+            second.add(&i).unwrap();
+        }
+
+        assert_close(first.estimate_union_size(&second).unwrap(), 6_000.0, 0.03);
+        assert_close(first.estimate_intersection_size(&second).unwrap(), 2_000.0, 0.1);
+
+        let other_size = BloomFilterRS::new(100, 0.01);
+        assert!(first.estimate_union_size(&other_size).is_err());
+        assert!(first.estimate_intersection_size(&other_size).is_err());
+    }
+}
+
+#[cfg(test)]
+mod tests_fill_ratio {
+    use super::*;
+
+    #[test]
+    fn test_empty_filter() {
+        let bloom_filter = BloomFilterRS::new(1_000, 0.01);
+        assert_eq!(bloom_filter.fill_ratio(), 0.0);
+        assert_eq!(bloom_filter.current_false_positive_rate(), 0.0);
+        assert!(!bloom_filter.is_saturated(0.5));
+    }
+
+    #[test]
+    fn test_current_false_positive_rate_tracks_fill() {
+        let n = 10_000;
+        let p = 0.01;
+        let mut bloom_filter = BloomFilterRS::new(n, p);
+        for i in 0..n / 2 {
+            bloom_filter.add(&i).unwrap();
+        }
+        assert!(bloom_filter.current_false_positive_rate() < p);
+
+        for i in n / 2..n {
+            bloom_filter.add(&i).unwrap();
+        }
+        // at design capacity roughly half the bits are set and the fp-rate matches the design
+        assert!((bloom_filter.fill_ratio() - 0.5).abs() < 0.02);
+        assert!((bloom_filter.current_false_positive_rate() - p).abs() < p * 0.2);
+        assert!(!bloom_filter.is_saturated(DEFAULT_SATURATION_THRESHOLD));
+
+        // overloading the filter pushes the rate above the design rate
+        for i in n..n * 3 {
+            bloom_filter.add(&i).unwrap();
+        }
+        assert!(bloom_filter.current_false_positive_rate() > p * 10.0);
+        assert!(bloom_filter.is_saturated(DEFAULT_SATURATION_THRESHOLD));
+        assert_eq!(bloom_filter.estimate_false_positive_rate(), BloomFilterRS::new(n, p).estimate_false_positive_rate());
+    }
+}
+
+#[cfg(test)]
+mod tests_capacity {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_inserted_count_and_remaining_capacity() {
+        let mut bloom_filter = BloomFilterRS::new(10, 0.01);
+        assert_eq!(bloom_filter.remaining_capacity(), 10);
+
+        bloom_filter.add(&"een").unwrap();
+        bloom_filter.add(&"een").unwrap();
+        bloom_filter.add(&"twee").unwrap();
+        assert_eq!(bloom_filter.get_inserted_count(), 3);
+        assert_eq!(bloom_filter.remaining_capacity(), 7);
+
+        bloom_filter.clear();
+        assert_eq!(bloom_filter.get_inserted_count(), 0);
+    }
+
+    #[test]
+    fn test_count_new_items_only() {
+        let mut bloom_filter = BloomFilterRS::new(10, 0.01);
+        bloom_filter.set_insertion_counting(InsertionCounting::NewItemsOnly);
+        bloom_filter.add(&"een").unwrap();
+        bloom_filter.add(&"een").unwrap();
+        bloom_filter.add(&"twee").unwrap();
+        assert_eq!(bloom_filter.get_inserted_count(), 2);
+    }
+
+    #[test]
+    fn test_overflow_policy_ignore() {
+        let mut bloom_filter = BloomFilterRS::new(2, 0.01);
+        for i in 0..5 {
+            bloom_filter.add(&i).unwrap();
+        }
+        assert_eq!(bloom_filter.get_inserted_count(), 5);
+        assert_eq!(bloom_filter.remaining_capacity(), 0);
+    }
+
+    #[test]
+    fn test_overflow_policy_warn_calls_back_once() {
+        let calls: Arc<Mutex<Vec<(usize, usize)>>> = Arc::new(Mutex::new(Vec::new()));
+        let recorded_calls = calls.clone();
+
+        let mut bloom_filter = BloomFilterRS::new(2, 0.01);
+        bloom_filter.set_overflow_policy(OverflowPolicy::Warn(Arc::new(move |inserted, capacity| {
+            recorded_calls.lock().unwrap().push((inserted, capacity));
+        })));
+        for i in 0..5 {
+            bloom_filter.add(&i).unwrap();
+        }
+        assert_eq!(*calls.lock().unwrap(), vec![(3, 2)]);
+        assert!((0..5).all(|i| bloom_filter.contains(&i)));
+    }
+
+    #[test]
+    fn test_overflow_policy_warn_after_jumping_past_capacity() {
+        let calls: Arc<Mutex<Vec<(usize, usize)>>> = Arc::new(Mutex::new(Vec::new()));
+        let recorded_calls = calls.clone();
+
+        let mut bloom_filter = BloomFilterRS::new(2, 0.01);
+        bloom_filter.set_overflow_policy(OverflowPolicy::Warn(Arc::new(move |inserted, capacity| {
+            recorded_calls.lock().unwrap().push((inserted, capacity));
+        })));
+        let mut other = BloomFilterRS::new(2, 0.01);
+        for i in 0..3 {
+            other.add(&i).unwrap();
+        }
+        bloom_filter.union_inplace(&other).unwrap();
+        bloom_filter.add(&3).unwrap();
+        bloom_filter.add(&4).unwrap();
+        assert_eq!(*calls.lock().unwrap(), vec![(4, 2)]);
+
+        // clearing the filter brings it back under its capacity, so the next overflow warns again
+        bloom_filter.clear();
+        for i in 0..3 {
+            bloom_filter.add(&i).unwrap();
+        }
+        assert_eq!(*calls.lock().unwrap(), vec![(4, 2), (3, 2)]);
+    }
+
+    #[test]
+    fn test_overflow_policy_error() {
+        let mut bloom_filter = BloomFilterRS::new(2, 0.01);
+        bloom_filter.set_overflow_policy(OverflowPolicy::Error);
+        bloom_filter.add(&1).unwrap();
+        bloom_filter.add(&2).unwrap();
+
+        assert_eq!(bloom_filter.add(&3), Err(BloomError::CapacityExceeded { capacity: 2 }));
+        assert!(!bloom_filter.contains(&3), "A refused insert should not set any bits");
+        assert_eq!(bloom_filter.get_inserted_count(), 2);
+
+        // with NewItemsOnly, re-adding a known item doesn't count and is therefore allowed
+        bloom_filter.set_insertion_counting(InsertionCounting::NewItemsOnly);
+        assert_eq!(bloom_filter.add(&1), Ok(()));
+    }
+
+    #[test]
+    fn test_overflow_policy_and_insertion_counting_are_serialized() {
+        let mut bloom_filter = BloomFilterRS::new(2, 0.01);
+        bloom_filter.set_overflow_policy(OverflowPolicy::Error);
+        bloom_filter.set_insertion_counting(InsertionCounting::NewItemsOnly);
+        bloom_filter.add(&1).unwrap();
+        bloom_filter.add(&2).unwrap();
+
+        let mut deserialized = BloomFilterRS::from_bytes(&bloom_filter.to_bytes()).unwrap();
+        assert!(matches!(deserialized.get_overflow_policy(), OverflowPolicy::Error));
+        assert_eq!(deserialized.add(&1), Ok(()), "Re-adding a known item should not count");
+        assert_eq!(deserialized.add(&3), Err(BloomError::CapacityExceeded { capacity: 2 }));
+
+        bloom_filter.set_overflow_policy(OverflowPolicy::Warn(Arc::new(|_, _| {})));
+        let mut deserialized = BloomFilterRS::from_bytes(&bloom_filter.to_bytes()).unwrap();
+        assert!(matches!(deserialized.get_overflow_policy(), OverflowPolicy::Warn(_)));
+        assert_eq!(deserialized.add(&3), Ok(()), "A loaded warn policy should accept the insert without a callback of its own");
+    }
+
+    #[test]
+    fn test_reads_version_3_payload() {
+        let mut bloom_filter = BloomFilterRS::new(2, 0.01);
+        bloom_filter.set_overflow_policy(OverflowPolicy::Error);
+        bloom_filter.add(&"item").unwrap();
+        // a version 3 payload is the version 4 payload without the overflow policy and insertion counting
+        let bytes = bloom_filter.to_bytes();
+        let mut version_3 = bytes[..41].to_vec();
+        version_3[4..6].copy_from_slice(&3u16.to_le_bytes());
+        version_3.extend_from_slice(&bytes[HEADER_SIZE..]);
+
+        let deserialized = BloomFilterRS::from_bytes(&version_3).expect("Version 3 payloads should load");
+        assert!(deserialized.contains(&"item"));
+        assert!(matches!(deserialized.get_overflow_policy(), OverflowPolicy::Ignore));
+    }
+
+    #[test]
+    fn test_inserted_count_is_serialized() {
+        let mut bloom_filter = BloomFilterRS::new(100, 0.01);
+        for i in 0..42 {
+            bloom_filter.add(&i).unwrap();
+        }
+        let deserialized = BloomFilterRS::from_bytes(&bloom_filter.to_bytes()).unwrap();
+        assert_eq!(deserialized.get_inserted_count(), 42);
+    }
+
+    #[test]
+    fn test_reads_version_1_payload() {
+        let mut bloom_filter = BloomFilterRS::new(1000, 0.01);
+        for i in 0..500 {
+            bloom_filter.add(&i).unwrap();
+        }
+        // a version 1 payload is the version 2 payload without the inserted count
+        let bytes = bloom_filter.to_bytes();
+        let mut version_1 = bytes[..32].to_vec();
+        version_1[4..6].copy_from_slice(&1u16.to_le_bytes());
+        version_1.extend_from_slice(&bytes[HEADER_SIZE..]);
+
+        let deserialized = BloomFilterRS::from_bytes(&version_1).expect("Version 1 payloads should load");
+        assert!((0..500).all(|i| deserialized.contains(&i)));
+        assert!((deserialized.get_inserted_count() as f64 - 500.0).abs() < 25.0, "Inserted count should be estimated");
+        assert!(matches!(BloomFilterRS::from_bytes(&version_1[..40]), Err(BloomError::Truncated { .. })));
+    }
+}
@@ -0,0 +1,390 @@
+//! Bloomier filter in Rust (Chazelle et al., "The Bloomier filter", 2004), on the binary fuse layout
+//! An immutable approximate map from keys to small integer values, built once from all of its key-value pairs.
+//! Every key maps to three slots like in `BinaryFuseFilterRS`, and the slots are filled such that the XOR of a key's
+//! three slots and a mask derived from its hash equals its value. Looking up an inserted key always returns its
+//! value; looking up any other key returns an arbitrary value. It takes 1.13 to 1.3 slots per key, fewer for more
+//! keys, each as wide as the values.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use crate::binary_fuse_filter::{calc_fuse_layout, fuse_positions, mix, FuseFingerprint, FusePeeler, ARITY, DEFAULT_MAX_ATTEMPTS, MAX_SEGMENT_LENGTH};
+use crate::error::BloomError;
+use crate::hashing::{splitmix64, AnyHasher, BloomHasher, HashAlgorithm};
+use crate::serialization::{self, ByteReader, ByteWriter};
+
+
+/// Magic number that every serialized BloomierFilterRS starts with
+const MAGIC: &[u8; 4] = b"BLMV";
+/// Version of the binary format that `to_bytes` writes
+const FORMAT_VERSION: u16 = 1;
+
+
+/// A Bloomier Filter; an immutable approximate map that returns the value of every inserted key and an arbitrary
+/// value for every other key
+#[derive(Clone, Debug, PartialEq)]
+pub struct BloomierFilterRS<V: FuseFingerprint = u8> {
+    /// One value per slot; a key's value is the XOR of its three slots and its mask
+    slots: Vec<V>,
+    /// Seed that the successful construction attempt mixed into every key hash
+    seed: u64,
+    /// Number of slots per segment; a power of two
+    segment_length: usize,
+    /// Number of slots in which the first of a key's three segments can start
+    segment_count_length: usize,
+    /// Number of distinct keys the filter was built from
+    item_count: usize,
+    /// The hash function that turns a key into the 64-bit hash the filter works on
+    hasher: AnyHasher,
+}
+
+impl<V: FuseFingerprint> BloomierFilterRS<V> {
+    /// Builds a filter from key-value pairs whose keys are serialized the same way `BloomFilterRS::add` does
+    /// Returns `BloomError::InvalidParameter` if a key is given two different values and
+    /// `BloomError::ConstructionFailed` if no seed out of `DEFAULT_MAX_ATTEMPTS` works
+    ///
+    /// # Arguments
+    /// * `pairs` - all keys the filter should map, with their values; repeated pairs are allowed
+    pub fn from_pairs<K: Serialize>(pairs: &[(K, V)]) -> Result<Self, BloomError> {
+        let hasher = AnyHasher::default();
+        let hashed_pairs = pairs.iter()
+            .map(|(key, value)| (hasher.hash_pair(&serialization::serialize(key)).0, *value))
+            .collect();
+        BloomierFilterRS::from_hashed_pairs(hashed_pairs, hasher, DEFAULT_MAX_ATTEMPTS)
+    }
+
+    /// Builds a filter from key-value pairs with the keys as bytes, the same bytes `BloomFilterRS::add_bytes` takes
+    /// Returns `BloomError::InvalidParameter` if a key is given two different values and
+    /// `BloomError::ConstructionFailed` if no seed out of `DEFAULT_MAX_ATTEMPTS` works
+    ///
+    /// # Arguments
+    /// * `pairs` - all keys the filter should map, with their values; repeated pairs are allowed
+    pub fn from_byte_pairs<K: AsRef<[u8]>>(pairs: &[(K, V)]) -> Result<Self, BloomError> {
+        BloomierFilterRS::from_byte_pairs_with_attempts(pairs, DEFAULT_MAX_ATTEMPTS)
+    }
+
+    /// Builds a filter from key-value pairs with the keys as bytes, trying at most `max_attempts` seeds
+    /// Returns `BloomError::InvalidParameter` if `max_attempts` is 0 or a key is given two different values and
+    /// `BloomError::ConstructionFailed` if no seed works
+    ///
+    /// # Arguments
+    /// * `pairs` - all keys the filter should map, with their values; repeated pairs are allowed
+    /// * `max_attempts` - number of seeds to try before giving up
+    pub fn from_byte_pairs_with_attempts<K: AsRef<[u8]>>(pairs: &[(K, V)], max_attempts: usize) -> Result<Self, BloomError> {
+        let hasher = AnyHasher::default();
+        let hashed_pairs = pairs.iter().map(|(key, value)| (hasher.hash_pair(key.as_ref()).0, *value)).collect();
+        BloomierFilterRS::from_hashed_pairs(hashed_pairs, hasher, max_attempts)
+    }
+
+    /// Looks up the value of given bytes
+    /// Returns the value the key was built with if it was, and an arbitrary value otherwise
+    ///
+    /// # Arguments
+    /// * `hash_bytes` - key to look up
+    pub fn get_bytes(&self, hash_bytes: &[u8]) -> V {
+        let hash = mix(self.hasher.hash_pair(hash_bytes).0.wrapping_add(self.seed));
+        let [h0, h1, h2] = fuse_positions(hash, self.segment_length, self.segment_count_length);
+        V::from_hash(hash) ^ self.slots[h0] ^ self.slots[h1] ^ self.slots[h2]
+    }
+
+    /// Looks up the value of a given item
+    ///
+    /// # Arguments
+    /// * `item` - item to be hashed and looked up
+    pub fn get<T: Serialize>(&self, item: &T) -> V {
+        self.get_bytes(&serialization::serialize(item))
+    }
+
+    /// Calculates the number of bits the filter uses per distinct key
+    pub fn bits_per_item(&self) -> f64 {
+        self.get_bit_count() as f64 / self.item_count.max(1) as f64
+    }
+
+    /// Retrieve the number of distinct keys the filter was built from
+    pub fn get_item_count(&self) -> usize {
+        self.item_count
+    }
+
+    /// Retrieve the number of bits per value
+    pub fn get_value_bits(&self) -> usize {
+        V::BITS
+    }
+
+    /// Retrieve the number of bits in the filter
+    pub fn get_bit_count(&self) -> usize {
+        self.slots.len() * V::BITS
+    }
+
+    /// Serializes the filter to bytes: magic number, version, hash algorithm, value size, seed, layout and the slots
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = ByteWriter::with_capacity(40 + self.get_bit_count() / 8);
+        writer.write_bytes(MAGIC);
+        writer.write_u16(FORMAT_VERSION);
+        writer.write_u8(self.hasher.algorithm().id());
+        writer.write_u8(V::BITS as u8);
+        writer.write_u64(self.seed);
+        writer.write_u64(self.segment_length as u64);
+        writer.write_u64(self.segment_count_length as u64);
+        writer.write_u64(self.item_count as u64);
+        for slot in &self.slots {
+            slot.write(&mut writer);
+        }
+        writer.into_bytes()
+    }
+
+    /// Deserializes a filter written by `to_bytes`
+    /// Returns an error if the payload is truncated, has trailing bytes, a header that doesn't match or a different
+    /// value size than `V`
+    ///
+    /// # Arguments
+    /// * `bytes` - serialized Bloomier filter
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BloomError> {
+        let mut reader = ByteReader::new(bytes);
+        if reader.read_bytes(MAGIC.len())? != MAGIC {
+            return Err(BloomError::InvalidMagic);
+        }
+        let version = reader.read_u16()?;
+        if version != FORMAT_VERSION {
+            return Err(BloomError::UnsupportedVersion(version));
+        }
+        let hash_algorithm = HashAlgorithm::from_id(reader.read_u8()?)?;
+        let value_bits = reader.read_u8()? as usize;
+        if value_bits != V::BITS {
+            return Err(BloomError::InvalidPayload(format!(
+                "payload has {}-bit values, expected {}", value_bits, V::BITS
+            )));
+        }
+        let seed = reader.read_u64()?;
+        let segment_length = reader.read_usize()?;
+        let segment_count_length = reader.read_usize()?;
+        let item_count = reader.read_usize()?;
+        if !segment_length.is_power_of_two() || segment_length > MAX_SEGMENT_LENGTH {
+            return Err(BloomError::InvalidPayload(format!("invalid segment length {}", segment_length)));
+        }
+        if segment_count_length == 0 || !segment_count_length.is_multiple_of(segment_length) {
+            return Err(BloomError::InvalidPayload(format!(
+                "segments start in {} slots, which is not a multiple of the segment length", segment_count_length
+            )));
+        }
+        let array_length = segment_count_length + (ARITY - 1) * segment_length;
+        let num_of_bytes = array_length.saturating_mul(V::BITS / 8);
+        if num_of_bytes > reader.remaining() {
+            return Err(BloomError::Truncated { expected: num_of_bytes, actual: reader.remaining() });
+        }
+        let slots = (0..array_length).map(|_| V::read(&mut reader)).collect::<Result<Vec<V>, BloomError>>()?;
+        reader.finish()?;
+        Ok(BloomierFilterRS {
+            slots,
+            seed,
+            segment_length,
+            segment_count_length,
+            item_count,
+            hasher: AnyHasher::from(hash_algorithm),
+        })
+    }
+
+    /// Builds the filter from 64-bit key hashes and their values, retrying with a new seed whenever peeling fails
+    fn from_hashed_pairs(mut hashed_pairs: Vec<(u64, V)>, hasher: AnyHasher, max_attempts: usize) -> Result<Self, BloomError> {
+        if max_attempts == 0 {
+            return Err(BloomError::InvalidParameter("max attempts must be at least 1".to_string()));
+        }
+        // equal keys hash equally under every seed and would never peel, so they have to agree on their value
+        hashed_pairs.sort_unstable_by_key(|(key_hash, _)| *key_hash);
+        hashed_pairs.dedup_by(|(key_hash, value), (kept_hash, kept_value)| key_hash == kept_hash && value == kept_value);
+        if hashed_pairs.windows(2).any(|pair| pair[0].0 == pair[1].0) {
+            return Err(BloomError::InvalidParameter("a key is given two different values".to_string()));
+        }
+        let key_hashes: Vec<u64> = hashed_pairs.iter().map(|(key_hash, _)| *key_hash).collect();
+        let (segment_length, segment_count_length, array_length) = calc_fuse_layout(key_hashes.len());
+        let mut bloomier_filter = BloomierFilterRS {
+            slots: vec![V::default(); array_length],
+            seed: 0,
+            segment_length,
+            segment_count_length,
+            item_count: key_hashes.len(),
+            hasher,
+        };
+
+        let mut peeler = FusePeeler::new(array_length, key_hashes.len());
+        let mut random_state: u64 = 0x3c6ef372fe94f82b;
+        for _ in 0..max_attempts {
+            let seed = splitmix64(&mut random_state);
+            let Some(peeled) = peeler.peel(&key_hashes, seed, segment_length, segment_count_length) else {
+                continue;
+            };
+            bloomier_filter.seed = seed;
+            let values: HashMap<u64, V> = hashed_pairs.iter()
+                .map(|(key_hash, value)| (mix(key_hash.wrapping_add(seed)), *value))
+                .collect();
+
+            // assign in reverse peeling order: a key's free slot is written after its other two are final
+            for (hash, position) in peeled.iter().rev() {
+                let positions = fuse_positions(*hash, segment_length, segment_count_length);
+                let slot = values[hash]
+                    ^ V::from_hash(*hash)
+                    ^ bloomier_filter.slots[positions[(position + 1) % ARITY]]
+                    ^ bloomier_filter.slots[positions[(position + 2) % ARITY]];
+                bloomier_filter.slots[positions[*position]] = slot;
+            }
+            return Ok(bloomier_filter);
+        }
+        Err(BloomError::ConstructionFailed { attempts: max_attempts })
+    }
+}
+
+
+/// A Bloomier filter with its value size chosen at runtime; this is what the Python wrapper holds
+#[derive(Clone, Debug, PartialEq)]
+pub enum AnyBloomierFilter {
+    Values8(BloomierFilterRS<u8>),
+    Values16(BloomierFilterRS<u16>),
+    Values32(BloomierFilterRS<u32>),
+}
+
+/// Forwards a call to the filter inside an `AnyBloomierFilter`
+macro_rules! dispatch {
+    ($any_filter:expr, $filter:ident => $call:expr) => {
+        match $any_filter {
+            AnyBloomierFilter::Values8($filter) => $call,
+            AnyBloomierFilter::Values16($filter) => $call,
+            AnyBloomierFilter::Values32($filter) => $call,
+        }
+    };
+}
+
+/// Narrows the values of key-value pairs to the value type of a filter
+/// Returns `BloomError::InvalidParameter` if a value doesn't fit
+fn narrow_values<K: AsRef<[u8]>, V: TryFrom<u64>>(pairs: &[(K, u64)], value_bits: usize) -> Result<Vec<(&[u8], V)>, BloomError> {
+    pairs.iter()
+        .map(|(key, value)| match V::try_from(*value) {
+            Ok(narrowed) => Ok((key.as_ref(), narrowed)),
+            Err(_) => Err(BloomError::InvalidParameter(format!("value {} doesn't fit in {} bits", value, value_bits))),
+        })
+        .collect()
+}
+
+impl AnyBloomierFilter {
+    /// Builds a filter from key-value pairs with the keys as bytes and 8-, 16- or 32-bit values
+    /// Returns an error if the value size isn't supported, a value doesn't fit in it or construction fails
+    ///
+    /// # Arguments
+    /// * `pairs` - all keys the filter should map, with their values; repeated pairs are allowed
+    /// * `value_bits` - 8, 16 or 32
+    /// * `max_attempts` - number of seeds to try before giving up
+    pub fn from_byte_pairs<K: AsRef<[u8]>>(pairs: &[(K, u64)], value_bits: usize, max_attempts: usize) -> Result<Self, BloomError> {
+        match value_bits {
+            8 => Ok(AnyBloomierFilter::Values8(BloomierFilterRS::from_byte_pairs_with_attempts(&narrow_values(pairs, 8)?, max_attempts)?)),
+            16 => Ok(AnyBloomierFilter::Values16(BloomierFilterRS::from_byte_pairs_with_attempts(&narrow_values(pairs, 16)?, max_attempts)?)),
+            32 => Ok(AnyBloomierFilter::Values32(BloomierFilterRS::from_byte_pairs_with_attempts(&narrow_values(pairs, 32)?, max_attempts)?)),
+            _ => Err(BloomError::InvalidParameter(format!("value bits must be 8, 16 or 32, got {}", value_bits))),
+        }
+    }
+
+    /// Looks up the value of given bytes; arbitrary for keys the filter wasn't built with
+    ///
+    /// # Arguments
+    /// * `hash_bytes` - key to look up
+    pub fn get_bytes(&self, hash_bytes: &[u8]) -> u64 {
+        dispatch!(self, filter => filter.get_bytes(hash_bytes).into())
+    }
+
+    /// Calculates the number of bits the filter uses per distinct key
+    pub fn bits_per_item(&self) -> f64 {
+        dispatch!(self, filter => filter.bits_per_item())
+    }
+
+    /// Retrieve the number of distinct keys the filter was built from
+    pub fn get_item_count(&self) -> usize {
+        dispatch!(self, filter => filter.get_item_count())
+    }
+
+    /// Retrieve the number of bits per value
+    pub fn get_value_bits(&self) -> usize {
+        dispatch!(self, filter => filter.get_value_bits())
+    }
+
+    /// Retrieve the number of bits in the filter
+    pub fn get_bit_count(&self) -> usize {
+        dispatch!(self, filter => filter.get_bit_count())
+    }
+
+    /// Serializes the filter; see `BloomierFilterRS::to_bytes`
+    pub fn to_bytes(&self) -> Vec<u8> {
+        dispatch!(self, filter => filter.to_bytes())
+    }
+
+    /// Deserializes a filter of any value size written by `BloomierFilterRS::to_bytes`
+    ///
+    /// # Arguments
+    /// * `bytes` - serialized Bloomier filter
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BloomError> {
+        match bytes.get(7) {
+            Some(16) => Ok(AnyBloomierFilter::Values16(BloomierFilterRS::from_bytes(bytes)?)),
+            Some(32) => Ok(AnyBloomierFilter::Values32(BloomierFilterRS::from_bytes(bytes)?)),
+            _ => Ok(AnyBloomierFilter::Values8(BloomierFilterRS::from_bytes(bytes)?)),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_returns_every_value() {
+        let pairs: Vec<(u64, u16)> = (0..10_000).map(|key| (key, (key * 7 % 1000) as u16)).collect();
+        let bloomier_filter = BloomierFilterRS::from_pairs(&pairs).unwrap();
+        assert!(pairs.iter().all(|(key, value)| bloomier_filter.get(key) == *value));
+        assert_eq!(bloomier_filter.get_item_count(), 10_000);
+        assert!(bloomier_filter.bits_per_item() < 16.0 * 1.3, "{} bits per item", bloomier_filter.bits_per_item());
+    }
+
+    #[test]
+    fn test_other_keys_get_arbitrary_values() {
+        let pairs: Vec<(u64, u8)> = (0..10_000).map(|key| (key, 0)).collect();
+        let bloomier_filter = BloomierFilterRS::from_pairs(&pairs).unwrap();
+        let zeros = (10_000..110_000u64).filter(|key| bloomier_filter.get(key) == 0).count();
+        assert!(zeros < 1000, "{} of 100000 other keys got the only inserted value", zeros);
+    }
+
+    #[test]
+    fn test_repeated_and_conflicting_pairs() {
+        let bloomier_filter = BloomierFilterRS::<u8>::from_byte_pairs(&[("a", 1), ("b", 2), ("a", 1)]).unwrap();
+        assert_eq!(bloomier_filter.get_item_count(), 2);
+        assert_eq!(bloomier_filter.get_bytes(b"a"), 1);
+        assert_eq!(bloomier_filter.get_bytes(b"b"), 2);
+        assert!(matches!(
+            BloomierFilterRS::<u8>::from_byte_pairs(&[("a", 1), ("a", 2)]),
+            Err(BloomError::InvalidParameter(_))
+        ));
+        for size in 0..20u32 {
+            let pairs: Vec<(u32, u32)> = (0..size).map(|key| (key, key << 20)).collect();
+            let bloomier_filter = BloomierFilterRS::from_pairs(&pairs).unwrap();
+            assert!(pairs.iter().all(|(key, value)| bloomier_filter.get(key) == *value));
+        }
+    }
+
+    #[test]
+    fn test_any_bloomier_filter() {
+        let pairs: Vec<(Vec<u8>, u64)> = (0..1000u64).map(|key| (key.to_le_bytes().to_vec(), key % 300)).collect();
+        assert!(matches!(AnyBloomierFilter::from_byte_pairs(&pairs, 8, 10), Err(BloomError::InvalidParameter(_))));
+        assert!(matches!(AnyBloomierFilter::from_byte_pairs(&pairs, 12, 10), Err(BloomError::InvalidParameter(_))));
+        assert!(matches!(AnyBloomierFilter::from_byte_pairs(&pairs, 16, 0), Err(BloomError::InvalidParameter(_))));
+        let bloomier_filter = AnyBloomierFilter::from_byte_pairs(&pairs, 16, 10).unwrap();
+        assert_eq!(bloomier_filter.get_value_bits(), 16);
+        assert!(pairs.iter().all(|(key, value)| bloomier_filter.get_bytes(key) == *value));
+    }
+
+    #[test]
+    fn test_serialization_roundtrip() {
+        let pairs: Vec<(Vec<u8>, u64)> = (0..1000u64).map(|key| (key.to_le_bytes().to_vec(), key)).collect();
+        let bloomier_filter = AnyBloomierFilter::from_byte_pairs(&pairs, 32, 10).unwrap();
+        let bytes = bloomier_filter.to_bytes();
+        assert_eq!(&bytes[0..4], MAGIC);
+        let restored = AnyBloomierFilter::from_bytes(&bytes).unwrap();
+        assert_eq!(restored, bloomier_filter);
+        assert!(matches!(BloomierFilterRS::<u8>::from_bytes(&bytes), Err(BloomError::InvalidPayload(_))));
+        assert!(matches!(AnyBloomierFilter::from_bytes(&bytes[..bytes.len() - 1]), Err(BloomError::Truncated { .. })));
+        assert!(matches!(AnyBloomierFilter::from_bytes(b"BLMB"), Err(BloomError::InvalidMagic)));
+    }
+}
@@ -1,63 +1,348 @@
-use std::hash::{Hash, Hasher};
-use std::collections::hash_map::DefaultHasher;
+//! Counting BloomFilter implementation in Rust; supports removing items
 
-struct CountingBloomFilter {
-    filter: Vec<u32>,
-    size: usize,
+use std::f64::consts::LN_2;
+
+use bitvec::prelude::*;
+use serde::Serialize;
+use crate::bloom_filter::{calc_optimal_number_of_bits, calculate_optimal_number_of_hashes, BloomFilterRS};
+use crate::error::BloomError;
+use crate::hashing::{self, AnyHasher, IndexMode};
+use crate::serialization;
+
+
+/// Number of bits each counter of a counting filter occupies
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CounterWidth {
+    /// 4-bit counters, two per byte; the usual choice for counting Bloom filters
+    Four = 4,
+    Eight = 8,
+    Sixteen = 16,
+    ThirtyTwo = 32,
+}
+
+impl CounterWidth {
+    /// Looks up the counter width for a number of bits
+    ///
+    /// # Arguments
+    /// * `bits` - 4, 8, 16 or 32
+    pub fn from_bits(bits: usize) -> Result<Self, BloomError> {
+        match bits {
+            4 => Ok(CounterWidth::Four),
+            8 => Ok(CounterWidth::Eight),
+            16 => Ok(CounterWidth::Sixteen),
+            32 => Ok(CounterWidth::ThirtyTwo),
+            _ => Err(BloomError::InvalidParameter(format!("counter width must be 4, 8, 16 or 32 bits, got {}", bits))),
+        }
+    }
+
+    /// Number of bits per counter
+    pub fn bits(&self) -> usize {
+        *self as usize
+    }
+
+    /// Largest value a counter can hold; a counter that reaches it is saturated
+    pub fn max_value(&self) -> u32 {
+        (u64::MAX >> (64 - self.bits())) as u32
+    }
+}
+
+
+/// How `add` updates the counters of an item
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InsertMode {
+    /// Increment all k counters; items can be removed again
+    Standard,
+    /// Only increment the counters that hold the current minimum (count-min "conservative update").
+    /// Gives tighter `estimate_count` results but items can no longer be removed
+    ConservativeUpdate,
+}
+
+
+/// Fixed-width counters packed back to back in a bit array
+pub(crate) struct PackedCounters {
+    bits: BitVec,
+    width: CounterWidth,
+    len: usize,
+}
+
+impl PackedCounters {
+    pub(crate) fn new(len: usize, width: CounterWidth) -> Self {
+        PackedCounters {
+            bits: BitVec::repeat(false, len * width.bits()),
+            width,
+            len,
+        }
+    }
+
+    pub(crate) fn get(&self, index: usize) -> u32 {
+        let width = self.width.bits();
+        self.bits[index * width..(index + 1) * width].load_le::<u32>()
+    }
+
+    pub(crate) fn set(&mut self, index: usize, value: u32) {
+        let width = self.width.bits();
+        self.bits[index * width..(index + 1) * width].store_le::<u32>(value);
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(crate) fn width(&self) -> CounterWidth {
+        self.width
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = u32> + '_ {
+        (0..self.len).map(move |index| self.get(index))
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.bits.fill(false);
+    }
+}
+
+
+/// A Bloom Filter that keeps a counter per slot instead of a single bit, so items can be removed again
+/// Counters saturate: once a counter reaches its maximum it sticks there and is never decremented again,
+/// which keeps `contains` free of false negatives at the cost of never fully removing items that hit it
+pub struct CountingBloomFilterRS {
+    filter: PackedCounters,
     hash_functions: u32,
+    /// The expected number of items this filter should hold
+    expected_n_items: usize,
+    /// The hash function used to derive the counter indices
+    hasher: AnyHasher,
+    /// How `add` updates the counters
+    insert_mode: InsertMode,
+    /// Number of adds minus the number of successful removes
+    inserted_count: usize,
 }
 
-impl CountingBloomFilter {
-    fn new(size: usize, hash_functions: u32) -> Self {
-        CountingBloomFilter {
-            filter: vec![0; size],
-            size,
-            hash_functions,
+impl CountingBloomFilterRS {
+    /// Creates a counting filter with 4-bit counters, sized like `BloomFilterRS::new`
+    /// Panics if `expected_number_of_items` is 0 or the false positive rate is outside (0, 1); `with_options`
+    /// returns an error instead
+    ///
+    /// # Arguments
+    /// * `expected_number_of_items` - Estimated number of items that the filter should accommodate
+    /// * `desired_false_positive_rate` - Desired/accepted false positive rate
+    pub fn new(expected_number_of_items: usize, desired_false_positive_rate: f64) -> Self {
+        CountingBloomFilterRS::with_counter_width(expected_number_of_items, desired_false_positive_rate, CounterWidth::Four)
+            .expect("invalid counting filter parameters")
+    }
+
+    /// Creates a counting filter sized like `BloomFilterRS::new` with counters of the given width
+    /// Returns an error if `expected_number_of_items` is 0 or the false positive rate is outside (0, 1)
+    ///
+    /// # Arguments
+    /// * `expected_number_of_items` - Estimated number of items that the filter should accommodate
+    /// * `desired_false_positive_rate` - Desired/accepted false positive rate
+    /// * `counter_width` - number of bits per counter
+    pub fn with_counter_width(expected_number_of_items: usize, desired_false_positive_rate: f64, counter_width: CounterWidth) -> Result<Self, BloomError> {
+        CountingBloomFilterRS::with_options(expected_number_of_items, desired_false_positive_rate, counter_width, InsertMode::Standard)
+    }
+
+    /// Creates a counting filter sized like `BloomFilterRS::new` with the given counter width and insert mode
+    /// Returns an error if `expected_number_of_items` is 0 or the false positive rate is outside (0, 1)
+    ///
+    /// # Arguments
+    /// * `expected_number_of_items` - Estimated number of items that the filter should accommodate
+    /// * `desired_false_positive_rate` - Desired/accepted false positive rate
+    /// * `counter_width` - number of bits per counter
+    /// * `insert_mode` - how `add` updates the counters
+    pub fn with_options(expected_number_of_items: usize, desired_false_positive_rate: f64, counter_width: CounterWidth, insert_mode: InsertMode) -> Result<Self, BloomError> {
+        if expected_number_of_items == 0 {
+            return Err(BloomError::InvalidParameter("expected number of items must be > 0".to_string()));
         }
+        if !(desired_false_positive_rate > 0.0 && desired_false_positive_rate < 1.0) {
+            return Err(BloomError::InvalidParameter(format!("false positive rate must be between 0 and 1, got {}", desired_false_positive_rate)));
+        }
+        let size = calc_optimal_number_of_bits(expected_number_of_items, desired_false_positive_rate);
+        let hash_functions = calculate_optimal_number_of_hashes(size, expected_number_of_items);
+        Ok(CountingBloomFilterRS {
+            filter: PackedCounters::new(size, counter_width),
+            hash_functions: hash_functions as u32,
+            expected_n_items: expected_number_of_items,
+            hasher: AnyHasher::default(),
+            insert_mode,
+            inserted_count: 0,
+        })
     }
 
-    fn add<T: Hash>(&mut self, item: &T) {
-        for i in 0..self.hash_functions {
-            let index = self.get_index(item, i);
-            self.filter[index] += 1;
+    /// Creates a counting filter with 4-bit counters and an explicit number of counters and hash functions
+    /// The expected number of items is set to the count for which `hash_functions` is optimal
+    ///
+    /// # Arguments
+    /// * `size` - number of counters
+    /// * `hash_functions` - number of counters each item touches
+    pub fn with_size(size: usize, hash_functions: u32) -> Self {
+        CountingBloomFilterRS {
+            filter: PackedCounters::new(size, CounterWidth::Four),
+            hash_functions,
+            expected_n_items: (size as f64 * LN_2 / hash_functions as f64) as usize,
+            hasher: AnyHasher::default(),
+            insert_mode: InsertMode::Standard,
+            inserted_count: 0,
         }
     }
 
-    fn contains<T: Hash>(&self, item: &T) -> bool {
-        for i in 0..self.hash_functions {
-            let index = self.get_index(item, i);
-            if self.filter[index] == 0 {
-                return false;
+    /// Adds bytes to the filter by incrementing their counters; saturated counters stay at their maximum
+    /// With `InsertMode::ConservativeUpdate` only the counters holding the current minimum are incremented
+    ///
+    /// # Arguments
+    /// * `hash_bytes` - item to insert into the filter
+    pub fn add_bytes(&mut self, hash_bytes: &[u8]) {
+        let max_value = self.filter.width().max_value();
+        // counters above the threshold are left alone
+        let threshold = match self.insert_mode {
+            InsertMode::Standard => max_value,
+            InsertMode::ConservativeUpdate => self.estimate_count_bytes(hash_bytes),
+        };
+        for index in self.indices(hash_bytes) {
+            let counter = self.filter.get(index);
+            if counter < max_value && counter <= threshold {
+                self.filter.set(index, counter + 1);
             }
         }
-        true
+        self.inserted_count += 1;
+    }
+
+    /// Hashes an item to the filter
+    ///
+    /// # Arguments
+    /// * `item` - item to insert into the filter
+    pub fn add<T: Serialize>(&mut self, item: &T) {
+        self.add_bytes(&serialization::serialize(item));
+    }
+
+    /// Checks if the given bytes may be contained by the filter
+    /// Returns boolean: False means definitely not contained, True means maybe contained
+    ///
+    /// # Arguments
+    /// * `hash_bytes` - bytes to check for membership
+    pub fn contains_bytes(&self, hash_bytes: &[u8]) -> bool {
+        self.indices(hash_bytes).all(|index| self.filter.get(index) > 0)
     }
 
-    fn remove<T: Hash>(&mut self, item: &T) {
-        for i in 0..self.hash_functions {
-            let index = self.get_index(item, i);
-            if self.filter[index] > 0 {
-                self.filter[index] -= 1;
+    /// Checks if a given item may be contained by the filter
+    ///
+    /// # Arguments
+    /// * `item` - item to be hashed and checked for membership
+    pub fn contains<T: Serialize>(&self, item: &T) -> bool {
+        self.contains_bytes(&serialization::serialize(item))
+    }
+
+    /// Estimates how many times the given bytes were added: the minimum of their counters
+    /// Never underestimates (unless counters saturated or the item was removed); overestimates on collisions
+    ///
+    /// # Arguments
+    /// * `hash_bytes` - bytes to estimate the count of
+    pub fn estimate_count_bytes(&self, hash_bytes: &[u8]) -> u32 {
+        self.indices(hash_bytes)
+            .map(|index| self.filter.get(index))
+            .min()
+            .unwrap_or(0)
+    }
+
+    /// Estimates how many times an item was added
+    ///
+    /// # Arguments
+    /// * `item` - item to be hashed and counted
+    pub fn estimate_count<T: Serialize>(&self, item: &T) -> u32 {
+        self.estimate_count_bytes(&serialization::serialize(item))
+    }
+
+    /// Removes bytes from the filter by decrementing their counters; saturated counters are left alone
+    /// Returns false, without touching any counter, if one of the counters is already zero:
+    /// the item was never added, or an earlier remove was wrong.
+    /// Always returns false for filters using `InsertMode::ConservativeUpdate`; decrementing their
+    /// counters would introduce false negatives
+    ///
+    /// # Arguments
+    /// * `hash_bytes` - item to remove from the filter
+    pub fn remove_bytes(&mut self, hash_bytes: &[u8]) -> bool {
+        if self.insert_mode == InsertMode::ConservativeUpdate || !self.contains_bytes(hash_bytes) {
+            return false;
+        }
+
+        let max_value = self.filter.width().max_value();
+        for index in self.indices(hash_bytes) {
+            let counter = self.filter.get(index);
+            if counter < max_value {
+                self.filter.set(index, counter - 1);
             }
         }
+        self.inserted_count = self.inserted_count.saturating_sub(1);
+        true
+    }
+
+    /// Removes an item from the filter
+    /// Returns false if the item could not have been in the filter
+    ///
+    /// # Arguments
+    /// * `item` - item to be hashed and removed
+    pub fn remove<T: Serialize>(&mut self, item: &T) -> bool {
+        self.remove_bytes(&serialization::serialize(item))
+    }
+
+    /// Resets all counters to zero
+    pub fn clear(&mut self) {
+        self.filter.clear();
+        self.inserted_count = 0;
+    }
+
+    /// Retrieve the number of items in the filter: adds minus successful removes
+    pub fn get_inserted_count(&self) -> usize {
+        self.inserted_count
+    }
+
+    /// Retrieve the number of hashes this filter uses
+    pub fn get_hash_count(&self) -> usize {
+        self.hash_functions as usize
+    }
+
+    /// Retrieve the number of counters this filter uses
+    pub fn get_counter_count(&self) -> usize {
+        self.filter.len()
+    }
+
+    /// Retrieve the width of the counters
+    pub fn get_counter_width(&self) -> CounterWidth {
+        self.filter.width()
+    }
+
+    /// Retrieve how `add` updates the counters
+    pub fn get_insert_mode(&self) -> InsertMode {
+        self.insert_mode
+    }
+
+    /// Retrieve the number of counters that reached their maximum value
+    pub fn get_saturated_counter_count(&self) -> usize {
+        let max_value = self.filter.width().max_value();
+        self.filter.iter().filter(|counter| *counter == max_value).count()
+    }
+
+    /// Converts to a plain `BloomFilterRS`; a bit is set wherever a counter is > 0
+    /// The result answers `contains` exactly like this filter and can be combined with filters built directly
+    pub fn to_bloom_filter(&self) -> BloomFilterRS {
+        let bit_array: BitVec = self.filter.iter().map(|counter| counter > 0).collect();
+        BloomFilterRS::from_parts(bit_array, self.hash_functions as usize, self.expected_n_items, self.hasher, self.inserted_count)
     }
 
-    fn get_index<T: Hash>(&self, item: &T, i: u32) -> usize {
-        let mut hasher = DefaultHasher::new();
-        item.hash(&mut hasher);
-        let hash = hasher.finish();
-        ((hash.wrapping_add(i as u64).wrapping_add(i.pow(2) as u64)) as usize) % self.size
+    fn indices<'a>(&self, hash_bytes: &'a [u8]) -> impl Iterator<Item = usize> + 'a {
+        hashing::indices(self.hasher, IndexMode::Rehash, hash_bytes, self.hash_functions as usize, self.filter.len())
     }
 }
 
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_add_and_check() {
-        let mut bloom_filter = CountingBloomFilter::new(100, 3);
+        let mut bloom_filter = CountingBloomFilterRS::with_size(100, 3);
         assert_eq!(bloom_filter.contains(&"item1"), false);
         bloom_filter.add(&"item1");
         assert_eq!(bloom_filter.contains(&"item1"), true);
@@ -65,7 +350,7 @@ mod tests {
 
     #[test]
     fn test_remove() {
-        let mut bloom_filter = CountingBloomFilter::new(100, 3);
+        let mut bloom_filter = CountingBloomFilterRS::with_size(100, 3);
         bloom_filter.add(&"item1");
         assert_eq!(bloom_filter.contains(&"item1"), true);
         bloom_filter.remove(&"item1");
@@ -74,7 +359,7 @@ mod tests {
 
     #[test]
     fn test_false_positive() {
-        let mut bloom_filter = CountingBloomFilter::new(100, 3);
+        let mut bloom_filter = CountingBloomFilterRS::with_size(100, 3);
         bloom_filter.add(&"item1");
         // Note: This test may fail due to the probabilistic nature of Bloom filters
         assert_eq!(bloom_filter.contains(&"item2"), false);
@@ -82,7 +367,7 @@ mod tests {
 
     #[test]
     fn test_multiple_items() {
-        let mut bloom_filter = CountingBloomFilter::new(100, 3);
+        let mut bloom_filter = CountingBloomFilterRS::with_size(100, 3);
         bloom_filter.add(&"item1");
         bloom_filter.add(&"item2");
         assert_eq!(bloom_filter.contains(&"item1"), true);
@@ -91,4 +376,193 @@ mod tests {
         assert_eq!(bloom_filter.contains(&"item1"), false);
         assert_eq!(bloom_filter.contains(&"item2"), true);
     }
+
+    #[test]
+    fn test_sized_like_bloom_filter() {
+        let bloom_filter = CountingBloomFilterRS::new(1000, 0.01);
+        assert_eq!(bloom_filter.get_counter_count(), calc_optimal_number_of_bits(1000, 0.01));
+        assert_eq!(bloom_filter.get_hash_count(), calculate_optimal_number_of_hashes(bloom_filter.get_counter_count(), 1000));
+    }
+
+    #[test]
+    fn test_converts_to_identical_bloom_filter() {
+        let mut counting_filter = CountingBloomFilterRS::new(500, 0.01);
+        let mut bloom_filter = BloomFilterRS::new(500, 0.01);
+        for i in 0..500 {
+            counting_filter.add(&i);
+            bloom_filter.add(&i).unwrap();
+        }
+        // removing an item again should leave the bits of other items intact
+        counting_filter.add(&"removed");
+        counting_filter.remove(&"removed");
+
+        assert_eq!(counting_filter.get_inserted_count(), 500);
+        let converted = counting_filter.to_bloom_filter();
+        assert_eq!(converted.to_bytes(), bloom_filter.to_bytes(), "Conversion should be bit-for-bit identical");
+        assert!((0..500).all(|i| converted.contains(&i)));
+    }
+
+    #[test]
+    fn test_false_positive_rate_matches_theory() {
+        let n = 10_000;
+        let p = 0.05;
+        let mut bloom_filter = CountingBloomFilterRS::new(n, p);
+        for i in 0..n {
+            bloom_filter.add(&i);
+        }
+        let fp_count_observed = (n..n * 2).filter(|i| bloom_filter.contains(i)).count();
+        let fp_count_expected = n as f64 * p;
+        assert!(
+            (fp_count_observed as f64 - fp_count_expected).abs() <= fp_count_expected * 0.15,
+            "Observed {} false positives, expected around {}", fp_count_observed, fp_count_expected
+        );
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut bloom_filter = CountingBloomFilterRS::new(100, 0.01);
+        bloom_filter.add(&"item1");
+        bloom_filter.add(&"item1");
+        bloom_filter.clear();
+        assert!(!bloom_filter.contains(&"item1"));
+    }
+}
+
+#[cfg(test)]
+mod tests_counter_width {
+    use super::*;
+
+    #[test]
+    fn test_rejects_invalid_parameters() {
+        for desired_false_positive_rate in [0.0, 1.0, -0.1, f64::NAN] {
+            let counting_filter = CountingBloomFilterRS::with_options(10, desired_false_positive_rate, CounterWidth::Four, InsertMode::Standard);
+            assert!(matches!(counting_filter, Err(BloomError::InvalidParameter(_))));
+        }
+        assert!(matches!(CountingBloomFilterRS::with_counter_width(0, 0.01, CounterWidth::Four), Err(BloomError::InvalidParameter(_))));
+    }
+
+    #[test]
+    fn test_counter_width_from_bits() {
+        assert_eq!(CounterWidth::from_bits(4), Ok(CounterWidth::Four));
+        assert_eq!(CounterWidth::from_bits(32), Ok(CounterWidth::ThirtyTwo));
+        assert!(matches!(CounterWidth::from_bits(5), Err(BloomError::InvalidParameter(_))));
+        assert_eq!(CounterWidth::Four.max_value(), 15);
+        assert_eq!(CounterWidth::Sixteen.max_value(), 65_535);
+        assert_eq!(CounterWidth::ThirtyTwo.max_value(), u32::MAX);
+    }
+
+    #[test]
+    fn test_packed_counters_do_not_overlap() {
+        for width in [CounterWidth::Four, CounterWidth::Eight, CounterWidth::Sixteen, CounterWidth::ThirtyTwo] {
+            let mut counters = PackedCounters::new(10, width);
+            for index in 0..10 {
+                counters.set(index, width.max_value() - index as u32);
+            }
+            for index in 0..10 {
+                assert_eq!(counters.get(index), width.max_value() - index as u32, "width {:?}", width);
+            }
+        }
+    }
+
+    #[test]
+    fn test_counters_saturate_instead_of_overflowing() {
+        let mut bloom_filter = CountingBloomFilterRS::with_size(64, 2);
+        for _ in 0..100 {
+            bloom_filter.add(&"item1");
+        }
+        assert_eq!(bloom_filter.get_saturated_counter_count(), 2);
+
+        // saturated counters are sticky, so the item can never be removed completely
+        for _ in 0..100 {
+            assert!(bloom_filter.remove(&"item1"));
+        }
+        assert!(bloom_filter.contains(&"item1"));
+    }
+
+    #[test]
+    fn test_wider_counters_saturate_later() {
+        let mut bloom_filter = CountingBloomFilterRS::with_counter_width(100, 0.01, CounterWidth::Eight).unwrap();
+        for _ in 0..100 {
+            bloom_filter.add(&"item1");
+        }
+        assert_eq!(bloom_filter.get_saturated_counter_count(), 0);
+        for _ in 0..100 {
+            assert!(bloom_filter.remove(&"item1"));
+        }
+        assert!(!bloom_filter.contains(&"item1"));
+    }
+
+    #[test]
+    fn test_remove_detects_items_that_were_never_added() {
+        let mut bloom_filter = CountingBloomFilterRS::new(100, 0.01);
+        bloom_filter.add(&"item1");
+
+        assert!(!bloom_filter.remove(&"item2"), "Removing an item that was never added should fail");
+        assert!(bloom_filter.remove(&"item1"));
+        assert!(!bloom_filter.remove(&"item1"), "Removing an item twice should fail");
+        assert_eq!(bloom_filter.to_bloom_filter().to_bytes(), CountingBloomFilterRS::new(100, 0.01).to_bloom_filter().to_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests_estimate_count {
+    use super::*;
+
+    #[test]
+    fn test_estimate_count() {
+        let mut bloom_filter = CountingBloomFilterRS::with_counter_width(100, 0.01, CounterWidth::Eight).unwrap();
+        for _ in 0..5 {
+            bloom_filter.add(&"five");
+        }
+        bloom_filter.add(&"one");
+
+        assert_eq!(bloom_filter.estimate_count(&"five"), 5);
+        assert_eq!(bloom_filter.estimate_count(&"one"), 1);
+        assert_eq!(bloom_filter.estimate_count(&"zero"), 0);
+
+        bloom_filter.remove(&"five");
+        assert_eq!(bloom_filter.estimate_count(&"five"), 4);
+    }
+
+    #[test]
+    fn test_estimate_count_caps_at_saturation() {
+        let mut bloom_filter = CountingBloomFilterRS::new(100, 0.01);
+        for _ in 0..20 {
+            bloom_filter.add(&"item");
+        }
+        assert_eq!(bloom_filter.estimate_count(&"item"), CounterWidth::Four.max_value());
+    }
+
+    #[test]
+    fn test_conservative_update_never_underestimates_and_is_tighter() {
+        // undersized on purpose so that counters are shared between many items
+        let mut standard = CountingBloomFilterRS::with_options(100, 0.1, CounterWidth::Sixteen, InsertMode::Standard).unwrap();
+        let mut conservative = CountingBloomFilterRS::with_options(100, 0.1, CounterWidth::Sixteen, InsertMode::ConservativeUpdate).unwrap();
+        for i in 0..1_000u32 {
+            for _ in 0..(i % 7) {
+                standard.add(&i);
+                conservative.add(&i);
+            }
+        }
+
+        let mut standard_error = 0;
+        let mut conservative_error = 0;
+        for i in 0..1_000u32 {
+            let true_count = i % 7;
+            assert!(standard.estimate_count(&i) >= true_count);
+            assert!(conservative.estimate_count(&i) >= true_count);
+            assert!(conservative.estimate_count(&i) <= standard.estimate_count(&i));
+            standard_error += standard.estimate_count(&i) - true_count;
+            conservative_error += conservative.estimate_count(&i) - true_count;
+        }
+        assert!(conservative_error < standard_error);
+    }
+
+    #[test]
+    fn test_conservative_update_refuses_remove() {
+        let mut bloom_filter = CountingBloomFilterRS::with_options(100, 0.01, CounterWidth::Four, InsertMode::ConservativeUpdate).unwrap();
+        bloom_filter.add(&"item");
+        assert!(!bloom_filter.remove(&"item"));
+        assert!(bloom_filter.contains(&"item"));
+    }
 }
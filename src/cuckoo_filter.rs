@@ -0,0 +1,499 @@
+//! Cuckoo filter in Rust (Fan et al., "Cuckoo Filter: Practically Better Than Bloom", 2014)
+//! Stores a short fingerprint of every item in one of two candidate buckets. The second bucket is derived from the
+//! first and the fingerprint alone (partial-key cuckoo hashing), so fingerprints can be moved between their two
+//! buckets to make room without knowing the original items. Items can be removed, and below a false positive rate
+//! of about 3% the filter needs fewer bits per item than a Bloom filter.
+
+use serde::Serialize;
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use bitvec::prelude::*;
+use crate::error::BloomError;
+use crate::hashing::{reduce, splitmix64, AnyHasher, BloomHasher, HashAlgorithm};
+use crate::serialization::{self, ByteReader, ByteWriter};
+
+
+/// Magic number that every serialized CuckooFilterRS starts with
+const MAGIC: &[u8; 4] = b"BLMC";
+/// Version of the binary format that `to_bytes` writes
+const FORMAT_VERSION: u16 = 1;
+/// Bucket size used by `new`
+pub const DEFAULT_BUCKET_SIZE: usize = 4;
+/// Number of relocations `add` tries before it reports the filter as full, used by `new`
+pub const DEFAULT_MAX_KICKS: usize = 500;
+
+
+/// Fraction of the slots that can be filled before inserts start to fail, by bucket size (Fan et al., table 2)
+/// Returns the load factor for `bucket_size` slots per bucket
+///
+/// # Arguments
+/// * `bucket_size` - number of slots per bucket
+pub fn expected_load_factor(bucket_size: usize) -> f64 {
+    match bucket_size {
+        1 => 0.5,
+        2 => 0.84,
+        3 => 0.91,
+        4..=7 => 0.95,
+        _ => 0.98,
+    }
+}
+
+/// Calculates the number of fingerprint bits needed for a false positive rate
+/// This is calculated by `f = ceil(log2(2b / p))`; a lookup compares against at most `2b` fingerprints
+///
+/// # Arguments
+/// * `bucket_size` - number of slots per bucket (b)
+/// * `desired_false_positive_rate` - Desired/accepted false positive rate (p)
+pub fn calc_fingerprint_bits(bucket_size: usize, desired_false_positive_rate: f64) -> usize {
+    ((2.0 * bucket_size as f64 / desired_false_positive_rate).log2().ceil() as usize).clamp(1, 32)
+}
+
+
+/// A Cuckoo Filter; an approximate set that supports removing items
+#[derive(Clone)]
+pub struct CuckooFilterRS {
+    /// The fingerprints, `fingerprint_bits` per slot and `bucket_size` slots per bucket; 0 marks an empty slot
+    slots: BitVec,
+    /// Number of buckets; a power of two
+    num_of_buckets: usize,
+    /// Number of slots per bucket
+    bucket_size: usize,
+    /// Number of bits per fingerprint
+    fingerprint_bits: usize,
+    /// Number of relocations `add` tries before it gives up
+    max_kicks: usize,
+    /// Number of fingerprints stored
+    inserted_count: usize,
+    /// The hash function; its first hash picks the bucket, the second the fingerprint
+    hasher: AnyHasher,
+    /// State of the random generator that picks the fingerprints to relocate
+    random_state: u64,
+}
+
+impl CuckooFilterRS {
+    /// Creates a cuckoo filter with `DEFAULT_BUCKET_SIZE` slots per bucket and the fingerprint size from
+    /// `calc_fingerprint_bits`
+    ///
+    /// # Arguments
+    /// * `expected_number_of_items` - Estimated number of items that the filter should accommodate
+    /// * `desired_false_positive_rate` - Desired/accepted false positive rate
+    pub fn new(expected_number_of_items: usize, desired_false_positive_rate: f64) -> Self {
+        let fingerprint_bits = calc_fingerprint_bits(DEFAULT_BUCKET_SIZE, desired_false_positive_rate);
+        CuckooFilterRS::with_parameters(expected_number_of_items, fingerprint_bits, DEFAULT_BUCKET_SIZE, DEFAULT_MAX_KICKS)
+            .expect("default parameters are valid")
+    }
+
+    /// Creates a cuckoo filter with explicit parameters
+    /// The number of buckets is the power of two that holds `expected_number_of_items` at the expected load factor
+    /// Returns an error if a parameter is out of range
+    ///
+    /// # Arguments
+    /// * `expected_number_of_items` - Estimated number of items that the filter should accommodate
+    /// * `fingerprint_bits` - number of bits per fingerprint (1..=32); every bit halves the false positive rate
+    /// * `bucket_size` - number of slots per bucket (1..=8); larger buckets fill up further but need more bits
+    /// * `max_kicks` - number of relocations `add` tries before it reports the filter as full
+    pub fn with_parameters(expected_number_of_items: usize, fingerprint_bits: usize, bucket_size: usize, max_kicks: usize) -> Result<Self, BloomError> {
+        if !(1..=32).contains(&fingerprint_bits) {
+            return Err(BloomError::InvalidParameter(format!("fingerprint bits must be between 1 and 32, got {}", fingerprint_bits)));
+        }
+        if !(1..=8).contains(&bucket_size) {
+            return Err(BloomError::InvalidParameter(format!("bucket size must be between 1 and 8, got {}", bucket_size)));
+        }
+        let needed_buckets = (expected_number_of_items as f64 / (bucket_size as f64 * expected_load_factor(bucket_size))).ceil() as usize;
+        let num_of_buckets = needed_buckets.max(1).next_power_of_two();
+        Ok(CuckooFilterRS {
+            slots: BitVec::repeat(false, num_of_buckets * bucket_size * fingerprint_bits),
+            num_of_buckets,
+            bucket_size,
+            fingerprint_bits,
+            max_kicks,
+            inserted_count: 0,
+            hasher: AnyHasher::default(),
+            random_state: RandomState::new().hash_one(0),
+        })
+    }
+
+    /// Seeds the random generator that picks the fingerprints to relocate, to make the filter deterministic
+    ///
+    /// # Arguments
+    /// * `seed` - any value; filters with the same seed and inserts end up identical
+    pub fn set_seed(&mut self, seed: u64) {
+        self.random_state = seed;
+    }
+
+    /// Adds bytes to the filter, relocating up to `max_kicks` fingerprints to make room
+    /// Adding an item twice stores it twice; it then has to be removed twice
+    /// Returns `BloomError::CapacityExceeded` if no room was found; the filter is left untouched in that case
+    ///
+    /// # Arguments
+    /// * `hash_bytes` - item to insert into the filter
+    pub fn add_bytes(&mut self, hash_bytes: &[u8]) -> Result<(), BloomError> {
+        let (first_bucket, fingerprint) = self.locate(hash_bytes);
+        let second_bucket = self.alternate_bucket(first_bucket, fingerprint);
+        for bucket in [first_bucket, second_bucket] {
+            if let Some(slot) = self.find_slot(bucket, 0) {
+                self.set_fingerprint(bucket, slot, fingerprint);
+                self.inserted_count += 1;
+                return Ok(());
+            }
+        }
+
+        // evict a random fingerprint and move it to its other bucket, until one lands in an empty slot
+        let mut evictions: Vec<(usize, usize, u32)> = Vec::new();
+        let mut fingerprint = fingerprint;
+        let mut bucket = match splitmix64(&mut self.random_state) & 1 {
+            0 => first_bucket,
+            _ => second_bucket,
+        };
+        for _ in 0..self.max_kicks {
+            let slot = reduce(splitmix64(&mut self.random_state), self.bucket_size);
+            let evicted = self.get_fingerprint(bucket, slot);
+            self.set_fingerprint(bucket, slot, fingerprint);
+            evictions.push((bucket, slot, evicted));
+            fingerprint = evicted;
+            bucket = self.alternate_bucket(bucket, fingerprint);
+            if let Some(slot) = self.find_slot(bucket, 0) {
+                self.set_fingerprint(bucket, slot, fingerprint);
+                self.inserted_count += 1;
+                return Ok(());
+            }
+        }
+
+        // undo the evictions so no item is lost
+        for (bucket, slot, evicted) in evictions.into_iter().rev() {
+            self.set_fingerprint(bucket, slot, evicted);
+        }
+        Err(BloomError::CapacityExceeded { capacity: self.get_capacity() })
+    }
+
+    /// Hashes an item to the filter
+    /// Returns `BloomError::CapacityExceeded` if the filter is full
+    ///
+    /// # Arguments
+    /// * `item` - item to insert into the filter
+    pub fn add<T: Serialize>(&mut self, item: &T) -> Result<(), BloomError> {
+        self.add_bytes(&serialization::serialize(item))
+    }
+
+    /// Checks if given bytes may be contained by the filter
+    /// Returns boolean: False means that the item definitely isn't contained.
+    /// True means that the item may be contained in the filter
+    ///
+    /// # Arguments
+    /// * `hash_bytes` - bytes to check for membership
+    pub fn contains_bytes(&self, hash_bytes: &[u8]) -> bool {
+        let (first_bucket, fingerprint) = self.locate(hash_bytes);
+        let second_bucket = self.alternate_bucket(first_bucket, fingerprint);
+        self.find_slot(first_bucket, fingerprint).is_some() || self.find_slot(second_bucket, fingerprint).is_some()
+    }
+
+    /// Checks if a given item may be contained by the filter
+    ///
+    /// # Arguments
+    /// * `item` - item to be hashed and checked for membership
+    pub fn contains<T: Serialize>(&self, item: &T) -> bool {
+        self.contains_bytes(&serialization::serialize(item))
+    }
+
+    /// Removes bytes that were added before by deleting one copy of their fingerprint
+    /// Only remove items that were actually added: removing an item that merely shares a fingerprint with an added
+    /// item removes that item instead
+    /// Returns false (and leaves the filter untouched) when the item isn't contained
+    ///
+    /// # Arguments
+    /// * `hash_bytes` - item to remove from the filter
+    pub fn remove_bytes(&mut self, hash_bytes: &[u8]) -> bool {
+        let (first_bucket, fingerprint) = self.locate(hash_bytes);
+        let second_bucket = self.alternate_bucket(first_bucket, fingerprint);
+        for bucket in [first_bucket, second_bucket] {
+            if let Some(slot) = self.find_slot(bucket, fingerprint) {
+                self.set_fingerprint(bucket, slot, 0);
+                self.inserted_count -= 1;
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Removes an item that was added before
+    ///
+    /// # Arguments
+    /// * `item` - item to remove from the filter
+    pub fn remove<T: Serialize>(&mut self, item: &T) -> bool {
+        self.remove_bytes(&serialization::serialize(item))
+    }
+
+    /// Clears the filter
+    pub fn clear(&mut self) {
+        self.slots.fill(false);
+        self.inserted_count = 0;
+    }
+
+    /// Calculates the upper bound of the false positive rate, reached when every slot is filled
+    /// This is calculated by `1 - (1 - 1 / 2^f)^(2b)`
+    pub fn estimate_false_positive_rate(&self) -> f64 {
+        let fingerprint_collision = 1.0 / 2.0_f64.powi(self.fingerprint_bits as i32);
+        1.0 - (1.0 - fingerprint_collision).powi(2 * self.bucket_size as i32)
+    }
+
+    /// Calculates the fraction of slots that hold a fingerprint
+    pub fn load_factor(&self) -> f64 {
+        self.inserted_count as f64 / self.get_capacity() as f64
+    }
+
+    /// Retrieve the number of items in the filter
+    pub fn get_inserted_count(&self) -> usize {
+        self.inserted_count
+    }
+
+    /// Retrieve the number of slots
+    pub fn get_capacity(&self) -> usize {
+        self.num_of_buckets * self.bucket_size
+    }
+
+    /// Retrieve the number of buckets
+    pub fn get_bucket_count(&self) -> usize {
+        self.num_of_buckets
+    }
+
+    /// Retrieve the number of slots per bucket
+    pub fn get_bucket_size(&self) -> usize {
+        self.bucket_size
+    }
+
+    /// Retrieve the number of bits per fingerprint
+    pub fn get_fingerprint_bits(&self) -> usize {
+        self.fingerprint_bits
+    }
+
+    /// Retrieve the number of relocations `add` tries before it gives up
+    pub fn get_max_kicks(&self) -> usize {
+        self.max_kicks
+    }
+
+    /// Retrieve the number of bits in the filter
+    pub fn get_bit_count(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Serializes the filter to bytes: magic number, version, hash algorithm, parameters and the packed slots
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let packed_slots = serialization::pack_bits(&self.slots);
+        let mut writer = ByteWriter::with_capacity(33 + packed_slots.len());
+        writer.write_bytes(MAGIC);
+        writer.write_u16(FORMAT_VERSION);
+        writer.write_u8(self.hasher.algorithm().id());
+        writer.write_u8(self.fingerprint_bits as u8);
+        writer.write_u8(self.bucket_size as u8);
+        writer.write_u64(self.num_of_buckets as u64);
+        writer.write_u64(self.max_kicks as u64);
+        writer.write_u64(self.inserted_count as u64);
+        writer.write_bytes(&packed_slots);
+        writer.into_bytes()
+    }
+
+    /// Deserializes a filter written by `to_bytes`
+    /// Returns an error if the payload is truncated, has trailing bytes or a header that doesn't match
+    ///
+    /// # Arguments
+    /// * `bytes` - serialized cuckoo filter
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BloomError> {
+        let mut reader = ByteReader::new(bytes);
+        if reader.read_bytes(MAGIC.len())? != MAGIC {
+            return Err(BloomError::InvalidMagic);
+        }
+        let version = reader.read_u16()?;
+        if version != FORMAT_VERSION {
+            return Err(BloomError::UnsupportedVersion(version));
+        }
+        let hash_algorithm = HashAlgorithm::from_id(reader.read_u8()?)?;
+        let fingerprint_bits = reader.read_u8()? as usize;
+        let bucket_size = reader.read_u8()? as usize;
+        let num_of_buckets = reader.read_usize()?;
+        let max_kicks = reader.read_usize()?;
+        let inserted_count = reader.read_usize()?;
+        if !num_of_buckets.is_power_of_two() {
+            return Err(BloomError::InvalidPayload(format!("number of buckets {} is not a power of two", num_of_buckets)));
+        }
+
+        let mut cuckoo_filter = CuckooFilterRS::with_parameters(0, fingerprint_bits, bucket_size, max_kicks)
+            .map_err(|err| BloomError::InvalidPayload(err.to_string()))?;
+        let num_of_bits = num_of_buckets
+            .checked_mul(bucket_size * fingerprint_bits)
+            .ok_or_else(|| BloomError::InvalidPayload(format!("{} buckets is too many", num_of_buckets)))?;
+        cuckoo_filter.slots = serialization::unpack_bits(reader.read_bytes(num_of_bits.div_ceil(8))?, num_of_bits)?;
+        reader.finish()?;
+        cuckoo_filter.num_of_buckets = num_of_buckets;
+        cuckoo_filter.hasher = AnyHasher::from(hash_algorithm);
+        cuckoo_filter.inserted_count = inserted_count;
+        if cuckoo_filter.count_fingerprints() != inserted_count {
+            return Err(BloomError::InvalidPayload("inserted count does not match the stored fingerprints".to_string()));
+        }
+        Ok(cuckoo_filter)
+    }
+
+    /// Derives the first bucket and the (non-zero) fingerprint of an item
+    fn locate(&self, hash_bytes: &[u8]) -> (usize, u32) {
+        let (h1, h2) = self.hasher.hash_pair(hash_bytes);
+        let fingerprint = (h2 >> (64 - self.fingerprint_bits)) as u32;
+        // 0 marks an empty slot
+        let fingerprint = fingerprint.max(1);
+        (h1 as usize & (self.num_of_buckets - 1), fingerprint)
+    }
+
+    /// The other bucket a fingerprint can live in; applying it twice returns the original bucket
+    fn alternate_bucket(&self, bucket: usize, fingerprint: u32) -> usize {
+        let fingerprint_hash = (fingerprint as u64).wrapping_mul(0x5bd1e995_u64) ^ 0x9e3779b97f4a7c15;
+        (bucket ^ fingerprint_hash as usize) & (self.num_of_buckets - 1)
+    }
+
+    /// First slot in a bucket that holds the given fingerprint (0 finds an empty slot)
+    fn find_slot(&self, bucket: usize, fingerprint: u32) -> Option<usize> {
+        (0..self.bucket_size).find(|slot| self.get_fingerprint(bucket, *slot) == fingerprint)
+    }
+
+    fn get_fingerprint(&self, bucket: usize, slot: usize) -> u32 {
+        let start = (bucket * self.bucket_size + slot) * self.fingerprint_bits;
+        self.slots[start..start + self.fingerprint_bits].load_le::<u32>()
+    }
+
+    fn set_fingerprint(&mut self, bucket: usize, slot: usize, fingerprint: u32) {
+        let start = (bucket * self.bucket_size + slot) * self.fingerprint_bits;
+        self.slots[start..start + self.fingerprint_bits].store_le::<u32>(fingerprint);
+    }
+
+    fn count_fingerprints(&self) -> usize {
+        (0..self.num_of_buckets)
+            .flat_map(|bucket| (0..self.bucket_size).map(move |slot| (bucket, slot)))
+            .filter(|(bucket, slot)| self.get_fingerprint(*bucket, *slot) != 0)
+            .count()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seeded(expected_number_of_items: usize, desired_false_positive_rate: f64) -> CuckooFilterRS {
+        let mut cuckoo_filter = CuckooFilterRS::new(expected_number_of_items, desired_false_positive_rate);
+        cuckoo_filter.set_seed(42);
+        cuckoo_filter
+    }
+
+    #[test]
+    fn test_add_contains_and_remove() {
+        let mut cuckoo_filter = seeded(1_000, 0.01);
+        for i in 0..1_000 {
+            cuckoo_filter.add(&i).unwrap();
+        }
+        assert!((0..1_000).all(|i| cuckoo_filter.contains(&i)), "Cuckoo filter should have no false negatives");
+        assert_eq!(cuckoo_filter.get_inserted_count(), 1_000);
+        for i in 0..500 {
+            assert!(cuckoo_filter.remove(&i));
+        }
+        assert!((500..1_000).all(|i| cuckoo_filter.contains(&i)));
+        assert!((0..500).filter(|i| cuckoo_filter.contains(i)).count() < 20);
+        assert_eq!(cuckoo_filter.get_inserted_count(), 500);
+    }
+
+    #[test]
+    fn test_duplicates_need_as_many_removes() {
+        let mut cuckoo_filter = seeded(100, 0.01);
+        cuckoo_filter.add(&"item").unwrap();
+        cuckoo_filter.add(&"item").unwrap();
+        assert!(cuckoo_filter.remove(&"item"));
+        assert!(cuckoo_filter.contains(&"item"));
+        assert!(cuckoo_filter.remove(&"item"));
+        assert!(!cuckoo_filter.contains(&"item"));
+        assert!(!cuckoo_filter.remove(&"item"));
+    }
+
+    #[test]
+    fn test_alternate_bucket_is_an_involution() {
+        let cuckoo_filter = seeded(10_000, 0.01);
+        for bucket in 0..cuckoo_filter.get_bucket_count() {
+            for fingerprint in [1, 2, 255, 4096] {
+                let other = cuckoo_filter.alternate_bucket(bucket, fingerprint);
+                assert_eq!(cuckoo_filter.alternate_bucket(other, fingerprint), bucket);
+            }
+        }
+    }
+
+    #[test]
+    fn test_full_filter_returns_error_and_loses_nothing() {
+        let mut cuckoo_filter = CuckooFilterRS::with_parameters(1_000, 12, 4, 100).unwrap();
+        cuckoo_filter.set_seed(1);
+        let mut added = 0;
+        let error = loop {
+            match cuckoo_filter.add(&added) {
+                Ok(()) => added += 1,
+                Err(error) => break error,
+            }
+        };
+        assert_eq!(error, BloomError::CapacityExceeded { capacity: cuckoo_filter.get_capacity() });
+        assert!(cuckoo_filter.load_factor() > 0.9, "load factor {} should be above 90%", cuckoo_filter.load_factor());
+        assert_eq!(cuckoo_filter.get_inserted_count(), added);
+        assert!((0..added).all(|i| cuckoo_filter.contains(&i)));
+    }
+
+    #[test]
+    fn test_false_positive_rate() {
+        let mut cuckoo_filter = seeded(10_000, 0.01);
+        assert_eq!(cuckoo_filter.get_fingerprint_bits(), 10);
+        assert!(cuckoo_filter.estimate_false_positive_rate() <= 0.01);
+        for i in 0..10_000 {
+            cuckoo_filter.add(&i).unwrap();
+        }
+        let false_positives = (10_000..110_000).filter(|i| cuckoo_filter.contains(i)).count();
+        assert!((false_positives as f64 / 100_000.0) < 0.01);
+    }
+
+    #[test]
+    fn test_bucket_sizes_and_fingerprint_widths() {
+        for (fingerprint_bits, bucket_size) in [(4, 1), (7, 2), (13, 4), (20, 8), (32, 4)] {
+            let mut cuckoo_filter = CuckooFilterRS::with_parameters(500, fingerprint_bits, bucket_size, 500).unwrap();
+            cuckoo_filter.set_seed(3);
+            for i in 0..400 {
+                cuckoo_filter.add(&i).unwrap();
+            }
+            assert!((0..400).all(|i| cuckoo_filter.contains(&i)));
+            assert_eq!(cuckoo_filter.get_bit_count(), cuckoo_filter.get_capacity() * fingerprint_bits);
+        }
+    }
+
+    #[test]
+    fn test_rejects_invalid_parameters() {
+        assert!(CuckooFilterRS::with_parameters(100, 0, 4, 500).is_err());
+        assert!(CuckooFilterRS::with_parameters(100, 33, 4, 500).is_err());
+        assert!(CuckooFilterRS::with_parameters(100, 8, 0, 500).is_err());
+        assert!(CuckooFilterRS::with_parameters(100, 8, 9, 500).is_err());
+    }
+
+    #[test]
+    fn test_serialization_roundtrip() {
+        let mut cuckoo_filter = seeded(1_000, 0.001);
+        for i in 0..800 {
+            cuckoo_filter.add(&i).unwrap();
+        }
+        let bytes = cuckoo_filter.to_bytes();
+        let mut restored = CuckooFilterRS::from_bytes(&bytes).unwrap();
+        assert_eq!(restored.to_bytes(), bytes);
+        assert_eq!(restored.get_inserted_count(), 800);
+        assert!((0..800).all(|i| restored.contains(&i)));
+        assert!(restored.remove(&0));
+    }
+
+    #[test]
+    fn test_rejects_invalid_payloads() {
+        let bytes = seeded(100, 0.01).to_bytes();
+        assert_eq!(CuckooFilterRS::from_bytes(b"BLMF").err(), Some(BloomError::InvalidMagic));
+        assert!(matches!(CuckooFilterRS::from_bytes(&bytes[..bytes.len() - 1]), Err(BloomError::Truncated { .. })));
+        let mut wrong_count = bytes.clone();
+        wrong_count[25..33].copy_from_slice(&5u64.to_le_bytes());
+        assert!(matches!(CuckooFilterRS::from_bytes(&wrong_count), Err(BloomError::InvalidPayload(_))));
+        let mut wrong_buckets = bytes.clone();
+        wrong_buckets[9..17].copy_from_slice(&3u64.to_le_bytes());
+        assert!(matches!(CuckooFilterRS::from_bytes(&wrong_buckets), Err(BloomError::InvalidPayload(_))));
+    }
+}
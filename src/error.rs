@@ -0,0 +1,58 @@
+//! Errors returned by the filters in bloomlib
+
+use std::fmt;
+
+
+/// All errors that the filters in this crate can return
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BloomError {
+    /// The payload ended before all announced data could be read
+    Truncated { expected: usize, actual: usize },
+    /// The payload does not start with the magic number of the structure that is being loaded
+    InvalidMagic,
+    /// The payload was written in a format version that this build cannot read
+    UnsupportedVersion(u16),
+    /// The payload references a hash algorithm that this build does not know
+    UnknownHashAlgorithm(u8),
+    /// The payload references an index mode that this build does not know
+    UnknownIndexMode(u8),
+    /// The payload is well-formed but its contents are inconsistent
+    InvalidPayload(String),
+    /// A parameter passed to a constructor or method is out of range
+    InvalidParameter(String),
+    /// Two filters cannot be combined because they were built with different parameters
+    Incompatible(String),
+    /// The payload was written without the key of its keyed hasher; it can only be loaded with that key
+    MissingKey,
+    /// The filter already holds the number of items it was sized for and refuses more
+    CapacityExceeded { capacity: usize },
+    /// A static filter could not be built from its keys within the given number of attempts
+    ConstructionFailed { attempts: usize },
+    /// An invertible filter holds more entries than it can list; `recovered` entries could be peeled off
+    DecodeFailed { recovered: usize },
+}
+
+impl fmt::Display for BloomError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BloomError::Truncated { expected, actual } => {
+                write!(f, "payload is truncated: expected {} bytes, got {}", expected, actual)
+            }
+            BloomError::InvalidMagic => write!(f, "payload does not start with the expected magic number"),
+            BloomError::UnsupportedVersion(version) => write!(f, "unsupported format version {}", version),
+            BloomError::UnknownHashAlgorithm(id) => write!(f, "unknown hash algorithm id {}", id),
+            BloomError::UnknownIndexMode(id) => write!(f, "unknown index mode id {}", id),
+            BloomError::InvalidPayload(reason) => write!(f, "invalid payload: {}", reason),
+            BloomError::InvalidParameter(reason) => write!(f, "invalid parameter: {}", reason),
+            BloomError::Incompatible(reason) => write!(f, "filters are incompatible: {}", reason),
+            BloomError::MissingKey => write!(f, "payload was written without its hash key; load it with the key"),
+            BloomError::CapacityExceeded { capacity } => write!(f, "filter is full: it was sized for {} items", capacity),
+            BloomError::ConstructionFailed { attempts } => write!(f, "could not build the filter in {} attempts", attempts),
+            BloomError::DecodeFailed { recovered } => {
+                write!(f, "could not list all entries (recovered {}); the filter holds more than it was sized for", recovered)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BloomError {}
@@ -0,0 +1,621 @@
+//! Hash algorithms that the filters use to derive their indices
+
+use std::fmt;
+use std::hash::Hasher;
+use std::io::Cursor;
+
+use siphasher::sip::SipHasher13;
+use siphasher::sip128::SipHasher13 as SipHasher13_128;
+
+use crate::error::BloomError;
+
+
+/// Identifies the hash algorithm a filter was built with.
+/// The discriminant is what gets written to the serialized header, so never renumber a variant.
+/// New filters use `Murmur3_128`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HashAlgorithm {
+    /// murmur3 (x86, 32 bit) hashed once per seed `0..k`; limited to 2^32 slots, kept to read older filters
+    Murmur3_32 = 0,
+    /// murmur3 (x64, 128 bit) hashed once per seed `0..k`, reduced to a 64 bit index
+    #[default]
+    Murmur3_128 = 1,
+    /// xxHash3 (64 bit), seeded with `0..k`
+    Xxh3 = 2,
+    /// SipHash-1-3 (64 bit) under a 128 bit key
+    SipHash13 = 3,
+    /// FNV-1a (64 bit) over the seed followed by the item
+    Fnv1a = 4,
+    /// CRC32C (Castagnoli) with the seed as initial value; limited to 2^32 slots
+    Crc32c = 5,
+}
+
+impl HashAlgorithm {
+    /// Returns the id under which this algorithm is stored in a serialized filter
+    pub fn id(&self) -> u8 {
+        *self as u8
+    }
+
+    /// Looks up the algorithm belonging to a serialized id
+    ///
+    /// # Arguments
+    /// * `id` - id as read from a serialized filter
+    pub fn from_id(id: u8) -> Result<Self, BloomError> {
+        match id {
+            0 => Ok(HashAlgorithm::Murmur3_32),
+            1 => Ok(HashAlgorithm::Murmur3_128),
+            2 => Ok(HashAlgorithm::Xxh3),
+            3 => Ok(HashAlgorithm::SipHash13),
+            4 => Ok(HashAlgorithm::Fnv1a),
+            5 => Ok(HashAlgorithm::Crc32c),
+            _ => Err(BloomError::UnknownHashAlgorithm(id)),
+        }
+    }
+
+    /// Returns the name under which the algorithm is selected from Python
+    pub fn name(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Murmur3_32 => "murmur3_32",
+            HashAlgorithm::Murmur3_128 => "murmur3_128",
+            HashAlgorithm::Xxh3 => "xxh3",
+            HashAlgorithm::SipHash13 => "siphash13",
+            HashAlgorithm::Fnv1a => "fnv1a",
+            HashAlgorithm::Crc32c => "crc32c",
+        }
+    }
+
+    /// Looks up an algorithm by its name
+    ///
+    /// # Arguments
+    /// * `name` - one of the names returned by `name`
+    pub fn from_name(name: &str) -> Result<Self, BloomError> {
+        [
+            HashAlgorithm::Murmur3_32,
+            HashAlgorithm::Murmur3_128,
+            HashAlgorithm::Xxh3,
+            HashAlgorithm::SipHash13,
+            HashAlgorithm::Fnv1a,
+            HashAlgorithm::Crc32c,
+        ]
+        .into_iter()
+        .find(|algorithm| algorithm.name() == name)
+        .ok_or_else(|| BloomError::InvalidParameter(format!("unknown hash algorithm '{}'", name)))
+    }
+}
+
+
+/// A hash function that a filter derives its indices from
+/// Implementations only have to provide a seeded 64 bit hash; index derivation is shared (see `indices`)
+pub trait BloomHasher: Clone + PartialEq + fmt::Debug {
+    /// Returns the algorithm that is written to the serialized header
+    fn algorithm(&self) -> HashAlgorithm;
+
+    /// Returns the hash of `bytes` under `seed`; different seeds must give independent hashes
+    ///
+    /// # Arguments
+    /// * `bytes` - the (serialized) item
+    /// * `seed` - index of the hash function, `0..k`
+    fn hash_with_seed(&self, bytes: &[u8], seed: u32) -> u64;
+
+    /// Returns the two base hashes `(h1, h2)` that double hashing combines into `h1 + i * h2`
+    /// Hashers with a wide output should override this to hash only once
+    ///
+    /// # Arguments
+    /// * `bytes` - the (serialized) item
+    fn hash_pair(&self, bytes: &[u8]) -> (u64, u64) {
+        (self.hash_with_seed(bytes, 0), self.hash_with_seed(bytes, 1))
+    }
+
+    /// Maps a hash onto `0..num_of_slots`
+    /// Defaults to `reduce`, which needs all 64 bits of the hash to be uniformly distributed
+    ///
+    /// # Arguments
+    /// * `hash` - a hash returned by `hash_with_seed` or combined from `hash_pair`
+    /// * `num_of_slots` - number of bits/counters in the filter
+    fn to_index(&self, hash: u64, num_of_slots: usize) -> usize {
+        reduce(hash, num_of_slots)
+    }
+
+    /// Returns the largest number of slots that `to_index` reaches every one of
+    /// Hashers that take 32 bit hashes modulo the number of slots can't address more than 2^32 slots
+    fn max_slots(&self) -> usize {
+        usize::MAX
+    }
+
+    /// Returns the secret key that is mixed into every hash, if the hasher is keyed
+    fn key(&self) -> Option<[u8; 16]> {
+        None
+    }
+}
+
+
+/// murmur3 (x86, 32 bit); indices are taken modulo the number of slots
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Murmur3_32Hasher;
+
+impl BloomHasher for Murmur3_32Hasher {
+    fn algorithm(&self) -> HashAlgorithm {
+        HashAlgorithm::Murmur3_32
+    }
+
+    fn hash_with_seed(&self, bytes: &[u8], seed: u32) -> u64 {
+        murmur3::murmur3_32(&mut Cursor::new(bytes), seed).unwrap() as u64
+    }
+
+    fn to_index(&self, hash: u64, num_of_slots: usize) -> usize {
+        (hash % num_of_slots as u64) as usize
+    }
+
+    fn max_slots(&self) -> usize {
+        (u32::MAX as usize).saturating_add(1)
+    }
+}
+
+/// murmur3 (x64, 128 bit); the lower 64 bits are used per seed, both halves for double hashing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Murmur3_128Hasher;
+
+impl BloomHasher for Murmur3_128Hasher {
+    fn algorithm(&self) -> HashAlgorithm {
+        HashAlgorithm::Murmur3_128
+    }
+
+    fn hash_with_seed(&self, bytes: &[u8], seed: u32) -> u64 {
+        murmur3::murmur3_x64_128(&mut Cursor::new(bytes), seed).unwrap() as u64
+    }
+
+    fn hash_pair(&self, bytes: &[u8]) -> (u64, u64) {
+        let hash_value = murmur3::murmur3_x64_128(&mut Cursor::new(bytes), 0).unwrap();
+        (hash_value as u64, (hash_value >> 64) as u64)
+    }
+}
+
+/// xxHash3; the fastest of the built-in hashers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Xxh3Hasher;
+
+impl BloomHasher for Xxh3Hasher {
+    fn algorithm(&self) -> HashAlgorithm {
+        HashAlgorithm::Xxh3
+    }
+
+    fn hash_with_seed(&self, bytes: &[u8], seed: u32) -> u64 {
+        xxhash_rust::xxh3::xxh3_64_with_seed(bytes, seed as u64)
+    }
+
+    fn hash_pair(&self, bytes: &[u8]) -> (u64, u64) {
+        let hash_value = xxhash_rust::xxh3::xxh3_128(bytes);
+        (hash_value as u64, (hash_value >> 64) as u64)
+    }
+}
+
+/// SipHash-1-3 under a 128 bit key; without knowing the key, nobody can predict which bits an item sets
+/// The default hasher uses the all-zero key, which is public and therefore counts as unkeyed; a hasher created
+/// with `new` counts as keyed whatever its key, so its key is serialized and required like any other
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub struct SipHash13Hasher {
+    key0: u64,
+    key1: u64,
+    /// Whether the key was given; the default hasher isn't keyed
+    keyed: bool,
+}
+
+impl SipHash13Hasher {
+    /// Creates a hasher with the given key
+    ///
+    /// # Arguments
+    /// * `key` - 128 bit key
+    pub fn new(key: [u8; 16]) -> Self {
+        SipHash13Hasher {
+            key0: u64::from_le_bytes(key[..8].try_into().unwrap()),
+            key1: u64::from_le_bytes(key[8..].try_into().unwrap()),
+            keyed: true,
+        }
+    }
+}
+
+impl fmt::Debug for SipHash13Hasher {
+    /// Never prints the key; it ends up in error messages
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SipHash13Hasher").field("keyed", &self.key().is_some()).finish()
+    }
+}
+
+impl BloomHasher for SipHash13Hasher {
+    fn algorithm(&self) -> HashAlgorithm {
+        HashAlgorithm::SipHash13
+    }
+
+    fn hash_with_seed(&self, bytes: &[u8], seed: u32) -> u64 {
+        let mut hasher = SipHasher13::new_with_keys(self.key0, self.key1);
+        hasher.write(&seed.to_le_bytes());
+        hasher.write(bytes);
+        hasher.finish()
+    }
+
+    fn hash_pair(&self, bytes: &[u8]) -> (u64, u64) {
+        let hash_value = SipHasher13_128::new_with_keys(self.key0, self.key1).hash(bytes);
+        (hash_value.h1, hash_value.h2)
+    }
+
+    fn key(&self) -> Option<[u8; 16]> {
+        if !self.keyed {
+            return None;
+        }
+        let mut key = [0u8; 16];
+        key[..8].copy_from_slice(&self.key0.to_le_bytes());
+        key[8..].copy_from_slice(&self.key1.to_le_bytes());
+        Some(key)
+    }
+}
+
+/// FNV-1a (64 bit); simple and fast for short items. FNV-1a mixes its last bytes poorly into the high bits that
+/// `reduce` uses, so the hash is finalized with `fmix64`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Fnv1aHasher;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+impl BloomHasher for Fnv1aHasher {
+    fn algorithm(&self) -> HashAlgorithm {
+        HashAlgorithm::Fnv1a
+    }
+
+    fn hash_with_seed(&self, bytes: &[u8], seed: u32) -> u64 {
+        let hash = seed.to_le_bytes()
+            .iter()
+            .chain(bytes)
+            .fold(FNV_OFFSET_BASIS, |hash, byte| (hash ^ *byte as u64).wrapping_mul(FNV_PRIME));
+        fmix64(hash)
+    }
+}
+
+/// CRC32C (Castagnoli); hardware accelerated on most CPUs, but only 32 bits wide, so indices are taken
+/// modulo the number of slots
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Crc32cHasher;
+
+impl BloomHasher for Crc32cHasher {
+    fn algorithm(&self) -> HashAlgorithm {
+        HashAlgorithm::Crc32c
+    }
+
+    fn hash_with_seed(&self, bytes: &[u8], seed: u32) -> u64 {
+        crc32c::crc32c_append(seed, bytes) as u64
+    }
+
+    /// CRC is linear: for items of equal length the CRCs under seeds 0 and 1 differ by the same constant, which
+    /// makes `h1 + i * h2` land on correlated indices; finalizing `h2` breaks that relation
+    fn hash_pair(&self, bytes: &[u8]) -> (u64, u64) {
+        (self.hash_with_seed(bytes, 0), fmix64(self.hash_with_seed(bytes, 1)))
+    }
+
+    fn to_index(&self, hash: u64, num_of_slots: usize) -> usize {
+        (hash % num_of_slots as u64) as usize
+    }
+
+    fn max_slots(&self) -> usize {
+        (u32::MAX as usize).saturating_add(1)
+    }
+}
+
+
+/// Any of the built-in hashers, chosen at runtime; this is what `BloomFilterRS` uses unless told otherwise
+/// and what a filter is loaded with when its hash algorithm is only known from the serialized header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnyHasher {
+    Murmur3_32(Murmur3_32Hasher),
+    Murmur3_128(Murmur3_128Hasher),
+    Xxh3(Xxh3Hasher),
+    SipHash13(SipHash13Hasher),
+    Fnv1a(Fnv1aHasher),
+    Crc32c(Crc32cHasher),
+}
+
+impl Default for AnyHasher {
+    fn default() -> Self {
+        AnyHasher::from(HashAlgorithm::default())
+    }
+}
+
+impl From<HashAlgorithm> for AnyHasher {
+    /// Builds the hasher for an algorithm; SipHash-1-3 gets an all-zero key
+    fn from(algorithm: HashAlgorithm) -> Self {
+        match algorithm {
+            HashAlgorithm::Murmur3_32 => AnyHasher::Murmur3_32(Murmur3_32Hasher),
+            HashAlgorithm::Murmur3_128 => AnyHasher::Murmur3_128(Murmur3_128Hasher),
+            HashAlgorithm::Xxh3 => AnyHasher::Xxh3(Xxh3Hasher),
+            HashAlgorithm::SipHash13 => AnyHasher::SipHash13(SipHash13Hasher::default()),
+            HashAlgorithm::Fnv1a => AnyHasher::Fnv1a(Fnv1aHasher),
+            HashAlgorithm::Crc32c => AnyHasher::Crc32c(Crc32cHasher),
+        }
+    }
+}
+
+/// Forwards a call to the hasher inside an `AnyHasher`
+macro_rules! dispatch {
+    ($any_hasher:expr, $hasher:ident => $call:expr) => {
+        match $any_hasher {
+            AnyHasher::Murmur3_32($hasher) => $call,
+            AnyHasher::Murmur3_128($hasher) => $call,
+            AnyHasher::Xxh3($hasher) => $call,
+            AnyHasher::SipHash13($hasher) => $call,
+            AnyHasher::Fnv1a($hasher) => $call,
+            AnyHasher::Crc32c($hasher) => $call,
+        }
+    };
+}
+
+impl BloomHasher for AnyHasher {
+    fn algorithm(&self) -> HashAlgorithm {
+        dispatch!(self, hasher => hasher.algorithm())
+    }
+
+    fn hash_with_seed(&self, bytes: &[u8], seed: u32) -> u64 {
+        dispatch!(self, hasher => hasher.hash_with_seed(bytes, seed))
+    }
+
+    fn hash_pair(&self, bytes: &[u8]) -> (u64, u64) {
+        dispatch!(self, hasher => hasher.hash_pair(bytes))
+    }
+
+    fn to_index(&self, hash: u64, num_of_slots: usize) -> usize {
+        dispatch!(self, hasher => hasher.to_index(hash, num_of_slots))
+    }
+
+    fn max_slots(&self) -> usize {
+        dispatch!(self, hasher => hasher.max_slots())
+    }
+
+    fn key(&self) -> Option<[u8; 16]> {
+        dispatch!(self, hasher => hasher.key())
+    }
+}
+
+
+/// How a filter derives the `k` indices of an item from its hash
+/// The discriminant is what gets written to the serialized header, so never renumber a variant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IndexMode {
+    /// Hash the item once per seed `0..k`
+    #[default]
+    Rehash = 0,
+    /// Hash the item once and derive index `i` as `h1 + i * h2` (Kirsch–Mitzenmacher)
+    DoubleHashing = 1,
+}
+
+impl IndexMode {
+    /// Returns the id under which this mode is stored in a serialized filter
+    pub fn id(&self) -> u8 {
+        *self as u8
+    }
+
+    /// Looks up the index mode belonging to a serialized id
+    ///
+    /// # Arguments
+    /// * `id` - id as read from a serialized filter
+    pub fn from_id(id: u8) -> Result<Self, BloomError> {
+        match id {
+            0 => Ok(IndexMode::Rehash),
+            1 => Ok(IndexMode::DoubleHashing),
+            _ => Err(BloomError::UnknownIndexMode(id)),
+        }
+    }
+}
+
+
+/// Derives the `count_of_hashes` slot indices of an item; every filter in this crate goes through here
+/// so that structures built with the same parameters set exactly the same slots
+/// Returns an iterator over indices in `0..num_of_slots`
+///
+/// # Arguments
+/// * `hasher` - hash function to derive the indices with
+/// * `index_mode` - whether to hash once per index or once per item
+/// * `hash_bytes` - the (serialized) item
+/// * `count_of_hashes` - number of indices to derive
+/// * `num_of_slots` - number of bits/counters in the filter
+pub fn indices<'a, H: BloomHasher + 'a>(hasher: H, index_mode: IndexMode, hash_bytes: &'a [u8], count_of_hashes: usize, num_of_slots: usize) -> impl Iterator<Item = usize> + 'a {
+    // with double hashing the item is hashed once, up front
+    let (h1, h2) = match index_mode {
+        IndexMode::Rehash => (0, 0),
+        IndexMode::DoubleHashing => hasher.hash_pair(hash_bytes),
+    };
+    (0..count_of_hashes).map(move |i| match index_mode {
+        IndexMode::Rehash => hasher.to_index(hasher.hash_with_seed(hash_bytes, i as u32), num_of_slots),
+        IndexMode::DoubleHashing => hasher.to_index(h1.wrapping_add((i as u64).wrapping_mul(h2)), num_of_slots),
+    })
+}
+
+
+/// Maps a 64 bit hash onto `0..num_of_slots` without the bias of a modulo
+/// Returns `floor(hash * num_of_slots / 2^64)`
+///
+/// # Arguments
+/// * `hash` - uniformly distributed 64 bit hash
+/// * `num_of_slots` - size of the range
+pub fn reduce(hash: u64, num_of_slots: usize) -> usize {
+    ((hash as u128 * num_of_slots as u128) >> 64) as usize
+}
+
+/// Finalizer of murmur3 (x64); spreads every input bit over all 64 output bits
+///
+/// # Arguments
+/// * `hash` - hash to finalize
+fn fmix64(hash: u64) -> u64 {
+    let mut hash = hash;
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xff51afd7ed558ccd);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xc4ceb9fe1a85ec53);
+    hash ^ (hash >> 33)
+}
+
+/// Advances a SplitMix64 generator and returns its next value; used where a filter needs cheap,
+/// seedable randomness
+///
+/// # Arguments
+/// * `state` - generator state; any value is a valid seed
+pub(crate) fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e3779b97f4a7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL_ALGORITHMS: [HashAlgorithm; 6] = [
+        HashAlgorithm::Murmur3_32,
+        HashAlgorithm::Murmur3_128,
+        HashAlgorithm::Xxh3,
+        HashAlgorithm::SipHash13,
+        HashAlgorithm::Fnv1a,
+        HashAlgorithm::Crc32c,
+    ];
+
+    #[test]
+    fn test_indices_are_in_range_and_deterministic() {
+        for algorithm in ALL_ALGORITHMS {
+            let hasher = AnyHasher::from(algorithm);
+            for index_mode in [IndexMode::Rehash, IndexMode::DoubleHashing] {
+                let derived: Vec<usize> = indices(hasher, index_mode, b"item", 7, 101).collect();
+                assert_eq!(derived.len(), 7);
+                assert!(derived.iter().all(|i| *i < 101));
+                assert_eq!(derived, indices(hasher, index_mode, b"item", 7, 101).collect::<Vec<usize>>());
+            }
+        }
+    }
+
+    #[test]
+    fn test_indices_are_spread_evenly() {
+        for algorithm in ALL_ALGORITHMS {
+            let hasher = AnyHasher::from(algorithm);
+            let mut buckets = [0usize; 10];
+            for i in 0..10_000u32 {
+                for index in indices(hasher, IndexMode::Rehash, &i.to_le_bytes(), 3, 1000) {
+                    buckets[index / 100] += 1;
+                }
+            }
+            assert!(buckets.iter().all(|count| (2700..3300).contains(count)), "{:?}: {:?}", algorithm, buckets);
+        }
+    }
+
+    #[test]
+    fn test_seeds_give_different_hashes() {
+        for algorithm in ALL_ALGORITHMS {
+            let hasher = AnyHasher::from(algorithm);
+            assert_ne!(hasher.hash_with_seed(b"item", 0), hasher.hash_with_seed(b"item", 1), "{:?}", algorithm);
+        }
+    }
+
+    #[test]
+    fn test_any_hasher_matches_concrete_hasher() {
+        let bytes = b"some item";
+        assert_eq!(AnyHasher::from(HashAlgorithm::Xxh3).hash_with_seed(bytes, 3), Xxh3Hasher.hash_with_seed(bytes, 3));
+        assert_eq!(AnyHasher::from(HashAlgorithm::Crc32c).hash_pair(bytes), Crc32cHasher.hash_pair(bytes));
+        assert_eq!(AnyHasher::default().algorithm(), HashAlgorithm::Murmur3_128);
+        for algorithm in ALL_ALGORITHMS {
+            assert_eq!(AnyHasher::from(algorithm).algorithm(), algorithm);
+        }
+    }
+
+    #[test]
+    fn test_known_hash_values() {
+        // FNV-1a of the 4 zero bytes of seed 0 followed by "a", finalized
+        let expected = [0u8, 0, 0, 0, b'a'].iter().fold(FNV_OFFSET_BASIS, |hash, byte| (hash ^ *byte as u64).wrapping_mul(FNV_PRIME));
+        assert_eq!(Fnv1aHasher.hash_with_seed(b"a", 0), fmix64(expected));
+        assert_eq!(fmix64(0), 0);
+        assert_eq!(Crc32cHasher.hash_pair(b"123456789").0, 0xe306_9283);
+        // CRC32C check value
+        assert_eq!(Crc32cHasher.hash_with_seed(b"123456789", 0), 0xe306_9283);
+        assert_eq!(Xxh3Hasher.hash_with_seed(b"", 0), 0x2d06_8005_38d3_94c2);
+    }
+
+    #[test]
+    fn test_siphash_depends_on_key() {
+        let hasher = SipHash13Hasher::new([1; 16]);
+        let other_key = SipHash13Hasher::new([2; 16]);
+        assert_ne!(hasher.hash_with_seed(b"item", 0), other_key.hash_with_seed(b"item", 0));
+        assert_ne!(hasher, other_key);
+        assert_eq!(hasher.hash_with_seed(b"item", 0), SipHash13Hasher::new([1; 16]).hash_with_seed(b"item", 0));
+
+        let key: [u8; 16] = core::array::from_fn(|i| i as u8);
+        assert_eq!(SipHash13Hasher::new(key).key(), Some(key));
+        assert_eq!(AnyHasher::SipHash13(SipHash13Hasher::new(key)).key(), Some(key));
+        assert_eq!(SipHash13Hasher::default().key(), None);
+        assert_eq!(Xxh3Hasher.key(), None);
+        assert!(!format!("{:?}", SipHash13Hasher::new([0xab; 16])).contains("171"), "Debug output should not reveal the key");
+    }
+
+    #[test]
+    fn test_indices_use_full_64_bit_range() {
+        let num_of_slots: usize = 1 << 40;
+        let indices: Vec<usize> = (0..1000u32)
+            .flat_map(|i| indices(Murmur3_128Hasher, IndexMode::Rehash, &i.to_le_bytes(), 3, num_of_slots).collect::<Vec<usize>>())
+            .collect();
+        assert!(indices.iter().all(|i| *i < num_of_slots));
+        assert!(indices.iter().filter(|i| **i > u32::MAX as usize).count() > 2900, "Indices should spread over the whole range");
+        // every eighth of the range should get roughly an eighth of the indices
+        for octant in 0..8 {
+            let in_octant = indices.iter().filter(|i| **i / (num_of_slots / 8) == octant).count();
+            assert!((300..450).contains(&in_octant), "Octant {} got {} of 3000 indices", octant, in_octant);
+        }
+    }
+
+    #[test]
+    fn test_reduce() {
+        assert_eq!(reduce(0, 10), 0);
+        assert_eq!(reduce(u64::MAX, 10), 9);
+        assert_eq!(reduce(1 << 63, 10), 5);
+        assert_eq!(reduce(u64::MAX, 1 << 40), (1 << 40) - 1);
+    }
+
+    #[test]
+    fn test_murmur3_32_indices_are_unchanged() {
+        // filters written by older versions must keep finding their items
+        let hash_value = murmur3::murmur3_32(&mut Cursor::new(b"item"), 3).unwrap();
+        let indices: Vec<usize> = indices(Murmur3_32Hasher, IndexMode::Rehash, b"item", 4, 101).collect();
+        assert_eq!(indices[3], (hash_value % 101) as usize);
+    }
+
+    #[test]
+    fn test_double_hashing_indices() {
+        for algorithm in ALL_ALGORITHMS {
+            let hasher = AnyHasher::from(algorithm);
+            assert_ne!(
+                indices(hasher, IndexMode::DoubleHashing, b"item", 7, 101).collect::<Vec<usize>>(),
+                indices(hasher, IndexMode::Rehash, b"item", 7, 101).collect::<Vec<usize>>()
+            );
+        }
+
+        // index i is h1 + i * h2, so the raw hashes of consecutive indices are h2 apart
+        let (h1, h2) = Murmur3_128Hasher.hash_pair(b"item");
+        let indices: Vec<usize> = indices(Murmur3_128Hasher, IndexMode::DoubleHashing, b"item", 3, 1 << 40).collect();
+        assert_eq!(indices[0], reduce(h1, 1 << 40));
+        assert_eq!(indices[2], reduce(h1.wrapping_add(h2.wrapping_mul(2)), 1 << 40));
+    }
+
+    #[test]
+    fn test_index_mode_ids_round_trip() {
+        for index_mode in [IndexMode::Rehash, IndexMode::DoubleHashing] {
+            assert_eq!(IndexMode::from_id(index_mode.id()), Ok(index_mode));
+        }
+        assert_eq!(IndexMode::from_id(7), Err(BloomError::UnknownIndexMode(7)));
+    }
+
+    #[test]
+    fn test_ids_and_names_round_trip() {
+        for algorithm in ALL_ALGORITHMS {
+            assert_eq!(HashAlgorithm::from_id(algorithm.id()), Ok(algorithm));
+            assert_eq!(HashAlgorithm::from_name(algorithm.name()), Ok(algorithm));
+        }
+        assert_eq!(HashAlgorithm::default(), HashAlgorithm::Murmur3_128);
+        assert_eq!(HashAlgorithm::from_id(255), Err(BloomError::UnknownHashAlgorithm(255)));
+        assert!(matches!(HashAlgorithm::from_name("md5"), Err(BloomError::InvalidParameter(_))));
+    }
+}
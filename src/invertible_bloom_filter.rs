@@ -0,0 +1,471 @@
+//! Invertible Bloom filter / lookup table in Rust (Goodrich & Mitzenmacher, "Invertible Bloom Lookup Tables", 2011;
+//! Eppstein et al., "What's the Difference? Efficient Set Reconciliation without Prior Context", 2011)
+//! Every cell keeps a count, the XOR of the keys hashed to it and the XOR of their checksums. Subtracting the filter of
+//! one set from that of another cancels the keys they share, and as long as the difference is small enough the
+//! remaining keys can be listed by repeatedly peeling off cells that hold a single key.
+
+use std::collections::VecDeque;
+use crate::error::BloomError;
+use crate::hashing::{AnyHasher, BloomHasher, HashAlgorithm};
+use crate::serialization::{ByteReader, ByteWriter};
+
+
+/// Magic number that every serialized InvertibleBloomFilterRS starts with
+const MAGIC: &[u8; 4] = b"BLMI";
+/// Version of the binary format that `to_bytes` writes
+const FORMAT_VERSION: u16 = 1;
+/// Number of cells every key is added to, used by `new`; with 4 small tables need far fewer cells than with 3
+pub const DEFAULT_NUMBER_OF_HASHES: usize = 4;
+/// Bytes in front of every key in a cell's key sum that hold the key's length
+const LENGTH_PREFIX: usize = 4;
+
+
+/// Calculates the number of cells needed to list a difference of `expected_difference` keys
+/// Large tables peel with high probability above about 1.3 cells per key, so this uses 1.5 cells per key. Small
+/// tables mostly fail on two keys that share all their cells, which happens with probability about
+/// `d² / 2 * (k / m)^k`; the table is at least `k * (250 * d²)^(1 / k)` cells, which keeps that below 1 in 500.
+/// A constant is added and the result is rounded up to a multiple of `number_of_hashes`
+///
+/// # Arguments
+/// * `expected_difference` - number of keys that are in one set but not the other
+/// * `number_of_hashes` - number of cells every key is added to (k)
+pub fn calc_number_of_cells(expected_difference: usize, number_of_hashes: usize) -> usize {
+    let expected_difference = expected_difference as f64;
+    let shared_cells_bound = number_of_hashes as f64 * (250.0 * expected_difference * expected_difference).powf(1.0 / number_of_hashes as f64);
+    let number_of_cells = (expected_difference * 1.5).max(shared_cells_bound).ceil() as usize + 12;
+    number_of_cells.div_ceil(number_of_hashes) * number_of_hashes
+}
+
+
+/// The result of listing an invertible Bloom filter's entries
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ListedEntries {
+    /// Keys that were inserted more often than deleted; after `a.subtract(b)` the keys only in `a`
+    pub inserted: Vec<Vec<u8>>,
+    /// Keys that were deleted more often than inserted; after `a.subtract(b)` the keys only in `b`
+    pub deleted: Vec<Vec<u8>>,
+}
+
+
+/// An Invertible Bloom Filter (IBLT); a set of byte keys whose difference with another set can be listed
+#[derive(Clone, Debug, PartialEq)]
+pub struct InvertibleBloomFilterRS {
+    /// Per cell: number of inserts minus number of deletes
+    counts: Vec<i64>,
+    /// Per cell: XOR of the checksums of the keys in it
+    hash_sums: Vec<u64>,
+    /// Per cell `key_width` bytes: XOR of the length-prefixed keys in it
+    key_sums: Vec<u8>,
+    /// Bytes per key sum; grows to fit the longest key
+    key_width: usize,
+    /// Number of cells every key is added to; the cells are split in this many equal parts, one per hash
+    count_of_hashes: usize,
+    /// The hash function; seeds `0..k` pick the cells and seed `k` is the checksum
+    hasher: AnyHasher,
+}
+
+impl InvertibleBloomFilterRS {
+    /// Creates an invertible Bloom filter that can list a difference of `expected_difference` keys
+    ///
+    /// # Arguments
+    /// * `expected_difference` - number of keys that are in one set but not the other
+    pub fn new(expected_difference: usize) -> Self {
+        InvertibleBloomFilterRS::with_hashes(expected_difference, DEFAULT_NUMBER_OF_HASHES)
+            .expect("default number of hashes is valid")
+    }
+
+    /// Creates an invertible Bloom filter that adds every key to `number_of_hashes` cells
+    /// Returns an error if `number_of_hashes` is outside `2..=8`
+    ///
+    /// # Arguments
+    /// * `expected_difference` - number of keys that are in one set but not the other
+    /// * `number_of_hashes` - number of cells every key is added to (k); 3 or 4 peel best
+    pub fn with_hashes(expected_difference: usize, number_of_hashes: usize) -> Result<Self, BloomError> {
+        if !(2..=8).contains(&number_of_hashes) {
+            return Err(BloomError::InvalidParameter(format!("number of hashes must be between 2 and 8, got {}", number_of_hashes)));
+        }
+        let number_of_cells = calc_number_of_cells(expected_difference, number_of_hashes);
+        Ok(InvertibleBloomFilterRS {
+            counts: vec![0; number_of_cells],
+            hash_sums: vec![0; number_of_cells],
+            key_sums: vec![0; number_of_cells * LENGTH_PREFIX],
+            key_width: LENGTH_PREFIX,
+            count_of_hashes: number_of_hashes,
+            hasher: AnyHasher::default(),
+        })
+    }
+
+    /// Inserts a key
+    ///
+    /// # Arguments
+    /// * `key` - key to insert
+    pub fn insert(&mut self, key: &[u8]) {
+        self.toggle(key, 1);
+    }
+
+    /// Deletes a key; deleting a key that was never inserted records it as a negative entry
+    ///
+    /// # Arguments
+    /// * `key` - key to delete
+    pub fn delete(&mut self, key: &[u8]) {
+        self.toggle(key, -1);
+    }
+
+    /// Subtracts another filter cell by cell; keys in both cancel out
+    /// Returns `BloomError::Incompatible` if the filters have a different number of cells, hashes or hash algorithm
+    ///
+    /// # Arguments
+    /// * `other` - filter of the other set
+    pub fn subtract(&self, other: &InvertibleBloomFilterRS) -> Result<InvertibleBloomFilterRS, BloomError> {
+        if self.counts.len() != other.counts.len() {
+            return Err(BloomError::Incompatible(format!(
+                "number of cells differs ({} vs {})", self.counts.len(), other.counts.len()
+            )));
+        }
+        if self.count_of_hashes != other.count_of_hashes {
+            return Err(BloomError::Incompatible(format!(
+                "number of hashes differs ({} vs {})", self.count_of_hashes, other.count_of_hashes
+            )));
+        }
+        if self.hasher.algorithm() != other.hasher.algorithm() {
+            return Err(BloomError::Incompatible(format!(
+                "hash algorithm differs ({:?} vs {:?})", self.hasher.algorithm(), other.hasher.algorithm()
+            )));
+        }
+        let mut difference = self.clone();
+        difference.widen(other.key_width);
+        for cell in 0..other.counts.len() {
+            difference.counts[cell] -= other.counts[cell];
+            difference.hash_sums[cell] ^= other.hash_sums[cell];
+            let other_key_sum = &other.key_sums[cell * other.key_width..(cell + 1) * other.key_width];
+            for (byte, other_byte) in difference.key_sum_mut(cell).iter_mut().zip(other_key_sum) {
+                *byte ^= other_byte;
+            }
+        }
+        Ok(difference)
+    }
+
+    /// Lists the entries by peeling off cells that hold a single key; the filter itself is left untouched
+    /// Returns `BloomError::DecodeFailed` if the filter holds more entries than it can list
+    pub fn list_entries(&self) -> Result<ListedEntries, BloomError> {
+        let mut remaining = self.clone();
+        let mut entries = ListedEntries::default();
+        let mut candidates: VecDeque<usize> = (0..remaining.counts.len()).collect();
+        while let Some(cell) = candidates.pop_front() {
+            let key = match remaining.pure_key(cell) {
+                Some(key) => key,
+                None => continue,
+            };
+            let count = remaining.counts[cell];
+            remaining.toggle(&key, -count);
+            candidates.extend(remaining.cells(&key));
+            match count {
+                1 => entries.inserted.push(key),
+                _ => entries.deleted.push(key),
+            }
+        }
+        if !remaining.is_empty() {
+            return Err(BloomError::DecodeFailed { recovered: entries.inserted.len() + entries.deleted.len() });
+        }
+        Ok(entries)
+    }
+
+    /// Checks whether every cell is zero, e.g. after subtracting the filter of an equal set
+    pub fn is_empty(&self) -> bool {
+        self.counts.iter().all(|count| *count == 0)
+            && self.hash_sums.iter().all(|hash_sum| *hash_sum == 0)
+            && self.key_sums.iter().all(|byte| *byte == 0)
+    }
+
+    /// Clears the filter
+    pub fn clear(&mut self) {
+        self.counts.fill(0);
+        self.hash_sums.fill(0);
+        self.key_sums = vec![0; self.counts.len() * LENGTH_PREFIX];
+        self.key_width = LENGTH_PREFIX;
+    }
+
+    /// Retrieve the number of cells
+    pub fn get_cell_count(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// Retrieve the number of cells every key is added to
+    pub fn get_hash_count(&self) -> usize {
+        self.count_of_hashes
+    }
+
+    /// Serializes the filter to bytes: magic number, version, hash algorithm, parameters, the counts, the checksums
+    /// and the key sums
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = ByteWriter::with_capacity(24 + self.counts.len() * 16 + self.key_sums.len());
+        writer.write_bytes(MAGIC);
+        writer.write_u16(FORMAT_VERSION);
+        writer.write_u8(self.hasher.algorithm().id());
+        writer.write_u8(self.count_of_hashes as u8);
+        writer.write_u64(self.counts.len() as u64);
+        writer.write_u64(self.key_width as u64);
+        for count in &self.counts {
+            writer.write_u64(*count as u64);
+        }
+        for hash_sum in &self.hash_sums {
+            writer.write_u64(*hash_sum);
+        }
+        writer.write_bytes(&self.key_sums);
+        writer.into_bytes()
+    }
+
+    /// Deserializes a filter written by `to_bytes`
+    /// Returns an error if the payload is truncated, has trailing bytes or a header that doesn't match
+    ///
+    /// # Arguments
+    /// * `bytes` - serialized invertible Bloom filter
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BloomError> {
+        let mut reader = ByteReader::new(bytes);
+        if reader.read_bytes(MAGIC.len())? != MAGIC {
+            return Err(BloomError::InvalidMagic);
+        }
+        let version = reader.read_u16()?;
+        if version != FORMAT_VERSION {
+            return Err(BloomError::UnsupportedVersion(version));
+        }
+        let hash_algorithm = HashAlgorithm::from_id(reader.read_u8()?)?;
+        let count_of_hashes = reader.read_u8()? as usize;
+        let number_of_cells = reader.read_usize()?;
+        let key_width = reader.read_usize()?;
+        if !(2..=8).contains(&count_of_hashes) || number_of_cells == 0 || !number_of_cells.is_multiple_of(count_of_hashes) {
+            return Err(BloomError::InvalidPayload(format!(
+                "{} cells cannot be split over {} hashes", number_of_cells, count_of_hashes
+            )));
+        }
+        if key_width < LENGTH_PREFIX {
+            return Err(BloomError::InvalidPayload(format!("key width {} is too small", key_width)));
+        }
+        let expected = number_of_cells.saturating_mul(key_width.saturating_add(16));
+        if expected > reader.remaining() {
+            return Err(BloomError::Truncated { expected, actual: reader.remaining() });
+        }
+        let counts = (0..number_of_cells).map(|_| reader.read_u64().map(|count| count as i64)).collect::<Result<Vec<i64>, BloomError>>()?;
+        let hash_sums = (0..number_of_cells).map(|_| reader.read_u64()).collect::<Result<Vec<u64>, BloomError>>()?;
+        let key_sums = reader.read_bytes(number_of_cells * key_width)?.to_vec();
+        reader.finish()?;
+        Ok(InvertibleBloomFilterRS {
+            counts,
+            hash_sums,
+            key_sums,
+            key_width,
+            count_of_hashes,
+            hasher: AnyHasher::from(hash_algorithm),
+        })
+    }
+
+    /// Adds (`sign` 1) or removes (`sign` -1) a key in each of its cells
+    fn toggle(&mut self, key: &[u8], sign: i64) {
+        self.widen(LENGTH_PREFIX + key.len());
+        let checksum = self.checksum(key);
+        let length = (key.len() as u32).to_le_bytes();
+        for cell in self.cells(key) {
+            self.counts[cell] += sign;
+            self.hash_sums[cell] ^= checksum;
+            let key_sum = self.key_sum_mut(cell);
+            for (byte, key_byte) in key_sum.iter_mut().zip(length.iter().chain(key)) {
+                *byte ^= key_byte;
+            }
+        }
+    }
+
+    /// The key in a cell that holds exactly one (inserted or deleted) key, verified by its checksum
+    fn pure_key(&self, cell: usize) -> Option<Vec<u8>> {
+        if self.counts[cell].abs() != 1 {
+            return None;
+        }
+        let key_sum = &self.key_sums[cell * self.key_width..(cell + 1) * self.key_width];
+        let length = u32::from_le_bytes([key_sum[0], key_sum[1], key_sum[2], key_sum[3]]) as usize;
+        let key = key_sum.get(LENGTH_PREFIX..LENGTH_PREFIX.checked_add(length)?)?;
+        match self.checksum(key) == self.hash_sums[cell] {
+            true => Some(key.to_vec()),
+            false => None,
+        }
+    }
+
+    /// The cells of a key, one in each of the `k` parts of the table
+    fn cells(&self, key: &[u8]) -> Vec<usize> {
+        let cells_per_hash = self.counts.len() / self.count_of_hashes;
+        (0..self.count_of_hashes)
+            .map(|i| i * cells_per_hash + self.hasher.to_index(self.hasher.hash_with_seed(key, i as u32), cells_per_hash))
+            .collect()
+    }
+
+    /// Checksum of a key, hashed independently of its cells
+    fn checksum(&self, key: &[u8]) -> u64 {
+        self.hasher.hash_with_seed(key, self.count_of_hashes as u32)
+    }
+
+    fn key_sum_mut(&mut self, cell: usize) -> &mut [u8] {
+        &mut self.key_sums[cell * self.key_width..(cell + 1) * self.key_width]
+    }
+
+    /// Grows every key sum to `key_width` bytes, padding with zeros
+    fn widen(&mut self, key_width: usize) {
+        if key_width <= self.key_width {
+            return;
+        }
+        let mut key_sums = vec![0; self.counts.len() * key_width];
+        for (cell, key_sum) in self.key_sums.chunks_exact(self.key_width).enumerate() {
+            key_sums[cell * key_width..cell * key_width + self.key_width].copy_from_slice(key_sum);
+        }
+        self.key_sums = key_sums;
+        self.key_width = key_width;
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hashing::splitmix64;
+
+    fn keys(prefix: &str, range: std::ops::Range<usize>) -> Vec<Vec<u8>> {
+        range.map(|key| format!("{}-{}", prefix, key).into_bytes()).collect()
+    }
+
+    fn sorted(mut keys: Vec<Vec<u8>>) -> Vec<Vec<u8>> {
+        keys.sort();
+        keys
+    }
+
+    #[test]
+    fn test_lists_inserted_and_deleted_keys() {
+        let mut invertible_filter = InvertibleBloomFilterRS::new(50);
+        for key in keys("in", 0..30) {
+            invertible_filter.insert(&key);
+        }
+        for key in keys("out", 0..10) {
+            invertible_filter.delete(&key);
+        }
+        let entries = invertible_filter.list_entries().unwrap();
+        assert_eq!(sorted(entries.inserted), sorted(keys("in", 0..30)));
+        assert_eq!(sorted(entries.deleted), sorted(keys("out", 0..10)));
+    }
+
+    #[test]
+    fn test_insert_then_delete_cancels() {
+        let mut invertible_filter = InvertibleBloomFilterRS::new(10);
+        invertible_filter.insert(b"key");
+        invertible_filter.insert(b"a much longer key than the first");
+        invertible_filter.delete(b"key");
+        invertible_filter.delete(b"a much longer key than the first");
+        assert!(invertible_filter.is_empty());
+        assert_eq!(invertible_filter.list_entries().unwrap(), ListedEntries::default());
+    }
+
+    #[test]
+    fn test_subtract_lists_the_difference() {
+        // two large sets that differ in 100 keys, 60 only on the left and 40 only on the right
+        let shared = keys("shared", 0..20_000);
+        let mut left = InvertibleBloomFilterRS::new(100);
+        let mut right = InvertibleBloomFilterRS::new(100);
+        for key in &shared {
+            left.insert(key);
+            right.insert(key);
+        }
+        for key in keys("left", 0..60) {
+            left.insert(&key);
+        }
+        for key in keys("right", 0..40) {
+            right.insert(&key);
+        }
+        let entries = left.subtract(&right).unwrap().list_entries().unwrap();
+        assert_eq!(sorted(entries.inserted), sorted(keys("left", 0..60)));
+        assert_eq!(sorted(entries.deleted), sorted(keys("right", 0..40)));
+        assert!(left.subtract(&left).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_keys_of_different_lengths() {
+        let mut invertible_filter = InvertibleBloomFilterRS::new(10);
+        let keys: Vec<Vec<u8>> = vec![vec![], vec![0], vec![0, 0], b"abc".to_vec(), vec![7; 300]];
+        for key in &keys {
+            invertible_filter.insert(key);
+        }
+        assert_eq!(sorted(invertible_filter.list_entries().unwrap().inserted), sorted(keys));
+    }
+
+    #[test]
+    fn test_too_large_difference_fails_to_decode() {
+        let mut invertible_filter = InvertibleBloomFilterRS::new(10);
+        for key in keys("key", 0..200) {
+            invertible_filter.insert(&key);
+        }
+        assert!(matches!(invertible_filter.list_entries(), Err(BloomError::DecodeFailed { .. })));
+    }
+
+    #[test]
+    fn test_decodes_at_the_expected_difference() {
+        for expected_difference in [1, 10, 100, 1_000] {
+            let mut invertible_filter = InvertibleBloomFilterRS::new(expected_difference);
+            for key in keys("key", 0..expected_difference) {
+                invertible_filter.insert(&key);
+            }
+            assert_eq!(invertible_filter.list_entries().unwrap().inserted.len(), expected_difference);
+        }
+    }
+
+    #[test]
+    fn test_decodes_random_keys_with_high_probability() {
+        let mut state = 0;
+        for number_of_hashes in [3, DEFAULT_NUMBER_OF_HASHES, 5] {
+            for expected_difference in [10, 100] {
+                let failures = (0..200)
+                    .filter(|_| {
+                        let mut invertible_filter = InvertibleBloomFilterRS::with_hashes(expected_difference, number_of_hashes).unwrap();
+                        for _ in 0..expected_difference {
+                            invertible_filter.insert(&splitmix64(&mut state).to_le_bytes());
+                        }
+                        invertible_filter.list_entries().is_err()
+                    })
+                    .count();
+                assert!(failures <= 2, "{} of 200 tables with {} keys and {} hashes failed to decode", failures, expected_difference, number_of_hashes);
+            }
+        }
+    }
+
+    #[test]
+    fn test_rejects_incompatible_filters() {
+        let filter = InvertibleBloomFilterRS::new(10);
+        assert!(matches!(filter.subtract(&InvertibleBloomFilterRS::new(100)), Err(BloomError::Incompatible(_))));
+        let four_hashes = InvertibleBloomFilterRS::with_hashes(8, 4).unwrap();
+        let three_hashes = InvertibleBloomFilterRS::with_hashes(4, 3).unwrap();
+        assert_eq!(four_hashes.get_cell_count(), three_hashes.get_cell_count());
+        assert!(matches!(four_hashes.subtract(&three_hashes), Err(BloomError::Incompatible(_))));
+        assert!(InvertibleBloomFilterRS::with_hashes(10, 1).is_err());
+        assert!(InvertibleBloomFilterRS::with_hashes(10, 9).is_err());
+    }
+
+    #[test]
+    fn test_serialization_roundtrip() {
+        let mut invertible_filter = InvertibleBloomFilterRS::new(20);
+        for key in keys("in", 0..10) {
+            invertible_filter.insert(&key);
+        }
+        invertible_filter.delete(b"out");
+        let bytes = invertible_filter.to_bytes();
+        let restored = InvertibleBloomFilterRS::from_bytes(&bytes).unwrap();
+        assert_eq!(restored, invertible_filter);
+        assert_eq!(restored.list_entries().unwrap().deleted, vec![b"out".to_vec()]);
+    }
+
+    #[test]
+    fn test_rejects_invalid_payloads() {
+        let bytes = InvertibleBloomFilterRS::new(5).to_bytes();
+        assert_eq!(InvertibleBloomFilterRS::from_bytes(b"BLMR").err(), Some(BloomError::InvalidMagic));
+        assert!(matches!(InvertibleBloomFilterRS::from_bytes(&bytes[..bytes.len() - 1]), Err(BloomError::Truncated { .. })));
+        let mut wrong_cells = bytes.clone();
+        wrong_cells[8..16].copy_from_slice(&7u64.to_le_bytes());
+        assert!(matches!(InvertibleBloomFilterRS::from_bytes(&wrong_cells), Err(BloomError::InvalidPayload(_))));
+        let mut huge_width = bytes.clone();
+        huge_width[16..24].copy_from_slice(&(1u64 << 60).to_le_bytes());
+        assert!(matches!(InvertibleBloomFilterRS::from_bytes(&huge_width), Err(BloomError::Truncated { .. })));
+    }
+}
+
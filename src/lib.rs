@@ -3,21 +3,58 @@
 use pyo3::prelude::{pyclass, pymethods, pymodule, PyModule};
 use pyo3::{PyObject, PyResult, Python};
 use pyo3::types::{
-    PyString, PyInt, PyFloat, PyDate, PyDateTime, PyDict, PyList, PyTuple, PySet, PyTime,
-    PyBool, PyLong, PyFunction
+    PyString, PyInt, PyFloat, PyDate, PyDateTime, PyDict, PyList, PyTuple, PySet, PyFrozenSet, PyTime,
+    PyBool, PyLong, PyFunction, PyBytes, PyByteArray, PyType
 };
-use std::collections::hash_map::DefaultHasher;
-use std::hash::{Hash, Hasher};
+use siphasher::sip::SipHasher13;
+use std::hash::Hasher;
 use pyo3::prelude::*;
-use crate::bloom_filter::BloomFilterRS;  // For Python, PyResult
+use pyo3::{PyMappingProtocol, PyNumberProtocol, PyObjectProtocol, PySequenceProtocol};
+use std::sync::Arc;
+use std::time::Duration;
+use crate::blocked_bloom_filter::BlockedBloomFilterRS;
+use crate::split_block_bloom_filter::SplitBlockBloomFilterRS;
+use crate::scalable_bloom_filter::{ScalableBloomFilterRS, DEFAULT_GROWTH_FACTOR, DEFAULT_TIGHTENING_RATIO};
+use crate::sliding_window_bloom_filter::{Clock, ManualClock, Rotation, SlidingWindowBloomFilterRS, SystemClock};
+use crate::stable_bloom_filter::StableBloomFilterRS;
+use crate::cuckoo_filter::{calc_fingerprint_bits, CuckooFilterRS, DEFAULT_BUCKET_SIZE, DEFAULT_MAX_KICKS};
+use crate::quotient_filter::QuotientFilterRS;
+use crate::binary_fuse_filter::{AnyBinaryFuseFilter, DEFAULT_MAX_ATTEMPTS};
+use crate::ribbon_filter::RibbonFilterRS;
+use crate::invertible_bloom_filter::{InvertibleBloomFilterRS, DEFAULT_NUMBER_OF_HASHES};
+use crate::bloomier_filter::AnyBloomierFilter;
+use crate::bloom_filter::{BloomFilterRS, InsertionCounting, DEFAULT_SATURATION_THRESHOLD, KeyPersistence, OverflowPolicy};  // For Python, PyResult
+use crate::counting_bloom_filter::{CounterWidth, CountingBloomFilterRS, InsertMode};
+use crate::error::BloomError;
+use crate::hashing::{HashAlgorithm, IndexMode};
 
-mod bloom_filter;
-mod serialization;
-mod counting_bloom_filter;
+pub mod bloom_filter;
+pub mod error;
+pub mod hashing;
+pub mod serialization;
+pub mod counting_bloom_filter;
+pub mod blocked_bloom_filter;
+pub mod split_block_bloom_filter;
+pub mod scalable_bloom_filter;
+pub mod sliding_window_bloom_filter;
+pub mod stable_bloom_filter;
+pub mod cuckoo_filter;
+pub mod quotient_filter;
+pub mod binary_fuse_filter;
+pub mod ribbon_filter;
+pub mod invertible_bloom_filter;
+pub mod bloomier_filter;
+
+
+impl From<BloomError> for PyErr {
+    fn from(err: BloomError) -> PyErr {
+        PyErr::new::<pyo3::exceptions::PyValueError, _>(err.to_string())
+    }
+}
 
 
 // Standard Bloom Filter
-#[pyclass]
+#[pyclass(module = "bloomlib")]
 struct BloomFilter {
     bloomfilter: BloomFilterRS
 }
@@ -25,10 +62,33 @@ struct BloomFilter {
 #[pymethods]
 impl BloomFilter {
     #[new]
-    pub fn new(expected_number_of_items: usize, desired_false_positive_rate: f64) -> Self {
-        BloomFilter {
-            bloomfilter: BloomFilterRS::new(expected_number_of_items, desired_false_positive_rate),
+    #[allow(clippy::too_many_arguments)]
+    #[args(overflow_policy = "\"ignore\"", count_new_items_only = "false", double_hashing = "false", hash_algorithm = "None", seed = "None", persist_seed = "false")]
+    pub fn new(py: Python, expected_number_of_items: usize, desired_false_positive_rate: f64, overflow_policy: &str, count_new_items_only: bool, double_hashing: bool, hash_algorithm: Option<String>, seed: Option<PyObject>, persist_seed: bool) -> PyResult<Self> {
+        let index_mode = match double_hashing {
+            true => IndexMode::DoubleHashing,
+            false => IndexMode::Rehash,
+        };
+        let mut bloomfilter = match (seed, hash_algorithm.as_deref()) {
+            (None, hash_algorithm) => {
+                let hash_algorithm = HashAlgorithm::from_name(hash_algorithm.unwrap_or("murmur3_128"))?;
+                BloomFilterRS::with_hash_algorithm(expected_number_of_items, desired_false_positive_rate, index_mode, hash_algorithm)?
+            }
+            (Some(seed), None | Some("siphash13")) => {
+                BloomFilterRS::with_key(expected_number_of_items, desired_false_positive_rate, index_mode, parse_seed(seed.as_ref(py))?)
+            }
+            (Some(_), Some(_)) => {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("a seed can only be used with hash_algorithm 'siphash13'"));
+            }
+        };
+        if persist_seed {
+            bloomfilter.set_key_persistence(KeyPersistence::Store);
+        }
+        bloomfilter.set_overflow_policy(parse_overflow_policy(overflow_policy)?);
+        if count_new_items_only {
+            bloomfilter.set_insertion_counting(InsertionCounting::NewItemsOnly);
         }
+        Ok(BloomFilter { bloomfilter })
     }
 
     pub fn add(&mut self, py: Python, item: PyObject) -> PyResult<()> {
@@ -40,8 +100,12 @@ impl BloomFilter {
         hash_pyobject(py, &item, &mut py_bytes)?;
 
         // Use the hash bytes to update the BloomSet
-        self.bloomfilter.add_bytes(&py_bytes);
+        self.bloomfilter.add_bytes(&py_bytes)?;
 
+        // The "warn" overflow policy leaves an exception behind when warnings are turned into errors
+        if PyErr::occurred(py) {
+            return Err(PyErr::fetch(py));
+        }
         Ok(())
     }
 
@@ -92,85 +156,1463 @@ impl BloomFilter {
         let py_long_biglen = bitlen.extract::<Py<PyLong>>(py)?;
         Ok(py_long_biglen)
     }
+    pub fn get_hash_algorithm(&self) -> &'static str {
+        self.bloomfilter.get_hash_algorithm().name()
+    }
     pub fn estimate_false_positive_rate(&self) -> f64 {
         self.bloomfilter.estimate_false_positive_rate()
     }
+    pub fn get_inserted_count(&self) -> usize {
+        self.bloomfilter.get_inserted_count()
+    }
+    pub fn remaining_capacity(&self) -> usize {
+        self.bloomfilter.remaining_capacity()
+    }
+
+    pub fn current_false_positive_rate(&self) -> f64 {
+        self.bloomfilter.current_false_positive_rate()
+    }
+    pub fn fill_ratio(&self) -> f64 {
+        self.bloomfilter.fill_ratio()
+    }
+    #[args(threshold = "DEFAULT_SATURATION_THRESHOLD")]
+    pub fn is_saturated(&self, threshold: f64) -> bool {
+        self.bloomfilter.is_saturated(threshold)
+    }
+    pub fn estimate_cardinality(&self) -> f64 {
+        self.bloomfilter.estimate_cardinality()
+    }
+    pub fn estimate_union_size(&self, other: &BloomFilter) -> PyResult<f64> {
+        Ok(self.bloomfilter.estimate_union_size(&other.bloomfilter)?)
+    }
+    pub fn estimate_intersection_size(&self, other: &BloomFilter) -> PyResult<f64> {
+        Ok(self.bloomfilter.estimate_intersection_size(&other.bloomfilter)?)
+    }
+
+    pub fn to_bytes(&self, py: Python) -> PyObject {
+        PyBytes::new(py, &self.bloomfilter.to_bytes()).into()
+    }
+    #[staticmethod]
+    #[args(seed = "None")]
+    pub fn from_bytes(py: Python, data: &[u8], seed: Option<PyObject>) -> PyResult<Self> {
+        let mut bloomfilter = match seed {
+            None => BloomFilterRS::from_bytes(data)?,
+            Some(seed) => BloomFilterRS::from_bytes_with_key(data, parse_seed(seed.as_ref(py))?)?,
+        };
+        restore_overflow_warning(&mut bloomfilter);
+        Ok(BloomFilter { bloomfilter })
+    }
+    pub fn save(&self, path: &str) -> PyResult<()> {
+        std::fs::write(path, self.bloomfilter.to_bytes())?;
+        Ok(())
+    }
+    #[staticmethod]
+    #[args(seed = "None")]
+    pub fn load(py: Python, path: &str, seed: Option<PyObject>) -> PyResult<Self> {
+        let data = std::fs::read(path)?;
+        BloomFilter::from_bytes(py, &data, seed)
+    }
+
+    // Pickle support; the state always includes the seed, so seeded filters can be sent to other processes,
+    // and whether `to_bytes` should write it
+    pub fn __getstate__(&self, py: Python) -> PyObject {
+        let bytes = self.bloomfilter.to_bytes_with_key_persistence(KeyPersistence::Store);
+        let persist_seed = self.bloomfilter.get_key_persistence() == KeyPersistence::Store;
+        (PyBytes::new(py, &bytes), persist_seed).into_py(py)
+    }
+    pub fn __setstate__(&mut self, state: (&PyBytes, bool)) -> PyResult<()> {
+        let (bytes, persist_seed) = state;
+        self.bloomfilter = BloomFilterRS::from_bytes(bytes.as_bytes())?;
+        if !persist_seed {
+            self.bloomfilter.set_key_persistence(KeyPersistence::Omit);
+        }
+        restore_overflow_warning(&mut self.bloomfilter);
+        Ok(())
+    }
+    pub fn __reduce__(&self, py: Python) -> (Py<PyType>, (usize, f64), PyObject) {
+        // Unpickling constructs the smallest possible filter and replaces it via __setstate__
+        (py.get_type::<BloomFilter>().into(), (1, 0.5), self.__getstate__(py))
+    }
 }
 
+// `len(bf)` approximates the number of distinct items that were added
+#[pyproto]
+impl PySequenceProtocol for BloomFilter {
+    fn __len__(&self) -> PyResult<usize> {
+        let cardinality = self.bloomfilter.estimate_cardinality();
+        if !cardinality.is_finite() {
+            return Err(PyErr::new::<pyo3::exceptions::PyOverflowError, _>(
+                "All bits are set; the number of items cannot be estimated",
+            ));
+        }
+        Ok(cardinality.round() as usize)
+    }
+}
 
+// A filter is always truthy; without `__bool__`, Python would fall back to `len(bf)` and an empty filter would be
+// falsy and a saturated one would raise
+#[pyproto]
+impl PyObjectProtocol for BloomFilter {
+    fn __bool__(&self) -> bool {
+        true
+    }
+}
 
+// Set operations: `|` and `&` combine filters with the same number of bits, hashes and hash algorithm
+#[pyproto]
+impl PyNumberProtocol for BloomFilter {
+    fn __or__(lhs: PyRef<BloomFilter>, rhs: PyRef<BloomFilter>) -> PyResult<BloomFilter> {
+        Ok(BloomFilter {
+            bloomfilter: lhs.bloomfilter.union(&rhs.bloomfilter)?,
+        })
+    }
+    fn __and__(lhs: PyRef<BloomFilter>, rhs: PyRef<BloomFilter>) -> PyResult<BloomFilter> {
+        Ok(BloomFilter {
+            bloomfilter: lhs.bloomfilter.intersect(&rhs.bloomfilter)?,
+        })
+    }
+    fn __ior__(&mut self, other: &PyCell<BloomFilter>) -> PyResult<()> {
+        // `bf |= bf` cannot borrow `other`; OR-ing a filter with itself changes nothing anyway
+        if let Ok(other) = other.try_borrow() {
+            self.bloomfilter.union_inplace(&other.bloomfilter)?;
+        }
+        Ok(())
+    }
+    fn __iand__(&mut self, other: &PyCell<BloomFilter>) -> PyResult<()> {
+        // `bf &= bf` cannot borrow `other`; AND-ing a filter with itself changes nothing anyway
+        if let Ok(other) = other.try_borrow() {
+            self.bloomfilter.intersect_inplace(&other.bloomfilter)?;
+        }
+        Ok(())
+    }
+}
 
-/// Hashes Python Objects. Returns Bytes
-fn hash_pyobject(py: Python, obj: &PyObject, output: &mut Vec<u8>) -> PyResult<()> {
-    let mut hasher = DefaultHasher::new();
 
-    let py_any = obj.as_ref(py);
 
-    match py_any {
-        // Combine integer types
-        obj if obj.cast_as::<PyInt>().is_ok() || obj.cast_as::<PyLong>().is_ok() => {
-            obj.extract::<i64>()?.hash(&mut hasher)
-        },
+// Counting Bloom Filter; supports removing items
+#[pyclass(module = "bloomlib")]
+struct CountingBloomFilter {
+    bloomfilter: CountingBloomFilterRS
+}
 
-        // Floats
-        obj if obj.cast_as::<PyFloat>().is_ok() => {
-            obj.extract::<f64>()?.to_bits().hash(&mut hasher)
-        },
+#[pymethods]
+impl CountingBloomFilter {
+    #[new]
+    #[args(counter_bits = "4", conservative_update = "false")]
+    pub fn new(expected_number_of_items: usize, desired_false_positive_rate: f64, counter_bits: usize, conservative_update: bool) -> PyResult<Self> {
+        let counter_width = CounterWidth::from_bits(counter_bits)?;
+        let insert_mode = match conservative_update {
+            true => InsertMode::ConservativeUpdate,
+            false => InsertMode::Standard,
+        };
+        Ok(CountingBloomFilter {
+            bloomfilter: CountingBloomFilterRS::with_options(expected_number_of_items, desired_false_positive_rate, counter_width, insert_mode)?,
+        })
+    }
 
-        // Booleans
-        obj if obj.cast_as::<PyBool>().is_ok() => {
-            obj.extract::<bool>()?.hash(&mut hasher)
-        },
+    pub fn add(&mut self, py: Python, item: PyObject) -> PyResult<()> {
+        let mut py_bytes: Vec<u8> = Vec::new();
+        hash_pyobject(py, &item, &mut py_bytes)?;
+        self.bloomfilter.add_bytes(&py_bytes);
+        Ok(())
+    }
 
-        // Combine string and various collections into one case
-        obj if obj.cast_as::<PyString>().is_ok()
-            || obj.cast_as::<PyList>().is_ok()
-            || obj.cast_as::<PyDict>().is_ok()
-            || obj.cast_as::<PyTuple>().is_ok()
-            || obj.cast_as::<PySet>().is_ok() => {
-            obj.str()?.to_string().hash(&mut hasher)
-        },
+    pub fn add_bulk(&mut self, py: Python, items: &PyAny) -> PyResult<()> {
+        if let Ok(item_iterator) = items.iter() {
+            for item in item_iterator {
+                self.add(py, item?.extract()?)?;
+            }
+        } else {
+            return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+                "Expected an iterable",
+            ));
+        }
+        Ok(())
+    }
 
-        // Date and time types
-        obj if obj.cast_as::<PyDate>().is_ok()
-            || obj.cast_as::<PyDateTime>().is_ok()
-            || obj.cast_as::<PyTime>().is_ok() => {
-            obj.call_method0("isoformat")?.to_string().hash(&mut hasher)
-        },
+    pub fn remove(&mut self, py: Python, item: PyObject) -> PyResult<bool> {
+        if self.bloomfilter.get_insert_mode() == InsertMode::ConservativeUpdate {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "Cannot remove items from a filter that uses conservative update",
+            ));
+        }
+        let mut py_bytes: Vec<u8> = Vec::new();
+        hash_pyobject(py, &item, &mut py_bytes)?;
+        Ok(self.bloomfilter.remove_bytes(&py_bytes))
+    }
 
-        // Functions get converted to string and hashed
-        obj if obj.cast_as::<PyFunction>().is_ok() => {
-            obj.str()?.to_string().hash(&mut hasher)
-        },
+    pub fn contains(&self, py: Python, item: PyObject) -> PyResult<bool> {
+        let mut py_bytes: Vec<u8> = Vec::new();
+        hash_pyobject(py, &item, &mut py_bytes)?;
+        Ok(self.bloomfilter.contains_bytes(&py_bytes))
+    }
+    pub fn estimate_count(&self, py: Python, item: PyObject) -> PyResult<u32> {
+        let mut py_bytes: Vec<u8> = Vec::new();
+        hash_pyobject(py, &item, &mut py_bytes)?;
+        Ok(self.bloomfilter.estimate_count_bytes(&py_bytes))
+    }
+    pub fn clear(&mut self) -> PyResult<()> {
+        self.bloomfilter.clear();
+        Ok(())
+    }
+    pub fn get_number_of_hashes(&self) -> usize {
+        self.bloomfilter.get_hash_count()
+    }
+    pub fn get_number_of_counters(&self) -> usize {
+        self.bloomfilter.get_counter_count()
+    }
+    pub fn get_counter_bits(&self) -> usize {
+        self.bloomfilter.get_counter_width().bits()
+    }
+    pub fn get_number_of_saturated_counters(&self) -> usize {
+        self.bloomfilter.get_saturated_counter_count()
+    }
+    pub fn to_bloom_filter(&self) -> BloomFilter {
+        BloomFilter {
+            bloomfilter: self.bloomfilter.to_bloom_filter(),
+        }
+    }
+}
+
+
+// Blocked Bloom Filter; all bits of an item live in one cache line
+#[pyclass(module = "bloomlib")]
+struct BlockedBloomFilter {
+    bloomfilter: BlockedBloomFilterRS
+}
+
+#[pymethods]
+impl BlockedBloomFilter {
+    #[new]
+    pub fn new(expected_number_of_items: usize, desired_false_positive_rate: f64) -> PyResult<Self> {
+        Ok(BlockedBloomFilter {
+            bloomfilter: BlockedBloomFilterRS::new(expected_number_of_items, desired_false_positive_rate)?,
+        })
+    }
+
+    pub fn add(&mut self, py: Python, item: PyObject) -> PyResult<()> {
+        let mut py_bytes: Vec<u8> = Vec::new();
+        hash_pyobject(py, &item, &mut py_bytes)?;
+        self.bloomfilter.add_bytes(&py_bytes);
+        Ok(())
+    }
+
+    pub fn add_bulk(&mut self, py: Python, items: &PyAny) -> PyResult<()> {
+        if let Ok(item_iterator) = items.iter() {
+            for item in item_iterator {
+                self.add(py, item?.extract()?)?;
+            }
+        } else {
+            return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+                "Expected an iterable",
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn contains(&self, py: Python, item: PyObject) -> PyResult<bool> {
+        let mut py_bytes: Vec<u8> = Vec::new();
+        hash_pyobject(py, &item, &mut py_bytes)?;
+        Ok(self.bloomfilter.contains_bytes(&py_bytes))
+    }
+    pub fn clear(&mut self) -> PyResult<()> {
+        self.bloomfilter.clear();
+        Ok(())
+    }
+    pub fn get_number_of_hashes(&self) -> usize {
+        self.bloomfilter.get_hash_count()
+    }
+    pub fn get_number_of_bits(&self) -> usize {
+        self.bloomfilter.get_bit_count()
+    }
+    pub fn get_number_of_blocks(&self) -> usize {
+        self.bloomfilter.get_block_count()
+    }
+    pub fn estimate_false_positive_rate(&self) -> f64 {
+        self.bloomfilter.estimate_false_positive_rate()
+    }
+}
+
+
+// Split-block Bloom Filter; reads and writes the bloom filters of Parquet column chunks
+#[pyclass(module = "bloomlib")]
+struct SplitBlockBloomFilter {
+    bloomfilter: SplitBlockBloomFilterRS
+}
+
+#[pymethods]
+impl SplitBlockBloomFilter {
+    #[new]
+    pub fn new(expected_number_of_items: usize, desired_false_positive_rate: f64) -> Self {
+        SplitBlockBloomFilter {
+            bloomfilter: SplitBlockBloomFilterRS::new(expected_number_of_items, desired_false_positive_rate),
+        }
+    }
+
+    pub fn add(&mut self, item: &PyAny) -> PyResult<()> {
+        self.bloomfilter.add_bytes(&parquet_plain_bytes(item)?);
+        Ok(())
+    }
 
-        obj if obj.cast_as::<PyDict>().is_ok() => {
-            let dict: &PyDict = obj.extract()?;
-            for (key, value) in dict.into_iter() {
-                key.to_string().hash(&mut hasher);
-                hash_pyobject(py, &value.to_object(py), output)?;
+    pub fn add_bulk(&mut self, items: &PyAny) -> PyResult<()> {
+        if let Ok(item_iterator) = items.iter() {
+            for item in item_iterator {
+                self.add(item?)?;
             }
-        },
+        } else {
+            return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+                "Expected an iterable",
+            ));
+        }
+        Ok(())
+    }
 
-        // Default case for other types
-        _ => {
-            // Call Python's `__hash__` function to get a hash value todo this should be optimized
-            let hash_val: i64 = obj.call_method0(py, "__hash__")?.extract(py)?;
-            hasher.write_i64(hash_val);
-        },
-    };
+    pub fn contains(&self, item: &PyAny) -> PyResult<bool> {
+        Ok(self.bloomfilter.contains_bytes(&parquet_plain_bytes(item)?))
+    }
+    pub fn add_hash(&mut self, hash: u64) {
+        self.bloomfilter.insert_hash(hash);
+    }
+    pub fn contains_hash(&self, hash: u64) -> bool {
+        self.bloomfilter.check_hash(hash)
+    }
+    pub fn clear(&mut self) -> PyResult<()> {
+        self.bloomfilter.clear();
+        Ok(())
+    }
+    pub fn get_number_of_bytes(&self) -> usize {
+        self.bloomfilter.get_byte_count()
+    }
+    pub fn get_number_of_blocks(&self) -> usize {
+        self.bloomfilter.get_block_count()
+    }
+    pub fn false_positive_rate(&self, number_of_items: usize) -> f64 {
+        self.bloomfilter.false_positive_rate(number_of_items)
+    }
 
-    let hash_bytes = hasher.finish().to_ne_bytes();
-    output.extend_from_slice(&hash_bytes);
+    pub fn to_bytes(&self, py: Python) -> PyObject {
+        PyBytes::new(py, &self.bloomfilter.to_bytes()).into()
+    }
+    #[staticmethod]
+    pub fn from_bytes(data: &[u8]) -> PyResult<Self> {
+        Ok(SplitBlockBloomFilter {
+            bloomfilter: SplitBlockBloomFilterRS::from_bytes(data)?,
+        })
+    }
+    pub fn to_parquet_bytes(&self, py: Python) -> PyObject {
+        PyBytes::new(py, &self.bloomfilter.to_parquet_bytes()).into()
+    }
+    #[staticmethod]
+    pub fn from_parquet_bytes(data: &[u8]) -> PyResult<Self> {
+        Ok(SplitBlockBloomFilter {
+            bloomfilter: SplitBlockBloomFilterRS::from_parquet_bytes(data)?,
+        })
+    }
+}
 
-    Ok(())
 
+// Scalable Bloom Filter; adds stages as items are added so the false positive rate stays bounded
+#[pyclass(module = "bloomlib")]
+struct ScalableBloomFilter {
+    bloomfilter: ScalableBloomFilterRS
+}
+
+#[pymethods]
+impl ScalableBloomFilter {
+    #[new]
+    #[args(growth_factor = "DEFAULT_GROWTH_FACTOR", tightening_ratio = "DEFAULT_TIGHTENING_RATIO")]
+    pub fn new(initial_capacity: usize, desired_false_positive_rate: f64, growth_factor: f64, tightening_ratio: f64) -> PyResult<Self> {
+        Ok(ScalableBloomFilter {
+            bloomfilter: ScalableBloomFilterRS::with_growth(initial_capacity, desired_false_positive_rate, growth_factor, tightening_ratio)?,
+        })
+    }
+
+    pub fn add(&mut self, py: Python, item: PyObject) -> PyResult<()> {
+        let mut py_bytes: Vec<u8> = Vec::new();
+        hash_pyobject(py, &item, &mut py_bytes)?;
+        self.bloomfilter.add_bytes(&py_bytes);
+        Ok(())
+    }
+
+    pub fn add_bulk(&mut self, py: Python, items: &PyAny) -> PyResult<()> {
+        if let Ok(item_iterator) = items.iter() {
+            for item in item_iterator {
+                self.add(py, item?.extract()?)?;
+            }
+        } else {
+            return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+                "Expected an iterable",
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn contains(&self, py: Python, item: PyObject) -> PyResult<bool> {
+        let mut py_bytes: Vec<u8> = Vec::new();
+        hash_pyobject(py, &item, &mut py_bytes)?;
+        Ok(self.bloomfilter.contains_bytes(&py_bytes))
+    }
+    pub fn clear(&mut self) -> PyResult<()> {
+        self.bloomfilter.clear();
+        Ok(())
+    }
+    pub fn get_number_of_stages(&self) -> usize {
+        self.bloomfilter.get_stage_count()
+    }
+    pub fn get_number_of_bits(&self) -> usize {
+        self.bloomfilter.get_bit_count()
+    }
+    pub fn get_inserted_count(&self) -> usize {
+        self.bloomfilter.get_inserted_count()
+    }
+    pub fn get_capacity(&self) -> usize {
+        self.bloomfilter.get_capacity()
+    }
+    pub fn current_false_positive_rate(&self) -> f64 {
+        self.bloomfilter.current_false_positive_rate()
+    }
+
+    pub fn to_bytes(&self, py: Python) -> PyObject {
+        PyBytes::new(py, &self.bloomfilter.to_bytes()).into()
+    }
+    #[staticmethod]
+    pub fn from_bytes(data: &[u8]) -> PyResult<Self> {
+        Ok(ScalableBloomFilter {
+            bloomfilter: ScalableBloomFilterRS::from_bytes(data)?,
+        })
+    }
+    pub fn save(&self, path: &str) -> PyResult<()> {
+        std::fs::write(path, self.bloomfilter.to_bytes())?;
+        Ok(())
+    }
+    #[staticmethod]
+    pub fn load(path: &str) -> PyResult<Self> {
+        let data = std::fs::read(path)?;
+        ScalableBloomFilter::from_bytes(&data)
+    }
+
+    // Pickle support
+    pub fn __getstate__(&self, py: Python) -> PyObject {
+        self.to_bytes(py)
+    }
+    pub fn __setstate__(&mut self, state: &[u8]) -> PyResult<()> {
+        self.bloomfilter = ScalableBloomFilterRS::from_bytes(state)?;
+        Ok(())
+    }
+    pub fn __reduce__(&self, py: Python) -> (Py<PyType>, (usize, f64), PyObject) {
+        // Unpickling constructs the smallest possible filter and replaces it via __setstate__
+        (py.get_type::<ScalableBloomFilter>().into(), (1, 0.5), self.__getstate__(py))
+    }
+}
+
+
+// Sliding-window Bloom Filter; items expire after a number of generations
+#[pyclass(module = "bloomlib")]
+struct SlidingWindowBloomFilter {
+    bloomfilter: SlidingWindowBloomFilterRS,
+    /// The user's clock, read before every operation that looks at the time
+    clock: Option<PythonClock>,
 }
 
+#[pymethods]
+impl SlidingWindowBloomFilter {
+    #[new]
+    #[args(generation_seconds = "None", clock = "None")]
+    pub fn new(expected_items_per_generation: usize, desired_false_positive_rate: f64, generations: usize, generation_seconds: Option<f64>, clock: Option<PyObject>) -> PyResult<Self> {
+        let mut python_clock = None;
+        let rotation = match (generation_seconds, clock) {
+            (None, None) => Rotation::Manual,
+            (None, Some(_)) => {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("a clock needs generation_seconds"));
+            }
+            (Some(generation_seconds), clock) => {
+                let generation_duration = Duration::try_from_secs_f64(generation_seconds).map_err(|_| {
+                    PyErr::new::<pyo3::exceptions::PyValueError, _>("generation_seconds must be a positive number")
+                })?;
+                let clock: Arc<dyn Clock> = match clock {
+                    Some(clock) => {
+                        let clock = PythonClock::new(clock);
+                        Python::with_gil(|py| clock.read(py))?;
+                        let reading = Arc::new(clock.reading.clone());
+                        python_clock = Some(clock);
+                        reading
+                    }
+                    None => Arc::new(SystemClock::new()),
+                };
+                Rotation::Timed { generation_duration, clock }
+            }
+        };
+        let bloomfilter = SlidingWindowBloomFilterRS::with_rotation(expected_items_per_generation, desired_false_positive_rate, generations, rotation)?;
+        Ok(SlidingWindowBloomFilter { bloomfilter, clock: python_clock })
+    }
+
+    pub fn add(&mut self, py: Python, item: PyObject) -> PyResult<()> {
+        let mut py_bytes: Vec<u8> = Vec::new();
+        hash_pyobject(py, &item, &mut py_bytes)?;
+        self.read_clock(py)?;
+        self.bloomfilter.add_bytes(&py_bytes);
+        Ok(())
+    }
+
+    pub fn add_bulk(&mut self, py: Python, items: &PyAny) -> PyResult<()> {
+        if let Ok(item_iterator) = items.iter() {
+            for item in item_iterator {
+                self.add(py, item?.extract()?)?;
+            }
+        } else {
+            return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+                "Expected an iterable",
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn contains(&self, py: Python, item: PyObject) -> PyResult<bool> {
+        let mut py_bytes: Vec<u8> = Vec::new();
+        hash_pyobject(py, &item, &mut py_bytes)?;
+        self.read_clock(py)?;
+        Ok(self.bloomfilter.contains_bytes(&py_bytes))
+    }
+    pub fn advance(&mut self, py: Python) -> PyResult<()> {
+        self.read_clock(py)?;
+        self.bloomfilter.advance();
+        Ok(())
+    }
+    pub fn expire(&mut self, py: Python) -> PyResult<()> {
+        self.read_clock(py)?;
+        self.bloomfilter.expire();
+        Ok(())
+    }
+    pub fn clear(&mut self, py: Python) -> PyResult<()> {
+        self.read_clock(py)?;
+        self.bloomfilter.clear();
+        Ok(())
+    }
+    pub fn get_number_of_generations(&self) -> usize {
+        self.bloomfilter.get_generation_count()
+    }
+    pub fn get_number_of_bits(&self) -> usize {
+        self.bloomfilter.get_bit_count()
+    }
+    pub fn current_false_positive_rate(&self, py: Python) -> PyResult<f64> {
+        self.read_clock(py)?;
+        Ok(self.bloomfilter.current_false_positive_rate())
+    }
+}
+
+impl SlidingWindowBloomFilter {
+    /// Reads the user's clock, if any, so that the filter sees the current time
+    /// An exception of the clock aborts the operation before it changes the filter
+    fn read_clock(&self, py: Python) -> PyResult<()> {
+        match &self.clock {
+            Some(clock) => clock.read(py),
+            None => Ok(()),
+        }
+    }
+}
+
+
+// Stable Bloom Filter; deduplicates unbounded streams at a fixed false positive rate
+#[pyclass(module = "bloomlib")]
+struct StableBloomFilter {
+    bloomfilter: StableBloomFilterRS
+}
+
+#[pymethods]
+impl StableBloomFilter {
+    #[new]
+    #[args(max_value = "3", seed = "None")]
+    pub fn new(number_of_cells: usize, desired_false_positive_rate: f64, max_value: u32, seed: Option<u64>) -> PyResult<Self> {
+        let mut bloomfilter = StableBloomFilterRS::new(number_of_cells, max_value, desired_false_positive_rate)?;
+        if let Some(seed) = seed {
+            bloomfilter.set_seed(seed);
+        }
+        Ok(StableBloomFilter { bloomfilter })
+    }
+
+    pub fn add(&mut self, py: Python, item: PyObject) -> PyResult<()> {
+        let mut py_bytes: Vec<u8> = Vec::new();
+        hash_pyobject(py, &item, &mut py_bytes)?;
+        self.bloomfilter.add_bytes(&py_bytes);
+        Ok(())
+    }
+
+    pub fn add_bulk(&mut self, py: Python, items: &PyAny) -> PyResult<()> {
+        if let Ok(item_iterator) = items.iter() {
+            for item in item_iterator {
+                self.add(py, item?.extract()?)?;
+            }
+        } else {
+            return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+                "Expected an iterable",
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn contains(&self, py: Python, item: PyObject) -> PyResult<bool> {
+        let mut py_bytes: Vec<u8> = Vec::new();
+        hash_pyobject(py, &item, &mut py_bytes)?;
+        Ok(self.bloomfilter.contains_bytes(&py_bytes))
+    }
+    pub fn clear(&mut self) -> PyResult<()> {
+        self.bloomfilter.clear();
+        Ok(())
+    }
+    pub fn get_number_of_cells(&self) -> usize {
+        self.bloomfilter.get_cell_count()
+    }
+    pub fn get_number_of_hashes(&self) -> usize {
+        self.bloomfilter.get_hash_count()
+    }
+    pub fn get_max_value(&self) -> u32 {
+        self.bloomfilter.get_max_value()
+    }
+    pub fn get_decrements_per_insert(&self) -> usize {
+        self.bloomfilter.get_decrements_per_insert()
+    }
+    pub fn stable_false_positive_rate(&self) -> f64 {
+        self.bloomfilter.stable_false_positive_rate()
+    }
+    pub fn current_false_positive_rate(&self) -> f64 {
+        self.bloomfilter.current_false_positive_rate()
+    }
+}
+
+
+// Cuckoo Filter; supports removing items and needs fewer bits than a Bloom filter at low false positive rates
+#[pyclass(module = "bloomlib")]
+struct CuckooFilter {
+    bloomfilter: CuckooFilterRS
+}
+
+#[pymethods]
+impl CuckooFilter {
+    #[new]
+    #[args(fingerprint_bits = "None", bucket_size = "DEFAULT_BUCKET_SIZE", max_kicks = "DEFAULT_MAX_KICKS", seed = "None")]
+    pub fn new(expected_number_of_items: usize, desired_false_positive_rate: f64, fingerprint_bits: Option<usize>, bucket_size: usize, max_kicks: usize, seed: Option<u64>) -> PyResult<Self> {
+        let fingerprint_bits = fingerprint_bits.unwrap_or_else(|| calc_fingerprint_bits(bucket_size, desired_false_positive_rate));
+        let mut bloomfilter = CuckooFilterRS::with_parameters(expected_number_of_items, fingerprint_bits, bucket_size, max_kicks)?;
+        if let Some(seed) = seed {
+            bloomfilter.set_seed(seed);
+        }
+        Ok(CuckooFilter { bloomfilter })
+    }
+
+    pub fn add(&mut self, py: Python, item: PyObject) -> PyResult<()> {
+        let mut py_bytes: Vec<u8> = Vec::new();
+        hash_pyobject(py, &item, &mut py_bytes)?;
+        self.bloomfilter.add_bytes(&py_bytes)?;
+        Ok(())
+    }
+
+    pub fn add_bulk(&mut self, py: Python, items: &PyAny) -> PyResult<()> {
+        if let Ok(item_iterator) = items.iter() {
+            for item in item_iterator {
+                self.add(py, item?.extract()?)?;
+            }
+        } else {
+            return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+                "Expected an iterable",
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn remove(&mut self, py: Python, item: PyObject) -> PyResult<bool> {
+        let mut py_bytes: Vec<u8> = Vec::new();
+        hash_pyobject(py, &item, &mut py_bytes)?;
+        Ok(self.bloomfilter.remove_bytes(&py_bytes))
+    }
+
+    pub fn contains(&self, py: Python, item: PyObject) -> PyResult<bool> {
+        let mut py_bytes: Vec<u8> = Vec::new();
+        hash_pyobject(py, &item, &mut py_bytes)?;
+        Ok(self.bloomfilter.contains_bytes(&py_bytes))
+    }
+    pub fn clear(&mut self) -> PyResult<()> {
+        self.bloomfilter.clear();
+        Ok(())
+    }
+    pub fn get_number_of_bits(&self) -> usize {
+        self.bloomfilter.get_bit_count()
+    }
+    pub fn get_number_of_buckets(&self) -> usize {
+        self.bloomfilter.get_bucket_count()
+    }
+    pub fn get_bucket_size(&self) -> usize {
+        self.bloomfilter.get_bucket_size()
+    }
+    pub fn get_fingerprint_bits(&self) -> usize {
+        self.bloomfilter.get_fingerprint_bits()
+    }
+    pub fn get_inserted_count(&self) -> usize {
+        self.bloomfilter.get_inserted_count()
+    }
+    pub fn get_capacity(&self) -> usize {
+        self.bloomfilter.get_capacity()
+    }
+    pub fn load_factor(&self) -> f64 {
+        self.bloomfilter.load_factor()
+    }
+    pub fn estimate_false_positive_rate(&self) -> f64 {
+        self.bloomfilter.estimate_false_positive_rate()
+    }
+
+    pub fn to_bytes(&self, py: Python) -> PyObject {
+        PyBytes::new(py, &self.bloomfilter.to_bytes()).into()
+    }
+    #[staticmethod]
+    pub fn from_bytes(data: &[u8]) -> PyResult<Self> {
+        Ok(CuckooFilter {
+            bloomfilter: CuckooFilterRS::from_bytes(data)?,
+        })
+    }
+    pub fn save(&self, path: &str) -> PyResult<()> {
+        std::fs::write(path, self.bloomfilter.to_bytes())?;
+        Ok(())
+    }
+    #[staticmethod]
+    pub fn load(path: &str) -> PyResult<Self> {
+        let data = std::fs::read(path)?;
+        CuckooFilter::from_bytes(&data)
+    }
+
+    // Pickle support
+    pub fn __getstate__(&self, py: Python) -> PyObject {
+        self.to_bytes(py)
+    }
+    pub fn __setstate__(&mut self, state: &[u8]) -> PyResult<()> {
+        self.bloomfilter = CuckooFilterRS::from_bytes(state)?;
+        Ok(())
+    }
+    pub fn __reduce__(&self, py: Python) -> (Py<PyType>, (usize, f64), PyObject) {
+        // Unpickling constructs the smallest possible filter and replaces it via __setstate__
+        (py.get_type::<CuckooFilter>().into(), (1, 0.5), self.__getstate__(py))
+    }
+}
+
+// `len(cf)` is the number of items in the filter
+#[pyproto]
+impl PySequenceProtocol for CuckooFilter {
+    fn __len__(&self) -> PyResult<usize> {
+        Ok(self.bloomfilter.get_inserted_count())
+    }
+}
+
+
+// Quotient Filter; supports removing items, doubling and merging without the original items
+#[pyclass(module = "bloomlib")]
+struct QuotientFilter {
+    bloomfilter: QuotientFilterRS
+}
+
+#[pymethods]
+impl QuotientFilter {
+    #[new]
+    pub fn new(expected_number_of_items: usize, desired_false_positive_rate: f64) -> Self {
+        QuotientFilter {
+            bloomfilter: QuotientFilterRS::new(expected_number_of_items, desired_false_positive_rate),
+        }
+    }
+
+    pub fn add(&mut self, py: Python, item: PyObject) -> PyResult<()> {
+        let mut py_bytes: Vec<u8> = Vec::new();
+        hash_pyobject(py, &item, &mut py_bytes)?;
+        self.bloomfilter.add_bytes(&py_bytes)?;
+        Ok(())
+    }
+
+    pub fn add_bulk(&mut self, py: Python, items: &PyAny) -> PyResult<()> {
+        if let Ok(item_iterator) = items.iter() {
+            for item in item_iterator {
+                self.add(py, item?.extract()?)?;
+            }
+        } else {
+            return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+                "Expected an iterable",
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn remove(&mut self, py: Python, item: PyObject) -> PyResult<bool> {
+        let mut py_bytes: Vec<u8> = Vec::new();
+        hash_pyobject(py, &item, &mut py_bytes)?;
+        Ok(self.bloomfilter.remove_bytes(&py_bytes))
+    }
+
+    pub fn contains(&self, py: Python, item: PyObject) -> PyResult<bool> {
+        let mut py_bytes: Vec<u8> = Vec::new();
+        hash_pyobject(py, &item, &mut py_bytes)?;
+        Ok(self.bloomfilter.contains_bytes(&py_bytes))
+    }
+    pub fn clear(&mut self) -> PyResult<()> {
+        self.bloomfilter.clear();
+        Ok(())
+    }
+    pub fn double(&mut self) -> PyResult<()> {
+        self.bloomfilter.double()?;
+        Ok(())
+    }
+    pub fn merge(&self, other: &QuotientFilter) -> PyResult<QuotientFilter> {
+        Ok(QuotientFilter {
+            bloomfilter: self.bloomfilter.merge(&other.bloomfilter)?,
+        })
+    }
+    pub fn get_number_of_bits(&self) -> usize {
+        self.bloomfilter.get_bit_count()
+    }
+    pub fn get_number_of_slots(&self) -> usize {
+        self.bloomfilter.get_slot_count()
+    }
+    pub fn get_quotient_bits(&self) -> usize {
+        self.bloomfilter.get_quotient_bits()
+    }
+    pub fn get_remainder_bits(&self) -> usize {
+        self.bloomfilter.get_remainder_bits()
+    }
+    pub fn get_inserted_count(&self) -> usize {
+        self.bloomfilter.get_inserted_count()
+    }
+    pub fn load_factor(&self) -> f64 {
+        self.bloomfilter.load_factor()
+    }
+    pub fn current_false_positive_rate(&self) -> f64 {
+        self.bloomfilter.current_false_positive_rate()
+    }
+
+    pub fn to_bytes(&self, py: Python) -> PyObject {
+        PyBytes::new(py, &self.bloomfilter.to_bytes()).into()
+    }
+    #[staticmethod]
+    pub fn from_bytes(data: &[u8]) -> PyResult<Self> {
+        Ok(QuotientFilter {
+            bloomfilter: QuotientFilterRS::from_bytes(data)?,
+        })
+    }
+    pub fn save(&self, path: &str) -> PyResult<()> {
+        std::fs::write(path, self.bloomfilter.to_bytes())?;
+        Ok(())
+    }
+    #[staticmethod]
+    pub fn load(path: &str) -> PyResult<Self> {
+        let data = std::fs::read(path)?;
+        QuotientFilter::from_bytes(&data)
+    }
+
+    // Pickle support
+    pub fn __getstate__(&self, py: Python) -> PyObject {
+        self.to_bytes(py)
+    }
+    pub fn __setstate__(&mut self, state: &[u8]) -> PyResult<()> {
+        self.bloomfilter = QuotientFilterRS::from_bytes(state)?;
+        Ok(())
+    }
+    pub fn __reduce__(&self, py: Python) -> (Py<PyType>, (usize, f64), PyObject) {
+        // Unpickling constructs the smallest possible filter and replaces it via __setstate__
+        (py.get_type::<QuotientFilter>().into(), (1, 0.5), self.__getstate__(py))
+    }
+}
+
+// `len(qf)` is the number of items in the filter
+#[pyproto]
+impl PySequenceProtocol for QuotientFilter {
+    fn __len__(&self) -> PyResult<usize> {
+        Ok(self.bloomfilter.get_inserted_count())
+    }
+}
+
+
+// Binary Fuse Filter; immutable, built once from all of its items
+#[pyclass(module = "bloomlib")]
+struct BinaryFuseFilter {
+    bloomfilter: AnyBinaryFuseFilter
+}
+
+#[pymethods]
+impl BinaryFuseFilter {
+    #[new]
+    #[args(fingerprint_bits = "8", max_attempts = "DEFAULT_MAX_ATTEMPTS")]
+    pub fn new(py: Python, items: &PyAny, fingerprint_bits: usize, max_attempts: usize) -> PyResult<Self> {
+        let mut keys: Vec<Vec<u8>> = Vec::new();
+        if let Ok(item_iterator) = items.iter() {
+            for item in item_iterator {
+                let item: PyObject = item?.extract()?;
+                let mut py_bytes: Vec<u8> = Vec::new();
+                hash_pyobject(py, &item, &mut py_bytes)?;
+                keys.push(py_bytes);
+            }
+        } else {
+            return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+                "Expected an iterable",
+            ));
+        }
+        Ok(BinaryFuseFilter {
+            bloomfilter: AnyBinaryFuseFilter::from_byte_keys(&keys, fingerprint_bits, max_attempts)?,
+        })
+    }
+
+    pub fn contains(&self, py: Python, item: PyObject) -> PyResult<bool> {
+        let mut py_bytes: Vec<u8> = Vec::new();
+        hash_pyobject(py, &item, &mut py_bytes)?;
+        Ok(self.bloomfilter.contains_bytes(&py_bytes))
+    }
+    pub fn get_number_of_bits(&self) -> usize {
+        self.bloomfilter.get_bit_count()
+    }
+    pub fn get_fingerprint_bits(&self) -> usize {
+        self.bloomfilter.get_fingerprint_bits()
+    }
+    pub fn get_item_count(&self) -> usize {
+        self.bloomfilter.get_item_count()
+    }
+    pub fn bits_per_item(&self) -> f64 {
+        self.bloomfilter.bits_per_item()
+    }
+    pub fn estimate_false_positive_rate(&self) -> f64 {
+        self.bloomfilter.estimate_false_positive_rate()
+    }
+
+    pub fn to_bytes(&self, py: Python) -> PyObject {
+        PyBytes::new(py, &self.bloomfilter.to_bytes()).into()
+    }
+    #[staticmethod]
+    pub fn from_bytes(data: &[u8]) -> PyResult<Self> {
+        Ok(BinaryFuseFilter {
+            bloomfilter: AnyBinaryFuseFilter::from_bytes(data)?,
+        })
+    }
+    pub fn save(&self, path: &str) -> PyResult<()> {
+        std::fs::write(path, self.bloomfilter.to_bytes())?;
+        Ok(())
+    }
+    #[staticmethod]
+    pub fn load(path: &str) -> PyResult<Self> {
+        let data = std::fs::read(path)?;
+        BinaryFuseFilter::from_bytes(&data)
+    }
+
+    // Pickle support
+    pub fn __getstate__(&self, py: Python) -> PyObject {
+        self.to_bytes(py)
+    }
+    pub fn __setstate__(&mut self, state: &[u8]) -> PyResult<()> {
+        self.bloomfilter = AnyBinaryFuseFilter::from_bytes(state)?;
+        Ok(())
+    }
+    pub fn __reduce__(&self, py: Python) -> (Py<PyType>, (Vec<u8>,), PyObject) {
+        // Unpickling builds a filter without items and replaces it via __setstate__
+        (py.get_type::<BinaryFuseFilter>().into(), (Vec::new(),), self.__getstate__(py))
+    }
+}
+
+// `len(bf)` is the number of distinct items the filter was built from
+#[pyproto]
+impl PySequenceProtocol for BinaryFuseFilter {
+    fn __len__(&self) -> PyResult<usize> {
+        Ok(self.bloomfilter.get_item_count())
+    }
+}
+
+
+// Ribbon Filter; immutable, built once from all of its items, close to the minimal size for its false positive rate
+#[pyclass(module = "bloomlib")]
+struct RibbonFilter {
+    bloomfilter: RibbonFilterRS
+}
+
+#[pymethods]
+impl RibbonFilter {
+    #[new]
+    pub fn new(py: Python, items: &PyAny, desired_false_positive_rate: f64) -> PyResult<Self> {
+        let mut keys: Vec<Vec<u8>> = Vec::new();
+        if let Ok(item_iterator) = items.iter() {
+            for item in item_iterator {
+                let item: PyObject = item?.extract()?;
+                let mut py_bytes: Vec<u8> = Vec::new();
+                hash_pyobject(py, &item, &mut py_bytes)?;
+                keys.push(py_bytes);
+            }
+        } else {
+            return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+                "Expected an iterable",
+            ));
+        }
+        Ok(RibbonFilter {
+            bloomfilter: RibbonFilterRS::from_byte_keys(&keys, desired_false_positive_rate)?,
+        })
+    }
+
+    pub fn contains(&self, py: Python, item: PyObject) -> PyResult<bool> {
+        let mut py_bytes: Vec<u8> = Vec::new();
+        hash_pyobject(py, &item, &mut py_bytes)?;
+        Ok(self.bloomfilter.contains_bytes(&py_bytes))
+    }
+    pub fn get_number_of_bits(&self) -> usize {
+        self.bloomfilter.get_bit_count()
+    }
+    pub fn get_number_of_slots(&self) -> usize {
+        self.bloomfilter.get_slot_count()
+    }
+    pub fn get_result_bits(&self) -> usize {
+        self.bloomfilter.get_result_bits()
+    }
+    pub fn get_item_count(&self) -> usize {
+        self.bloomfilter.get_item_count()
+    }
+    pub fn bits_per_item(&self) -> f64 {
+        self.bloomfilter.bits_per_item()
+    }
+    pub fn estimate_false_positive_rate(&self) -> f64 {
+        self.bloomfilter.estimate_false_positive_rate()
+    }
+
+    pub fn to_bytes(&self, py: Python) -> PyObject {
+        PyBytes::new(py, &self.bloomfilter.to_bytes()).into()
+    }
+    #[staticmethod]
+    pub fn from_bytes(data: &[u8]) -> PyResult<Self> {
+        Ok(RibbonFilter {
+            bloomfilter: RibbonFilterRS::from_bytes(data)?,
+        })
+    }
+    pub fn save(&self, path: &str) -> PyResult<()> {
+        std::fs::write(path, self.bloomfilter.to_bytes())?;
+        Ok(())
+    }
+    #[staticmethod]
+    pub fn load(path: &str) -> PyResult<Self> {
+        let data = std::fs::read(path)?;
+        RibbonFilter::from_bytes(&data)
+    }
+
+    // Pickle support
+    pub fn __getstate__(&self, py: Python) -> PyObject {
+        self.to_bytes(py)
+    }
+    pub fn __setstate__(&mut self, state: &[u8]) -> PyResult<()> {
+        self.bloomfilter = RibbonFilterRS::from_bytes(state)?;
+        Ok(())
+    }
+    pub fn __reduce__(&self, py: Python) -> (Py<PyType>, (Vec<u8>, f64), PyObject) {
+        // Unpickling builds a filter without items and replaces it via __setstate__
+        (py.get_type::<RibbonFilter>().into(), (Vec::new(), 0.5), self.__getstate__(py))
+    }
+}
+
+// `len(rf)` is the number of items the filter was built from
+#[pyproto]
+impl PySequenceProtocol for RibbonFilter {
+    fn __len__(&self) -> PyResult<usize> {
+        Ok(self.bloomfilter.get_item_count())
+    }
+}
+
+
+// Invertible Bloom Filter (IBLT); lists the keys that differ between two sets
+#[pyclass(module = "bloomlib")]
+struct InvertibleBloomFilter {
+    bloomfilter: InvertibleBloomFilterRS
+}
+
+#[pymethods]
+impl InvertibleBloomFilter {
+    #[new]
+    #[args(number_of_hashes = "DEFAULT_NUMBER_OF_HASHES")]
+    pub fn new(expected_difference: usize, number_of_hashes: usize) -> PyResult<Self> {
+        Ok(InvertibleBloomFilter {
+            bloomfilter: InvertibleBloomFilterRS::with_hashes(expected_difference, number_of_hashes)?,
+        })
+    }
+
+    pub fn insert(&mut self, key: &[u8]) {
+        self.bloomfilter.insert(key);
+    }
+    pub fn delete(&mut self, key: &[u8]) {
+        self.bloomfilter.delete(key);
+    }
+    pub fn subtract(&self, other: &InvertibleBloomFilter) -> PyResult<InvertibleBloomFilter> {
+        Ok(InvertibleBloomFilter {
+            bloomfilter: self.bloomfilter.subtract(&other.bloomfilter)?,
+        })
+    }
+    pub fn list_entries(&self, py: Python) -> PyResult<(Vec<PyObject>, Vec<PyObject>)> {
+        let entries = self.bloomfilter.list_entries()?;
+        let to_py_bytes = |keys: Vec<Vec<u8>>| -> Vec<PyObject> {
+            keys.iter().map(|key| PyBytes::new(py, key).into()).collect()
+        };
+        Ok((to_py_bytes(entries.inserted), to_py_bytes(entries.deleted)))
+    }
+    pub fn is_empty(&self) -> bool {
+        self.bloomfilter.is_empty()
+    }
+    pub fn clear(&mut self) -> PyResult<()> {
+        self.bloomfilter.clear();
+        Ok(())
+    }
+    pub fn get_number_of_cells(&self) -> usize {
+        self.bloomfilter.get_cell_count()
+    }
+    pub fn get_number_of_hashes(&self) -> usize {
+        self.bloomfilter.get_hash_count()
+    }
+
+    pub fn to_bytes(&self, py: Python) -> PyObject {
+        PyBytes::new(py, &self.bloomfilter.to_bytes()).into()
+    }
+    #[staticmethod]
+    pub fn from_bytes(data: &[u8]) -> PyResult<Self> {
+        Ok(InvertibleBloomFilter {
+            bloomfilter: InvertibleBloomFilterRS::from_bytes(data)?,
+        })
+    }
+    pub fn save(&self, path: &str) -> PyResult<()> {
+        std::fs::write(path, self.bloomfilter.to_bytes())?;
+        Ok(())
+    }
+    #[staticmethod]
+    pub fn load(path: &str) -> PyResult<Self> {
+        let data = std::fs::read(path)?;
+        InvertibleBloomFilter::from_bytes(&data)
+    }
+
+    // Pickle support
+    pub fn __getstate__(&self, py: Python) -> PyObject {
+        self.to_bytes(py)
+    }
+    pub fn __setstate__(&mut self, state: &[u8]) -> PyResult<()> {
+        self.bloomfilter = InvertibleBloomFilterRS::from_bytes(state)?;
+        Ok(())
+    }
+    pub fn __reduce__(&self, py: Python) -> (Py<PyType>, (usize,), PyObject) {
+        // Unpickling constructs the smallest possible filter and replaces it via __setstate__
+        (py.get_type::<InvertibleBloomFilter>().into(), (0,), self.__getstate__(py))
+    }
+}
+
+
+// Bloomier Filter; an immutable map from items to small integers that returns arbitrary values for other items
+#[pyclass(module = "bloomlib")]
+struct BloomierFilter {
+    bloomfilter: AnyBloomierFilter
+}
+
+#[pymethods]
+impl BloomierFilter {
+    #[new]
+    #[args(value_bits = "None", max_attempts = "DEFAULT_MAX_ATTEMPTS")]
+    pub fn new(py: Python, items: &PyAny, value_bits: Option<usize>, max_attempts: usize) -> PyResult<Self> {
+        // a mapping is read through its items, anything else has to be an iterable of (key, value) pairs
+        let items = if items.hasattr("items")? { items.call_method0("items")? } else { items };
+        let mut pairs: Vec<(Vec<u8>, u64)> = Vec::new();
+        if let Ok(item_iterator) = items.iter() {
+            for item in item_iterator {
+                let (key, value): (PyObject, u64) = item?.extract()?;
+                let mut py_bytes: Vec<u8> = Vec::new();
+                hash_pyobject(py, &key, &mut py_bytes)?;
+                pairs.push((py_bytes, value));
+            }
+        } else {
+            return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+                "Expected a mapping or an iterable of (key, value) pairs",
+            ));
+        }
+        let value_bits = value_bits.unwrap_or_else(|| {
+            match pairs.iter().map(|(_, value)| *value).max().unwrap_or(0) {
+                value if value <= u8::MAX as u64 => 8,
+                value if value <= u16::MAX as u64 => 16,
+                _ => 32,
+            }
+        });
+        Ok(BloomierFilter {
+            bloomfilter: AnyBloomierFilter::from_byte_pairs(&pairs, value_bits, max_attempts)?,
+        })
+    }
+
+    pub fn get(&self, py: Python, item: PyObject) -> PyResult<u64> {
+        let mut py_bytes: Vec<u8> = Vec::new();
+        hash_pyobject(py, &item, &mut py_bytes)?;
+        Ok(self.bloomfilter.get_bytes(&py_bytes))
+    }
+    pub fn get_number_of_bits(&self) -> usize {
+        self.bloomfilter.get_bit_count()
+    }
+    pub fn get_value_bits(&self) -> usize {
+        self.bloomfilter.get_value_bits()
+    }
+    pub fn get_item_count(&self) -> usize {
+        self.bloomfilter.get_item_count()
+    }
+    pub fn bits_per_item(&self) -> f64 {
+        self.bloomfilter.bits_per_item()
+    }
+
+    pub fn to_bytes(&self, py: Python) -> PyObject {
+        PyBytes::new(py, &self.bloomfilter.to_bytes()).into()
+    }
+    #[staticmethod]
+    pub fn from_bytes(data: &[u8]) -> PyResult<Self> {
+        Ok(BloomierFilter {
+            bloomfilter: AnyBloomierFilter::from_bytes(data)?,
+        })
+    }
+    pub fn save(&self, path: &str) -> PyResult<()> {
+        std::fs::write(path, self.bloomfilter.to_bytes())?;
+        Ok(())
+    }
+    #[staticmethod]
+    pub fn load(path: &str) -> PyResult<Self> {
+        let data = std::fs::read(path)?;
+        BloomierFilter::from_bytes(&data)
+    }
+
+    // Pickle support
+    pub fn __getstate__(&self, py: Python) -> PyObject {
+        self.to_bytes(py)
+    }
+    pub fn __setstate__(&mut self, state: &[u8]) -> PyResult<()> {
+        self.bloomfilter = AnyBloomierFilter::from_bytes(state)?;
+        Ok(())
+    }
+    pub fn __reduce__(&self, py: Python) -> (Py<PyType>, (Vec<u8>,), PyObject) {
+        // Unpickling builds a filter without items and replaces it via __setstate__
+        (py.get_type::<BloomierFilter>().into(), (Vec::new(),), self.__getstate__(py))
+    }
+}
+
+// `bf[item]` looks up the value of an item and `len(bf)` is the number of distinct items the filter was built from;
+// there is no `in` or iteration, since the filter doesn't know which items it was built from
+#[pyproto]
+impl PyMappingProtocol for BloomierFilter {
+    fn __getitem__(&self, item: PyObject) -> PyResult<u64> {
+        let gil = Python::acquire_gil();
+        self.get(gil.python(), item)
+    }
+    fn __len__(&self) -> PyResult<usize> {
+        Ok(self.bloomfilter.get_item_count())
+    }
+}
+
+
+/// A Python function returning seconds, e.g. `time.monotonic`, as seen by a timed `SlidingWindowBloomFilterRS`
+/// The filter's clock is `reading`, which only moves on `read`; calling the function up front lets its exceptions
+/// surface as the original Python error, and the filter sees one consistent time per operation
+struct PythonClock {
+    now: PyObject,
+    reading: ManualClock,
+}
+
+impl PythonClock {
+    fn new(now: PyObject) -> Self {
+        PythonClock { now, reading: ManualClock::new() }
+    }
+
+    /// Calls the function and moves `reading` forward to its result; a clock running backwards is ignored
+    fn read(&self, py: Python) -> PyResult<()> {
+        let seconds: f64 = self.now.call0(py)?.extract(py)?;
+        let now = Duration::try_from_secs_f64(seconds).map_err(|_| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>("clock must return a non-negative number of seconds")
+        })?;
+        self.reading.advance(now.saturating_sub(self.reading.now()));
+        Ok(())
+    }
+}
+
+
+/// Translates the `overflow_policy` argument of the Python classes
+/// "warn" emits a RuntimeWarning through Python's warnings module
+fn parse_overflow_policy(overflow_policy: &str) -> PyResult<OverflowPolicy> {
+    match overflow_policy {
+        "ignore" => Ok(OverflowPolicy::Ignore),
+        "error" => Ok(OverflowPolicy::Error),
+        "warn" => Ok(OverflowPolicy::Warn(Arc::new(|inserted_count, capacity| {
+            Python::with_gil(|py| {
+                let message = format!("Bloom filter was sized for {} items and now holds {}", capacity, inserted_count);
+                let warned = py.import("builtins")
+                    .and_then(|builtins| builtins.getattr("RuntimeWarning"))
+                    .and_then(|category| PyErr::warn(py, category, &message, 1));
+                if let Err(err) = warned {
+                    // picked up by the caller of `add`
+                    err.restore(py);
+                }
+            })
+        }))),
+        _ => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            "overflow_policy must be one of 'ignore', 'warn' or 'error'",
+        )),
+    }
+}
+
+/// Replaces the callback of a deserialized "warn" overflow policy, which only knows the policy kind, by the one
+/// that raises a Python `RuntimeWarning`
+fn restore_overflow_warning(bloomfilter: &mut BloomFilterRS) {
+    if let OverflowPolicy::Warn(_) = bloomfilter.get_overflow_policy() {
+        bloomfilter.set_overflow_policy(parse_overflow_policy("warn").unwrap());
+    }
+}
+
+/// Translates the `seed` argument of the Python classes into a 128 bit key
+/// Accepts 16 bytes or a positive int below 2**128 (stored little-endian); the all-zero key is rejected, as it is
+/// the public key of unkeyed SipHash and would offer no protection
+fn parse_seed(seed: &PyAny) -> PyResult<[u8; 16]> {
+    let key: [u8; 16] = if let Ok(seed_bytes) = seed.downcast::<PyBytes>() {
+        seed_bytes.as_bytes().try_into().map_err(|_| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>("a bytes seed must be exactly 16 bytes long")
+        })?
+    } else {
+        match seed.extract::<u128>() {
+            Ok(seed) => seed.to_le_bytes(),
+            Err(_) => return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "seed must be 16 bytes or an int between 1 and 2**128 - 1",
+            )),
+        }
+    };
+    if key == [0; 16] {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>("seed must not be all zeros"));
+    }
+    Ok(key)
+}
+
+
+/// Encodes a Python value with Parquet's PLAIN encoding, the bytes Parquet hashes into its bloom filters
+/// bytes as-is, str as UTF-8, int as INT64 and float as DOUBLE (both little-endian); INT32 and FLOAT columns need
+/// their 4 byte encoding passed as bytes
+fn parquet_plain_bytes(item: &PyAny) -> PyResult<Vec<u8>> {
+    if let Ok(item) = item.downcast::<PyBytes>() {
+        return Ok(item.as_bytes().to_vec());
+    }
+    if let Ok(item) = item.downcast::<PyString>() {
+        return Ok(item.to_str()?.as_bytes().to_vec());
+    }
+    if item.downcast::<PyBool>().is_err() {
+        if let Ok(item) = item.downcast::<PyLong>() {
+            return Ok(item.extract::<i64>()?.to_le_bytes().to_vec());
+        }
+        if let Ok(item) = item.downcast::<PyFloat>() {
+            return Ok(item.value().to_le_bytes().to_vec());
+        }
+    }
+    Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+        "SplitBlockBloomFilter accepts bytes, str, int or float values",
+    ))
+}
+
+
+/// Hashes Python Objects. Returns Bytes
+/// The bytes only depend on the value of the object, so they are the same in every process and on every platform
+/// and a saved filter can be loaded elsewhere. Objects without such a value (anything not handled below) are
+/// rejected with a TypeError instead of falling back to `__hash__`, which Python randomizes per process
+fn hash_pyobject(py: Python, obj: &PyObject, output: &mut Vec<u8>) -> PyResult<()> {
+    // SipHash-1-3 with the zero key, which is what `DefaultHasher` uses today; unlike `DefaultHasher` the algorithm
+    // is fixed, and everything is written little-endian
+    let mut hasher = SipHasher13::new();
+
+    let py_any = obj.as_ref(py);
+
+    match py_any {
+        obj if obj.is_none() => {
+            hasher.write(&[NONE_TAG])
+        },
+
+        // Combine integer types
+        obj if obj.cast_as::<PyInt>().is_ok() || obj.cast_as::<PyLong>().is_ok() => {
+            hasher.write(&obj.extract::<i64>()?.to_le_bytes())
+        },
+
+        // Floats
+        obj if obj.cast_as::<PyFloat>().is_ok() => {
+            hasher.write(&obj.extract::<f64>()?.to_bits().to_le_bytes())
+        },
+
+        // Booleans
+        obj if obj.cast_as::<PyBool>().is_ok() => {
+            hasher.write(&[obj.extract::<bool>()? as u8])
+        },
+
+        // Bytes are length-prefixed and end in a tag of their own, so they never collide with an equal str
+        obj if obj.cast_as::<PyBytes>().is_ok() || obj.cast_as::<PyByteArray>().is_ok() => {
+            let bytes: Vec<u8> = obj.extract()?;
+            hasher.write(&(bytes.len() as u64).to_le_bytes());
+            hasher.write(&bytes);
+            hasher.write(&[BYTES_TAG]);
+        },
+
+        // Sets are hashed from the sorted hashes of their elements; their str() follows the randomized element hashes
+        obj if obj.cast_as::<PySet>().is_ok() || obj.cast_as::<PyFrozenSet>().is_ok() => {
+            let mut element_hashes = Vec::new();
+            for element in obj.iter()? {
+                let mut element_hash = Vec::new();
+                hash_pyobject(py, &element?.to_object(py), &mut element_hash)?;
+                element_hashes.push(element_hash);
+            }
+            element_hashes.sort();
+            hasher.write(&[SET_TAG]);
+            element_hashes.iter().for_each(|element_hash| hasher.write(element_hash));
+        },
+
+        // Combine string and various collections into one case
+        obj if obj.cast_as::<PyString>().is_ok()
+            || obj.cast_as::<PyList>().is_ok()
+            || obj.cast_as::<PyDict>().is_ok()
+            || obj.cast_as::<PyTuple>().is_ok() => {
+            write_str(&mut hasher, &obj.str()?.to_string())
+        },
+
+        // Date and time types
+        obj if obj.cast_as::<PyDate>().is_ok()
+            || obj.cast_as::<PyDateTime>().is_ok()
+            || obj.cast_as::<PyTime>().is_ok() => {
+            write_str(&mut hasher, &obj.call_method0("isoformat")?.to_string())
+        },
+
+        // Functions get converted to string and hashed
+        obj if obj.cast_as::<PyFunction>().is_ok() => {
+            write_str(&mut hasher, &obj.str()?.to_string())
+        },
+
+        // Default case for other types
+        obj => {
+            return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(format!(
+                "cannot hash an object of type {} by value", obj.get_type().name()?
+            )));
+        },
+    };
+
+    let hash_bytes = hasher.finish().to_le_bytes();
+    output.extend_from_slice(&hash_bytes);
+
+    Ok(())
+
+}
+
+/// Tags that end the hashed bytes of values without a str() of their own; a str ends in 0xff (as in `Hash for str`)
+const NONE_TAG: u8 = 0xfe;
+const BYTES_TAG: u8 = 0xfd;
+const SET_TAG: u8 = 0xfc;
+
+/// Writes a string the way `Hash for str` does: its bytes followed by 0xff
+fn write_str(hasher: &mut SipHasher13, string: &str) {
+    hasher.write(string.as_bytes());
+    hasher.write_u8(0xff);
+}
 
 /// Create the Python module
 #[pymodule]
 fn bloomlib(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_class::<BloomFilter>()?;
+    m.add_class::<CountingBloomFilter>()?;
+    m.add_class::<BlockedBloomFilter>()?;
+    m.add_class::<SplitBlockBloomFilter>()?;
+    m.add_class::<ScalableBloomFilter>()?;
+    m.add_class::<SlidingWindowBloomFilter>()?;
+    m.add_class::<StableBloomFilter>()?;
+    m.add_class::<CuckooFilter>()?;
+    m.add_class::<QuotientFilter>()?;
+    m.add_class::<BinaryFuseFilter>()?;
+    m.add_class::<RibbonFilter>()?;
+    m.add_class::<InvertibleBloomFilter>()?;
+    m.add_class::<BloomierFilter>()?;
 //     m.add_function(wrap_pyfunction!(estimate_false_positive_rate, m)?)?;
     Ok(())
 }
@@ -0,0 +1,608 @@
+//! Quotient filter in Rust (Bender et al., "Don't Thrash: How to Cache Your Hash on Flash", 2012)
+//! Every item is reduced to a `q + r` bit fingerprint. The upper `q` bits (the quotient) pick a slot and the lower
+//! `r` bits (the remainder) are stored in it; collisions are shifted into the following slots, and three metadata
+//! bits per slot are enough to recover the quotient of every stored remainder.
+//! Because the fingerprints can be recovered without the original items, the filter can be doubled in size (one
+//! remainder bit moves into the quotient) and two filters can be merged.
+
+use serde::Serialize;
+use std::collections::VecDeque;
+use bitvec::prelude::*;
+use crate::error::BloomError;
+use crate::hashing::{AnyHasher, BloomHasher, HashAlgorithm};
+use crate::serialization::{self, ByteReader, ByteWriter};
+
+
+/// Magic number that every serialized QuotientFilterRS starts with
+const MAGIC: &[u8; 4] = b"BLMQ";
+/// Version of the binary format that `to_bytes` writes
+const FORMAT_VERSION: u16 = 1;
+/// Fraction of the slots `new` and `merge` plan to fill; clusters, and with them lookups, grow quickly above it
+pub const MAX_LOAD_FACTOR: f64 = 0.75;
+/// Largest number of quotient bits; the filter allocates `2^q` slots
+pub const MAX_QUOTIENT_BITS: usize = 40;
+
+
+/// A Quotient Filter; an approximate multiset that supports removing items, doubling and merging
+#[derive(Clone)]
+pub struct QuotientFilterRS {
+    /// The stored remainders, `remainder_bits` per slot
+    remainders: BitVec,
+    /// Set on slot `i` when some item has quotient `i`
+    occupied: BitVec,
+    /// Set when the slot holds a remainder of the same quotient as the slot before it
+    continuation: BitVec,
+    /// Set when the slot holds a remainder that isn't in its canonical slot
+    shifted: BitVec,
+    /// Number of quotient bits (q); the filter has `2^q` slots
+    quotient_bits: usize,
+    /// Number of remainder bits (r)
+    remainder_bits: usize,
+    /// Number of stored fingerprints
+    inserted_count: usize,
+    /// The hash function; the top `q + r` bits of its first hash are the fingerprint
+    hasher: AnyHasher,
+}
+
+impl QuotientFilterRS {
+    /// Creates a quotient filter sized for `expected_number_of_items` at `MAX_LOAD_FACTOR`
+    /// The false positive rate of a quotient filter with `n` items is about `n / 2^(q+r)`, so the fingerprint gets
+    /// `ceil(log2(n / p))` bits
+    ///
+    /// # Arguments
+    /// * `expected_number_of_items` - Estimated number of items that the filter should accommodate
+    /// * `desired_false_positive_rate` - Desired/accepted false positive rate
+    pub fn new(expected_number_of_items: usize, desired_false_positive_rate: f64) -> Self {
+        let expected_number_of_items = expected_number_of_items.max(1) as f64;
+        let quotient_bits = ((expected_number_of_items / MAX_LOAD_FACTOR).log2().ceil() as usize).clamp(1, MAX_QUOTIENT_BITS);
+        let fingerprint_bits = ((expected_number_of_items / desired_false_positive_rate).log2().ceil() as usize).min(64);
+        let remainder_bits = fingerprint_bits.saturating_sub(quotient_bits).max(1).min(64 - quotient_bits);
+        QuotientFilterRS::with_parameters(quotient_bits, remainder_bits).expect("derived parameters are valid")
+    }
+
+    /// Creates a quotient filter with `2^quotient_bits` slots of `remainder_bits` bits each
+    /// Returns an error if the quotient bits are outside `1..=MAX_QUOTIENT_BITS`, the remainder bits are 0 or the
+    /// fingerprint would be wider than 64 bits
+    ///
+    /// # Arguments
+    /// * `quotient_bits` - number of quotient bits (q)
+    /// * `remainder_bits` - number of remainder bits (r)
+    pub fn with_parameters(quotient_bits: usize, remainder_bits: usize) -> Result<Self, BloomError> {
+        if !(1..=MAX_QUOTIENT_BITS).contains(&quotient_bits) {
+            return Err(BloomError::InvalidParameter(format!("quotient bits must be between 1 and {}, got {}", MAX_QUOTIENT_BITS, quotient_bits)));
+        }
+        if remainder_bits == 0 || quotient_bits + remainder_bits > 64 {
+            return Err(BloomError::InvalidParameter(format!(
+                "remainder bits must be at least 1 and at most {}, got {}", 64 - quotient_bits, remainder_bits
+            )));
+        }
+        let num_of_slots = 1usize << quotient_bits;
+        Ok(QuotientFilterRS {
+            remainders: BitVec::repeat(false, num_of_slots * remainder_bits),
+            occupied: BitVec::repeat(false, num_of_slots),
+            continuation: BitVec::repeat(false, num_of_slots),
+            shifted: BitVec::repeat(false, num_of_slots),
+            quotient_bits,
+            remainder_bits,
+            inserted_count: 0,
+            hasher: AnyHasher::default(),
+        })
+    }
+
+    /// Adds bytes to the filter; adding an item twice stores it twice
+    /// Returns `BloomError::CapacityExceeded` if every slot is in use; `double` makes room
+    ///
+    /// # Arguments
+    /// * `hash_bytes` - item to insert into the filter
+    pub fn add_bytes(&mut self, hash_bytes: &[u8]) -> Result<(), BloomError> {
+        self.insert_fingerprint(self.fingerprint(hash_bytes))
+    }
+
+    /// Hashes an item to the filter
+    /// Returns `BloomError::CapacityExceeded` if every slot is in use
+    ///
+    /// # Arguments
+    /// * `item` - item to insert into the filter
+    pub fn add<T: Serialize>(&mut self, item: &T) -> Result<(), BloomError> {
+        self.add_bytes(&serialization::serialize(item))
+    }
+
+    /// Checks if given bytes may be contained by the filter
+    /// Returns boolean: False means that the item definitely isn't contained.
+    /// True means that the item may be contained in the filter
+    ///
+    /// # Arguments
+    /// * `hash_bytes` - bytes to check for membership
+    pub fn contains_bytes(&self, hash_bytes: &[u8]) -> bool {
+        let (quotient, remainder) = self.split(self.fingerprint(hash_bytes));
+        if !self.occupied[quotient] {
+            return false;
+        }
+        let (start, len) = self.cluster(quotient);
+        let offset = self.offset(start, quotient);
+        self.decode(start, len).contains(&(offset, remainder))
+    }
+
+    /// Checks if a given item may be contained by the filter
+    ///
+    /// # Arguments
+    /// * `item` - item to be hashed and checked for membership
+    pub fn contains<T: Serialize>(&self, item: &T) -> bool {
+        self.contains_bytes(&serialization::serialize(item))
+    }
+
+    /// Removes bytes that were added before by deleting one copy of their fingerprint
+    /// Only remove items that were actually added: removing an item that merely shares a fingerprint with an added
+    /// item removes that item instead
+    /// Returns false (and leaves the filter untouched) when the item isn't contained
+    ///
+    /// # Arguments
+    /// * `hash_bytes` - item to remove from the filter
+    pub fn remove_bytes(&mut self, hash_bytes: &[u8]) -> bool {
+        self.remove_fingerprint(self.fingerprint(hash_bytes))
+    }
+
+    /// Removes an item that was added before
+    ///
+    /// # Arguments
+    /// * `item` - item to remove from the filter
+    pub fn remove<T: Serialize>(&mut self, item: &T) -> bool {
+        self.remove_bytes(&serialization::serialize(item))
+    }
+
+    /// Doubles the number of slots by moving one bit from every remainder into its quotient
+    /// The fingerprints, and so the false positive rate, stay the same; no original items are needed
+    /// Returns an error when the remainders have a single bit left or the quotient would exceed `MAX_QUOTIENT_BITS`
+    pub fn double(&mut self) -> Result<(), BloomError> {
+        if self.remainder_bits == 1 {
+            return Err(BloomError::InvalidParameter("cannot double a filter with 1 remainder bit".to_string()));
+        }
+        let mut doubled = QuotientFilterRS::with_parameters(self.quotient_bits + 1, self.remainder_bits - 1)?;
+        doubled.hasher = self.hasher;
+        for fingerprint in self.fingerprints() {
+            doubled.insert_fingerprint(fingerprint)?;
+        }
+        *self = doubled;
+        Ok(())
+    }
+
+    /// Merges two filters into a new filter that contains the items of both
+    /// The result has the larger of the two quotients, doubled until the items fit under `MAX_LOAD_FACTOR`
+    /// Returns `BloomError::Incompatible` if the filters use different fingerprint sizes or hash algorithms
+    ///
+    /// # Arguments
+    /// * `other` - filter to merge with
+    pub fn merge(&self, other: &QuotientFilterRS) -> Result<QuotientFilterRS, BloomError> {
+        if self.get_fingerprint_bits() != other.get_fingerprint_bits() {
+            return Err(BloomError::Incompatible(format!(
+                "number of fingerprint bits differs ({} vs {})", self.get_fingerprint_bits(), other.get_fingerprint_bits()
+            )));
+        }
+        if self.hasher.algorithm() != other.hasher.algorithm() {
+            return Err(BloomError::Incompatible(format!(
+                "hash algorithm differs ({:?} vs {:?})", self.hasher.algorithm(), other.hasher.algorithm()
+            )));
+        }
+        let fingerprint_bits = self.get_fingerprint_bits();
+        let total = self.inserted_count + other.inserted_count;
+        let mut quotient_bits = self.quotient_bits.max(other.quotient_bits);
+        while total as f64 > MAX_LOAD_FACTOR * (1usize << quotient_bits) as f64 && quotient_bits + 1 < fingerprint_bits.min(MAX_QUOTIENT_BITS + 1) {
+            quotient_bits += 1;
+        }
+        let mut merged = QuotientFilterRS::with_parameters(quotient_bits, fingerprint_bits - quotient_bits)?;
+        merged.hasher = self.hasher;
+        for fingerprint in self.fingerprints().into_iter().chain(other.fingerprints()) {
+            merged.insert_fingerprint(fingerprint)?;
+        }
+        Ok(merged)
+    }
+
+    /// Clears the filter
+    pub fn clear(&mut self) {
+        self.remainders.fill(false);
+        self.occupied.fill(false);
+        self.continuation.fill(false);
+        self.shifted.fill(false);
+        self.inserted_count = 0;
+    }
+
+    /// Calculates the false positive rate for the items currently in the filter
+    /// This is calculated by `1 - (1 - 1 / 2^(q+r))^n`
+    pub fn current_false_positive_rate(&self) -> f64 {
+        let fingerprint_collision = 0.5_f64.powi(self.get_fingerprint_bits() as i32);
+        1.0 - (1.0 - fingerprint_collision).powi(self.inserted_count as i32)
+    }
+
+    /// Calculates the fraction of slots that hold a remainder
+    pub fn load_factor(&self) -> f64 {
+        self.inserted_count as f64 / self.get_slot_count() as f64
+    }
+
+    /// Retrieve the number of items in the filter
+    pub fn get_inserted_count(&self) -> usize {
+        self.inserted_count
+    }
+
+    /// Retrieve the number of slots
+    pub fn get_slot_count(&self) -> usize {
+        self.occupied.len()
+    }
+
+    /// Retrieve the number of quotient bits (q)
+    pub fn get_quotient_bits(&self) -> usize {
+        self.quotient_bits
+    }
+
+    /// Retrieve the number of remainder bits (r)
+    pub fn get_remainder_bits(&self) -> usize {
+        self.remainder_bits
+    }
+
+    /// Retrieve the number of fingerprint bits (q + r)
+    pub fn get_fingerprint_bits(&self) -> usize {
+        self.quotient_bits + self.remainder_bits
+    }
+
+    /// Retrieve the number of bits in the filter, including the three metadata bits per slot
+    pub fn get_bit_count(&self) -> usize {
+        self.get_slot_count() * (self.remainder_bits + 3)
+    }
+
+    /// Serializes the filter to bytes: magic number, version, hash algorithm, parameters, the packed metadata bits
+    /// and the packed remainders
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = ByteWriter::with_capacity(21 + self.get_bit_count() / 8);
+        writer.write_bytes(MAGIC);
+        writer.write_u16(FORMAT_VERSION);
+        writer.write_u8(self.hasher.algorithm().id());
+        writer.write_u8(self.quotient_bits as u8);
+        writer.write_u8(self.remainder_bits as u8);
+        writer.write_u64(self.inserted_count as u64);
+        for bits in [&self.occupied, &self.continuation, &self.shifted, &self.remainders] {
+            writer.write_bytes(&serialization::pack_bits(bits));
+        }
+        writer.into_bytes()
+    }
+
+    /// Deserializes a filter written by `to_bytes`
+    /// Returns an error if the payload is truncated, has trailing bytes or a header that doesn't match
+    ///
+    /// # Arguments
+    /// * `bytes` - serialized quotient filter
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BloomError> {
+        let mut reader = ByteReader::new(bytes);
+        if reader.read_bytes(MAGIC.len())? != MAGIC {
+            return Err(BloomError::InvalidMagic);
+        }
+        let version = reader.read_u16()?;
+        if version != FORMAT_VERSION {
+            return Err(BloomError::UnsupportedVersion(version));
+        }
+        let hash_algorithm = HashAlgorithm::from_id(reader.read_u8()?)?;
+        let quotient_bits = reader.read_u8()? as usize;
+        let remainder_bits = reader.read_u8()? as usize;
+        let inserted_count = reader.read_usize()?;
+
+        let mut quotient_filter = QuotientFilterRS::with_parameters(quotient_bits, remainder_bits)
+            .map_err(|err| BloomError::InvalidPayload(err.to_string()))?;
+        let num_of_slots = quotient_filter.get_slot_count();
+        quotient_filter.occupied = serialization::unpack_bits(reader.read_bytes(num_of_slots.div_ceil(8))?, num_of_slots)?;
+        quotient_filter.continuation = serialization::unpack_bits(reader.read_bytes(num_of_slots.div_ceil(8))?, num_of_slots)?;
+        quotient_filter.shifted = serialization::unpack_bits(reader.read_bytes(num_of_slots.div_ceil(8))?, num_of_slots)?;
+        let num_of_bits = num_of_slots * remainder_bits;
+        quotient_filter.remainders = serialization::unpack_bits(reader.read_bytes(num_of_bits.div_ceil(8))?, num_of_bits)?;
+        reader.finish()?;
+        quotient_filter.hasher = AnyHasher::from(hash_algorithm);
+        quotient_filter.inserted_count = inserted_count;
+        if quotient_filter.fingerprints().len() != inserted_count {
+            return Err(BloomError::InvalidPayload("inserted count does not match the stored remainders".to_string()));
+        }
+        Ok(quotient_filter)
+    }
+
+    /// Derives the `q + r` bit fingerprint of an item
+    fn fingerprint(&self, hash_bytes: &[u8]) -> u64 {
+        let (h1, _) = self.hasher.hash_pair(hash_bytes);
+        h1 >> (64 - self.get_fingerprint_bits())
+    }
+
+    /// Splits a fingerprint into its quotient (the canonical slot) and remainder
+    fn split(&self, fingerprint: u64) -> (usize, u64) {
+        let remainder_mask = u64::MAX >> (64 - self.remainder_bits);
+        ((fingerprint >> self.remainder_bits) as usize, fingerprint & remainder_mask)
+    }
+
+    /// Inserts a fingerprint; the cluster it lands in is decoded, extended and written back
+    fn insert_fingerprint(&mut self, fingerprint: u64) -> Result<(), BloomError> {
+        if self.inserted_count == self.get_slot_count() {
+            return Err(BloomError::CapacityExceeded { capacity: self.get_slot_count() });
+        }
+        let (quotient, remainder) = self.split(fingerprint);
+        let (start, len) = self.cluster(quotient);
+        let mut entries = self.decode(start, len);
+        let entry = (self.offset(start, quotient), remainder);
+        let position = entries.partition_point(|existing| *existing < entry);
+        entries.insert(position, entry);
+        self.write_cluster(start, len, &entries);
+        self.inserted_count += 1;
+        Ok(())
+    }
+
+    /// Removes one copy of a fingerprint; the cluster it is in is decoded, shrunk and written back
+    fn remove_fingerprint(&mut self, fingerprint: u64) -> bool {
+        let (quotient, remainder) = self.split(fingerprint);
+        if !self.occupied[quotient] {
+            return false;
+        }
+        let (start, len) = self.cluster(quotient);
+        let entry = (self.offset(start, quotient), remainder);
+        let mut entries = self.decode(start, len);
+        match entries.iter().position(|existing| *existing == entry) {
+            Some(position) => {
+                entries.remove(position);
+                self.write_cluster(start, len, &entries);
+                self.inserted_count -= 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Every stored fingerprint, in slot order
+    fn fingerprints(&self) -> Vec<u64> {
+        let num_of_slots = self.get_slot_count();
+        let start = match (0..num_of_slots).find(|slot| self.is_filled(*slot) && !self.shifted[*slot]) {
+            Some(start) => start,
+            None => return Vec::new(),
+        };
+        self.decode(start, num_of_slots)
+            .into_iter()
+            .map(|(offset, remainder)| ((((start + offset) % num_of_slots) as u64) << self.remainder_bits) | remainder)
+            .collect()
+    }
+
+    /// Finds the run of filled slots around a canonical slot
+    /// Returns the first slot of the cluster (a filled slot in its canonical position, or the canonical slot itself
+    /// if it is empty) and the number of filled slots from there on
+    fn cluster(&self, quotient: usize) -> (usize, usize) {
+        let num_of_slots = self.get_slot_count();
+        let mut start = quotient;
+        if self.is_filled(start) {
+            while self.shifted[start] {
+                start = (start + num_of_slots - 1) % num_of_slots;
+            }
+        }
+        let len = (0..num_of_slots)
+            .find(|step| !self.is_filled((start + step) % num_of_slots))
+            .unwrap_or(num_of_slots);
+        (start, len)
+    }
+
+    /// Recovers the `(quotient offset from start, remainder)` of every remainder in `len` slots from `start`
+    /// `start` must hold a remainder in its canonical slot or be empty
+    fn decode(&self, start: usize, len: usize) -> Vec<(usize, u64)> {
+        let num_of_slots = self.get_slot_count();
+        let mut pending_quotients: VecDeque<usize> = VecDeque::new();
+        let mut current_quotient = 0;
+        let mut entries = Vec::with_capacity(len);
+        for offset in 0..len {
+            let slot = (start + offset) % num_of_slots;
+            if self.occupied[slot] {
+                pending_quotients.push_back(offset);
+            }
+            if !self.is_filled(slot) {
+                continue;
+            }
+            if !self.continuation[slot] {
+                current_quotient = pending_quotients.pop_front().unwrap_or(offset);
+            }
+            entries.push((current_quotient, self.get_remainder(slot)));
+        }
+        entries
+    }
+
+    /// Lays out sorted entries from `start`, each in the first free slot at or after its canonical slot, after
+    /// clearing the `old_len` slots they came from
+    fn write_cluster(&mut self, start: usize, old_len: usize, entries: &[(usize, u64)]) {
+        let num_of_slots = self.get_slot_count();
+        for offset in 0..old_len {
+            let slot = (start + offset) % num_of_slots;
+            self.occupied.set(slot, false);
+            self.continuation.set(slot, false);
+            self.shifted.set(slot, false);
+            self.set_remainder(slot, 0);
+        }
+        let mut next_offset = 0;
+        let mut previous_quotient = None;
+        for (quotient_offset, remainder) in entries {
+            let offset = next_offset.max(*quotient_offset);
+            let slot = (start + offset) % num_of_slots;
+            self.occupied.set((start + quotient_offset) % num_of_slots, true);
+            self.continuation.set(slot, previous_quotient == Some(*quotient_offset));
+            self.shifted.set(slot, offset != *quotient_offset);
+            self.set_remainder(slot, *remainder);
+            previous_quotient = Some(*quotient_offset);
+            next_offset = offset + 1;
+        }
+    }
+
+    /// Distance from `start` to `slot`, wrapping around the end of the table
+    fn offset(&self, start: usize, slot: usize) -> usize {
+        (slot + self.get_slot_count() - start) % self.get_slot_count()
+    }
+
+    /// A slot is empty when all three of its metadata bits are unset
+    fn is_filled(&self, slot: usize) -> bool {
+        self.occupied[slot] || self.continuation[slot] || self.shifted[slot]
+    }
+
+    fn get_remainder(&self, slot: usize) -> u64 {
+        let start = slot * self.remainder_bits;
+        self.remainders[start..start + self.remainder_bits].load_le::<u64>()
+    }
+
+    fn set_remainder(&mut self, slot: usize, remainder: u64) {
+        let start = slot * self.remainder_bits;
+        self.remainders[start..start + self.remainder_bits].store_le::<u64>(remainder);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_contains_and_remove() {
+        let mut quotient_filter = QuotientFilterRS::new(1_000, 0.01);
+        for i in 0..1_000 {
+            quotient_filter.add(&i).unwrap();
+        }
+        assert!((0..1_000).all(|i| quotient_filter.contains(&i)), "Quotient filter should have no false negatives");
+        assert_eq!(quotient_filter.get_inserted_count(), 1_000);
+        for i in 0..500 {
+            assert!(quotient_filter.remove(&i));
+        }
+        assert!((500..1_000).all(|i| quotient_filter.contains(&i)));
+        assert!((0..500).filter(|i| quotient_filter.contains(i)).count() < 20);
+        assert_eq!(quotient_filter.get_inserted_count(), 500);
+        assert_eq!(quotient_filter.fingerprints().len(), 500);
+    }
+
+    #[test]
+    fn test_duplicates_need_as_many_removes() {
+        let mut quotient_filter = QuotientFilterRS::new(100, 0.01);
+        quotient_filter.add(&"item").unwrap();
+        quotient_filter.add(&"item").unwrap();
+        assert!(quotient_filter.remove(&"item"));
+        assert!(quotient_filter.contains(&"item"));
+        assert!(quotient_filter.remove(&"item"));
+        assert!(!quotient_filter.contains(&"item"));
+        assert!(!quotient_filter.remove(&"item"));
+    }
+
+    #[test]
+    fn test_fills_every_slot_and_wraps_around() {
+        // 16 slots with tiny remainders force long, wrapping clusters
+        let mut quotient_filter = QuotientFilterRS::with_parameters(4, 4).unwrap();
+        let fingerprints: Vec<u64> = (0..16).map(|i| (15 - i % 3) << 4 | i).collect();
+        for fingerprint in &fingerprints {
+            quotient_filter.insert_fingerprint(*fingerprint).unwrap();
+        }
+        assert_eq!(
+            quotient_filter.insert_fingerprint(0),
+            Err(BloomError::CapacityExceeded { capacity: 16 })
+        );
+        let mut stored = quotient_filter.fingerprints();
+        stored.sort_unstable();
+        let mut expected = fingerprints.clone();
+        expected.sort_unstable();
+        assert_eq!(stored, expected);
+    }
+
+    #[test]
+    fn test_matches_a_multiset_under_random_operations() {
+        let mut quotient_filter = QuotientFilterRS::with_parameters(6, 3).unwrap();
+        let mut expected: Vec<u64> = Vec::new();
+        let mut state = 7;
+        for _ in 0..5_000 {
+            let random = crate::hashing::splitmix64(&mut state);
+            let fingerprint = random & 0x1ff;
+            if random >> 63 == 0 && expected.len() < 64 {
+                quotient_filter.insert_fingerprint(fingerprint).unwrap();
+                expected.push(fingerprint);
+            } else {
+                let position = expected.iter().position(|stored| *stored == fingerprint);
+                assert_eq!(quotient_filter.remove_fingerprint(fingerprint), position.is_some());
+                if let Some(position) = position {
+                    expected.remove(position);
+                }
+            }
+            let mut stored = quotient_filter.fingerprints();
+            stored.sort_unstable();
+            let mut sorted = expected.clone();
+            sorted.sort_unstable();
+            assert_eq!(stored, sorted);
+        }
+    }
+
+    #[test]
+    fn test_false_positive_rate() {
+        let mut quotient_filter = QuotientFilterRS::new(10_000, 0.01);
+        for i in 0..10_000 {
+            quotient_filter.add(&i).unwrap();
+        }
+        assert!(quotient_filter.current_false_positive_rate() <= 0.01);
+        let false_positives = (10_000..110_000).filter(|i| quotient_filter.contains(i)).count();
+        assert!((false_positives as f64 / 100_000.0) < 0.01);
+    }
+
+    #[test]
+    fn test_double_keeps_items() {
+        let mut quotient_filter = QuotientFilterRS::with_parameters(8, 12).unwrap();
+        for i in 0..256 {
+            quotient_filter.add(&i).unwrap();
+        }
+        assert!(quotient_filter.add(&256).is_err());
+        quotient_filter.double().unwrap();
+        assert_eq!((quotient_filter.get_quotient_bits(), quotient_filter.get_remainder_bits()), (9, 11));
+        assert_eq!(quotient_filter.get_inserted_count(), 256);
+        quotient_filter.add(&256).unwrap();
+        assert!((0..257).all(|i| quotient_filter.contains(&i)));
+        assert!(QuotientFilterRS::with_parameters(8, 1).unwrap().double().is_err());
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut first = QuotientFilterRS::new(1_000, 0.01);
+        let mut second = QuotientFilterRS::new(1_000, 0.01);
+        for i in 0..1_000 {
+            first.add(&i).unwrap();
+            second.add(&(i + 1_000)).unwrap();
+        }
+        let merged = first.merge(&second).unwrap();
+        assert_eq!(merged.get_inserted_count(), 2_000);
+        assert_eq!(merged.get_fingerprint_bits(), first.get_fingerprint_bits());
+        assert!(merged.load_factor() <= MAX_LOAD_FACTOR);
+        assert!((0..2_000).all(|i| merged.contains(&i)));
+        assert!(matches!(first.merge(&QuotientFilterRS::new(1_000, 0.0001)), Err(BloomError::Incompatible(_))));
+    }
+
+    #[test]
+    fn test_rejects_invalid_parameters() {
+        assert!(QuotientFilterRS::with_parameters(0, 8).is_err());
+        assert!(QuotientFilterRS::with_parameters(41, 8).is_err());
+        assert!(QuotientFilterRS::with_parameters(8, 0).is_err());
+        assert!(QuotientFilterRS::with_parameters(8, 57).is_err());
+        assert!(QuotientFilterRS::with_parameters(8, 56).is_ok());
+    }
+
+    #[test]
+    fn test_serialization_roundtrip() {
+        let mut quotient_filter = QuotientFilterRS::new(1_000, 0.001);
+        for i in 0..800 {
+            quotient_filter.add(&i).unwrap();
+        }
+        let bytes = quotient_filter.to_bytes();
+        let mut restored = QuotientFilterRS::from_bytes(&bytes).unwrap();
+        assert_eq!(restored.to_bytes(), bytes);
+        assert_eq!(restored.get_inserted_count(), 800);
+        assert!((0..800).all(|i| restored.contains(&i)));
+        assert!(restored.remove(&0));
+    }
+
+    #[test]
+    fn test_rejects_invalid_payloads() {
+        let mut quotient_filter = QuotientFilterRS::new(100, 0.01);
+        quotient_filter.add(&1).unwrap();
+        let bytes = quotient_filter.to_bytes();
+        assert_eq!(QuotientFilterRS::from_bytes(b"BLMC").err(), Some(BloomError::InvalidMagic));
+        assert!(matches!(QuotientFilterRS::from_bytes(&bytes[..bytes.len() - 1]), Err(BloomError::Truncated { .. })));
+        let mut wrong_count = bytes.clone();
+        wrong_count[9..17].copy_from_slice(&5u64.to_le_bytes());
+        assert!(matches!(QuotientFilterRS::from_bytes(&wrong_count), Err(BloomError::InvalidPayload(_))));
+        let mut wrong_bits = bytes.clone();
+        wrong_bits[8] = 0;
+        assert!(matches!(QuotientFilterRS::from_bytes(&wrong_bits), Err(BloomError::InvalidPayload(_))));
+    }
+}
@@ -0,0 +1,414 @@
+//! Standard Ribbon filter in Rust (Dillinger & Walzer, "Ribbon filter: practically smaller than Bloom and Xor", 2021)
+//! An immutable filter that is built once from all of its keys, in the layout RocksDB uses. Every key maps to a
+//! 128-bit band of coefficients starting at a hashed slot and to an `r` bit result; construction solves the linear
+//! system "XOR of the solution rows selected by a key's coefficients equals its result" by Gaussian elimination on
+//! the banded matrix. The solution takes `r` bits per slot and only a few percent more slots than keys, close to
+//! the `log2(1 / p)` bits per key minimum.
+
+use serde::Serialize;
+use crate::error::BloomError;
+use crate::hashing::{splitmix64, AnyHasher, BloomHasher, HashAlgorithm};
+use crate::serialization::{self, ByteReader, ByteWriter};
+
+
+/// Magic number that every serialized RibbonFilterRS starts with
+const MAGIC: &[u8; 4] = b"BLMR";
+/// Version of the binary format that `to_bytes` writes
+const FORMAT_VERSION: u16 = 1;
+/// Number of seeds construction tries before it gives up
+pub const DEFAULT_MAX_ATTEMPTS: usize = 100;
+/// Width of the coefficient band of every key
+const RIBBON_WIDTH: usize = 128;
+
+
+/// Calculates the number of result bits for a false positive rate
+/// This is calculated by `r = ceil(log2(1 / p))`; the false positive rate of the filter is `1 / 2^r`
+///
+/// # Arguments
+/// * `desired_false_positive_rate` - Desired/accepted false positive rate (p)
+pub fn calc_result_bits(desired_false_positive_rate: f64) -> usize {
+    ((1.0 / desired_false_positive_rate).log2().ceil() as usize).clamp(1, 32)
+}
+
+/// Calculates the extra slots, as a fraction of the number of keys, that construction needs to succeed on its
+/// first seed with high probability; it grows slowly with the number of keys
+/// This is calculated by `max(0.02, log2(n) / 400)`, e.g. 5% for a million keys
+///
+/// # Arguments
+/// * `number_of_keys` - number of keys the filter is built from
+pub fn calc_space_overhead(number_of_keys: usize) -> f64 {
+    ((number_of_keys.max(1) as f64).log2() / 400.0).max(0.02)
+}
+
+/// Finalizer of murmur3; spreads a seeded hash over all 64 bits
+fn mix(hash: u64) -> u64 {
+    let mut hash = hash;
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xff51afd7ed558ccd);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xc4ceb9fe1a85ec53);
+    hash ^ (hash >> 33)
+}
+
+
+/// A (standard) Ribbon Filter; an immutable approximate set built from all of its keys at once
+#[derive(Clone, Debug, PartialEq)]
+pub struct RibbonFilterRS {
+    /// The solution in column-major ("interleaved") layout: `result_bits` columns of `words_per_column` words, so a
+    /// query reads one 128-bit window per result bit
+    solution: Vec<u64>,
+    /// Number of result bits (r) per key
+    result_bits: usize,
+    /// Number of slots (rows of the solution)
+    num_of_slots: usize,
+    /// Seed that the successful construction attempt mixed into every key hash
+    seed: u64,
+    /// Number of keys the filter was built from
+    item_count: usize,
+    /// The hash function that turns a key into the hash pair the filter works on
+    hasher: AnyHasher,
+}
+
+impl RibbonFilterRS {
+    /// Builds a filter from keys as bytes, the same bytes `BloomFilterRS::add_bytes` takes
+    /// Returns an error if the false positive rate isn't in (0, 1) or no seed out of `DEFAULT_MAX_ATTEMPTS` works
+    ///
+    /// # Arguments
+    /// * `keys` - all keys the filter should contain; duplicates are allowed
+    /// * `desired_false_positive_rate` - Desired/accepted false positive rate; rounded down to a power of 1/2
+    pub fn from_byte_keys<K: AsRef<[u8]>>(keys: &[K], desired_false_positive_rate: f64) -> Result<Self, BloomError> {
+        if desired_false_positive_rate.is_nan() || desired_false_positive_rate <= 0.0 || desired_false_positive_rate >= 1.0 {
+            return Err(BloomError::InvalidParameter(format!(
+                "false positive rate must be between 0 and 1, got {}", desired_false_positive_rate
+            )));
+        }
+        let result_bits = calc_result_bits(desired_false_positive_rate);
+        RibbonFilterRS::from_byte_keys_with_options(keys, result_bits, calc_space_overhead(keys.len()), DEFAULT_MAX_ATTEMPTS)
+    }
+
+    /// Builds a filter from keys that are serialized the same way `BloomFilterRS::add` does
+    ///
+    /// # Arguments
+    /// * `keys` - all keys the filter should contain; duplicates are allowed
+    /// * `desired_false_positive_rate` - Desired/accepted false positive rate; rounded down to a power of 1/2
+    pub fn from_keys<T: Serialize>(keys: &[T], desired_false_positive_rate: f64) -> Result<Self, BloomError> {
+        let byte_keys: Vec<Vec<u8>> = keys.iter().map(serialization::serialize).collect();
+        RibbonFilterRS::from_byte_keys(&byte_keys, desired_false_positive_rate)
+    }
+
+    /// Builds a filter from keys as bytes with explicit parameters
+    /// Returns `BloomError::InvalidParameter` if a parameter is out of range and `BloomError::ConstructionFailed` if
+    /// no seed works; a larger space overhead makes construction more likely to succeed
+    ///
+    /// # Arguments
+    /// * `keys` - all keys the filter should contain; duplicates are allowed
+    /// * `result_bits` - bits per key (1..=32); the false positive rate is `1 / 2^result_bits`
+    /// * `space_overhead` - extra slots as a fraction of the number of keys, see `calc_space_overhead`
+    /// * `max_attempts` - number of seeds to try before giving up
+    pub fn from_byte_keys_with_options<K: AsRef<[u8]>>(keys: &[K], result_bits: usize, space_overhead: f64, max_attempts: usize) -> Result<Self, BloomError> {
+        if !(1..=32).contains(&result_bits) {
+            return Err(BloomError::InvalidParameter(format!("result bits must be between 1 and 32, got {}", result_bits)));
+        }
+        if space_overhead.is_nan() || space_overhead < 0.0 {
+            return Err(BloomError::InvalidParameter(format!("space overhead must be at least 0, got {}", space_overhead)));
+        }
+        if max_attempts == 0 {
+            return Err(BloomError::InvalidParameter("max attempts must be at least 1".to_string()));
+        }
+        let hasher = AnyHasher::default();
+        let key_hashes: Vec<(u64, u64)> = keys.iter().map(|key| hasher.hash_pair(key.as_ref())).collect();
+        let num_of_slots = ((key_hashes.len() as f64 * (1.0 + space_overhead)).ceil() as usize).max(RIBBON_WIDTH);
+        let mut ribbon_filter = RibbonFilterRS {
+            solution: Vec::new(),
+            result_bits,
+            num_of_slots,
+            seed: 0,
+            item_count: key_hashes.len(),
+            hasher,
+        };
+
+        let mut coefficients = vec![0u128; num_of_slots];
+        let mut results = vec![0u32; num_of_slots];
+        let mut random_state: u64 = 0x726b2b9d438b9d4d;
+        for _ in 0..max_attempts {
+            ribbon_filter.seed = splitmix64(&mut random_state);
+            coefficients.fill(0);
+            results.fill(0);
+            let banded = key_hashes.iter().all(|(h1, h2)| {
+                let (start, coefficient, result) = ribbon_filter.equation(*h1, *h2);
+                RibbonFilterRS::add_to_band(&mut coefficients, &mut results, start, coefficient, result)
+            });
+            if banded {
+                ribbon_filter.back_substitute(&coefficients, &results);
+                return Ok(ribbon_filter);
+            }
+        }
+        Err(BloomError::ConstructionFailed { attempts: max_attempts })
+    }
+
+    /// Checks if given bytes may be contained by the filter
+    /// Returns boolean: False means that the item definitely isn't contained.
+    /// True means that the item may be contained in the filter
+    ///
+    /// # Arguments
+    /// * `hash_bytes` - bytes to check for membership
+    pub fn contains_bytes(&self, hash_bytes: &[u8]) -> bool {
+        let (h1, h2) = self.hasher.hash_pair(hash_bytes);
+        let (start, coefficient, result) = self.equation(h1, h2);
+        (0..self.result_bits).all(|column| {
+            let parity = (self.window(column, start) & coefficient).count_ones() & 1;
+            parity == (result >> column) & 1
+        })
+    }
+
+    /// Checks if a given item may be contained by the filter
+    ///
+    /// # Arguments
+    /// * `item` - item to be hashed and checked for membership
+    pub fn contains<T: Serialize>(&self, item: &T) -> bool {
+        self.contains_bytes(&serialization::serialize(item))
+    }
+
+    /// Calculates the false positive rate, `1 / 2^r`
+    pub fn estimate_false_positive_rate(&self) -> f64 {
+        0.5_f64.powi(self.result_bits as i32)
+    }
+
+    /// Calculates the number of solution bits per key
+    pub fn bits_per_item(&self) -> f64 {
+        (self.num_of_slots * self.result_bits) as f64 / self.item_count.max(1) as f64
+    }
+
+    /// Retrieve the number of keys the filter was built from
+    pub fn get_item_count(&self) -> usize {
+        self.item_count
+    }
+
+    /// Retrieve the number of result bits per key
+    pub fn get_result_bits(&self) -> usize {
+        self.result_bits
+    }
+
+    /// Retrieve the number of slots
+    pub fn get_slot_count(&self) -> usize {
+        self.num_of_slots
+    }
+
+    /// Retrieve the number of bits in the solution
+    pub fn get_bit_count(&self) -> usize {
+        self.num_of_slots * self.result_bits
+    }
+
+    /// Serializes the filter to bytes: magic number, version, hash algorithm, parameters and the solution words
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = ByteWriter::with_capacity(32 + self.solution.len() * 8);
+        writer.write_bytes(MAGIC);
+        writer.write_u16(FORMAT_VERSION);
+        writer.write_u8(self.hasher.algorithm().id());
+        writer.write_u8(self.result_bits as u8);
+        writer.write_u64(self.seed);
+        writer.write_u64(self.num_of_slots as u64);
+        writer.write_u64(self.item_count as u64);
+        for word in &self.solution {
+            writer.write_u64(*word);
+        }
+        writer.into_bytes()
+    }
+
+    /// Deserializes a filter written by `to_bytes`
+    /// Returns an error if the payload is truncated, has trailing bytes or a header that doesn't match
+    ///
+    /// # Arguments
+    /// * `bytes` - serialized ribbon filter
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BloomError> {
+        let mut reader = ByteReader::new(bytes);
+        if reader.read_bytes(MAGIC.len())? != MAGIC {
+            return Err(BloomError::InvalidMagic);
+        }
+        let version = reader.read_u16()?;
+        if version != FORMAT_VERSION {
+            return Err(BloomError::UnsupportedVersion(version));
+        }
+        let hash_algorithm = HashAlgorithm::from_id(reader.read_u8()?)?;
+        let result_bits = reader.read_u8()? as usize;
+        let seed = reader.read_u64()?;
+        let num_of_slots = reader.read_usize()?;
+        let item_count = reader.read_usize()?;
+        if !(1..=32).contains(&result_bits) {
+            return Err(BloomError::InvalidPayload(format!("result bits must be between 1 and 32, got {}", result_bits)));
+        }
+        if num_of_slots < RIBBON_WIDTH {
+            return Err(BloomError::InvalidPayload(format!("{} slots is fewer than the ribbon width", num_of_slots)));
+        }
+        let num_of_words = (num_of_slots.div_ceil(64) + 2).saturating_mul(result_bits);
+        if num_of_words.saturating_mul(8) > reader.remaining() {
+            return Err(BloomError::Truncated { expected: num_of_words.saturating_mul(8), actual: reader.remaining() });
+        }
+        let solution = (0..num_of_words).map(|_| reader.read_u64()).collect::<Result<Vec<u64>, BloomError>>()?;
+        reader.finish()?;
+        Ok(RibbonFilterRS {
+            solution,
+            result_bits,
+            num_of_slots,
+            seed,
+            item_count,
+            hasher: AnyHasher::from(hash_algorithm),
+        })
+    }
+
+    /// Derives the equation of a key: the slot its band starts in, its 128 coefficients (the lowest always set) and
+    /// its `r` bit result
+    fn equation(&self, h1: u64, h2: u64) -> (usize, u128, u32) {
+        let start_hash = mix(h1.wrapping_add(self.seed));
+        let coefficient_hash = ((mix(h2 ^ self.seed) as u128) << 64) | mix(h2.wrapping_add(self.seed)) as u128;
+        let num_of_starts = (self.num_of_slots - RIBBON_WIDTH + 1) as u128;
+        let start = ((start_hash as u128 * num_of_starts) >> 64) as usize;
+        let result_mask = u32::MAX >> (32 - self.result_bits);
+        (start, coefficient_hash | 1, start_hash as u32 & result_mask)
+    }
+
+    /// Adds an equation to the banded matrix, eliminating it against the rows it overlaps
+    /// Returns false if the equation contradicts the ones already added
+    fn add_to_band(coefficients: &mut [u128], results: &mut [u32], start: usize, coefficient: u128, result: u32) -> bool {
+        let (mut start, mut coefficient, mut result) = (start, coefficient, result);
+        loop {
+            if coefficients[start] == 0 {
+                coefficients[start] = coefficient;
+                results[start] = result;
+                return true;
+            }
+            coefficient ^= coefficients[start];
+            result ^= results[start];
+            if coefficient == 0 {
+                // the equation is implied by earlier ones, e.g. a duplicate key
+                return result == 0;
+            }
+            let shift = coefficient.trailing_zeros();
+            start += shift as usize;
+            coefficient >>= shift;
+        }
+    }
+
+    /// Solves the banded matrix from the last row up; rows without an equation are left 0
+    fn back_substitute(&mut self, coefficients: &[u128], results: &[u32]) {
+        let words_per_column = self.num_of_slots.div_ceil(64) + 2;
+        self.solution = vec![0u64; words_per_column * self.result_bits];
+        for slot in (0..self.num_of_slots).rev() {
+            if coefficients[slot] == 0 {
+                continue;
+            }
+            for column in 0..self.result_bits {
+                // bit `slot` itself is still 0, so only the rows below contribute
+                let parity = (self.window(column, slot) & coefficients[slot]).count_ones() & 1;
+                let bit = ((results[slot] >> column) & 1) ^ parity;
+                self.solution[column * words_per_column + slot / 64] |= (bit as u64) << (slot % 64);
+            }
+        }
+    }
+
+    /// The 128 solution bits of a column starting at `slot`
+    fn window(&self, column: usize, slot: usize) -> u128 {
+        let words_per_column = self.num_of_slots.div_ceil(64) + 2;
+        let word = column * words_per_column + slot / 64;
+        let words = (self.solution[word] as u128) | ((self.solution[word + 1] as u128) << 64);
+        match slot % 64 {
+            0 => words,
+            shift => (words >> shift) | ((self.solution[word + 2] as u128) << (128 - shift)),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn byte_keys(range: std::ops::Range<u64>) -> Vec<[u8; 8]> {
+        range.map(|key| key.to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn test_contains_every_key() {
+        let keys = byte_keys(0..10_000);
+        let ribbon_filter = RibbonFilterRS::from_byte_keys(&keys, 0.01).unwrap();
+        assert!(keys.iter().all(|key| ribbon_filter.contains_bytes(key)), "Ribbon filter should have no false negatives");
+        assert_eq!(ribbon_filter.get_result_bits(), 7);
+        assert_eq!(ribbon_filter.get_item_count(), 10_000);
+    }
+
+    #[test]
+    fn test_false_positive_rate_and_size() {
+        let keys = byte_keys(0..100_000);
+        let ribbon_filter = RibbonFilterRS::from_byte_keys(&keys, 1.0 / 128.0).unwrap();
+        let false_positives = byte_keys(100_000..1_100_000).iter().filter(|key| ribbon_filter.contains_bytes(*key)).count();
+        let false_positive_rate = false_positives as f64 / 1_000_000.0;
+        assert!((false_positive_rate - 1.0 / 128.0).abs() < 0.001, "false positive rate {}", false_positive_rate);
+        // within a few percent of the log2(1/p) = 7 bits per key minimum
+        assert!(ribbon_filter.bits_per_item() < 7.0 * 1.06, "{} bits per item", ribbon_filter.bits_per_item());
+    }
+
+    #[test]
+    fn test_matches_bloom_filter_key_stream() {
+        let keys: Vec<String> = (0..1_000).map(|key| format!("key-{}", key)).collect();
+        let ribbon_filter = RibbonFilterRS::from_keys(&keys, 0.001).unwrap();
+        let byte_keys: Vec<Vec<u8>> = keys.iter().map(serialization::serialize).collect();
+        assert!(keys.iter().all(|key| ribbon_filter.contains(key)));
+        assert_eq!(ribbon_filter, RibbonFilterRS::from_byte_keys(&byte_keys, 0.001).unwrap());
+    }
+
+    #[test]
+    fn test_duplicates_and_small_key_sets() {
+        let ribbon_filter = RibbonFilterRS::from_keys(&[1, 2, 2, 3, 3, 3], 0.01).unwrap();
+        assert!([1, 2, 3].iter().all(|key| ribbon_filter.contains(key)));
+        for size in 0..100 {
+            let keys = byte_keys(0..size);
+            let ribbon_filter = RibbonFilterRS::from_byte_keys(&keys, 0.01).unwrap();
+            assert!(keys.iter().all(|key| ribbon_filter.contains_bytes(key)));
+        }
+    }
+
+    #[test]
+    fn test_construction_failure() {
+        // without spare slots the band is too tight to solve
+        let keys = byte_keys(0..10_000);
+        assert_eq!(
+            RibbonFilterRS::from_byte_keys_with_options(&keys, 8, 0.0, 3),
+            Err(BloomError::ConstructionFailed { attempts: 3 })
+        );
+        assert!(RibbonFilterRS::from_byte_keys_with_options(&keys, 8, 0.2, 1).is_ok());
+    }
+
+    #[test]
+    fn test_rejects_invalid_parameters() {
+        let keys = byte_keys(0..10);
+        assert!(RibbonFilterRS::from_byte_keys(&keys, 0.0).is_err());
+        assert!(RibbonFilterRS::from_byte_keys(&keys, 1.0).is_err());
+        assert!(RibbonFilterRS::from_byte_keys_with_options(&keys, 0, 0.05, 10).is_err());
+        assert!(RibbonFilterRS::from_byte_keys_with_options(&keys, 33, 0.05, 10).is_err());
+        assert!(RibbonFilterRS::from_byte_keys_with_options(&keys, 8, -0.1, 10).is_err());
+        assert!(RibbonFilterRS::from_byte_keys_with_options(&keys, 8, 0.05, 0).is_err());
+    }
+
+    #[test]
+    fn test_serialization_roundtrip() {
+        let keys = byte_keys(0..1_000);
+        let ribbon_filter = RibbonFilterRS::from_byte_keys(&keys, 0.0001).unwrap();
+        let bytes = ribbon_filter.to_bytes();
+        let restored = RibbonFilterRS::from_bytes(&bytes).unwrap();
+        assert_eq!(restored, ribbon_filter);
+        assert!(keys.iter().all(|key| restored.contains_bytes(key)));
+    }
+
+    #[test]
+    fn test_rejects_invalid_payloads() {
+        let bytes = RibbonFilterRS::from_keys(&[1, 2, 3], 0.01).unwrap().to_bytes();
+        assert_eq!(RibbonFilterRS::from_bytes(b"BLMB").err(), Some(BloomError::InvalidMagic));
+        assert!(matches!(RibbonFilterRS::from_bytes(&bytes[..bytes.len() - 1]), Err(BloomError::Truncated { .. })));
+        let mut wrong_bits = bytes.clone();
+        wrong_bits[7] = 0;
+        assert!(matches!(RibbonFilterRS::from_bytes(&wrong_bits), Err(BloomError::InvalidPayload(_))));
+        let mut huge_slots = bytes.clone();
+        huge_slots[16..24].copy_from_slice(&(1u64 << 50).to_le_bytes());
+        assert!(matches!(RibbonFilterRS::from_bytes(&huge_slots), Err(BloomError::Truncated { .. })));
+    }
+}
@@ -0,0 +1,363 @@
+//! Scalable Bloom filter in Rust (Almeida et al., "Scalable Bloom Filters", 2007)
+//! A chain of `BloomFilterRS` stages. When the newest stage holds the number of items it was sized for, a stage
+//! that is `growth_factor` times larger is appended. Stage `i` is built for a false positive rate of
+//! `p * (1 - r) * r^i` with tightening ratio `r`; these rates add up to at most `p`, so the compound false positive
+//! rate stays below the requested bound no matter how many items are added.
+
+use serde::Serialize;
+use crate::bloom_filter::BloomFilterRS;
+use crate::error::BloomError;
+use crate::serialization::{self, ByteReader, ByteWriter};
+
+
+/// Magic number that every serialized ScalableBloomFilterRS starts with
+const MAGIC: &[u8; 4] = b"BLMS";
+/// Version of the binary format that `to_bytes` writes
+const FORMAT_VERSION: u16 = 1;
+/// Growth factor used by `new`
+pub const DEFAULT_GROWTH_FACTOR: f64 = 2.0;
+/// Tightening ratio used by `new`
+pub const DEFAULT_TIGHTENING_RATIO: f64 = 0.85;
+
+
+/// A Bloom Filter that grows as items are added while keeping its false positive rate bounded
+#[derive(Clone)]
+pub struct ScalableBloomFilterRS {
+    /// The stages; only the last one receives new items
+    stages: Vec<BloomFilterRS>,
+    /// Number of items the first stage is sized for
+    initial_capacity: usize,
+    /// Upper bound of the compound false positive rate
+    false_positive_rate: f64,
+    /// Every stage holds this many times more items than the previous one
+    growth_factor: f64,
+    /// Every stage has this many times the false positive rate of the previous one
+    tightening_ratio: f64,
+}
+
+impl ScalableBloomFilterRS {
+    /// Creates a scalable Bloom filter with `DEFAULT_GROWTH_FACTOR` and `DEFAULT_TIGHTENING_RATIO`
+    ///
+    /// # Arguments
+    /// * `initial_capacity` - number of items the first stage is sized for
+    /// * `desired_false_positive_rate` - upper bound of the false positive rate of the whole filter
+    pub fn new(initial_capacity: usize, desired_false_positive_rate: f64) -> Self {
+        ScalableBloomFilterRS::with_growth(initial_capacity, desired_false_positive_rate, DEFAULT_GROWTH_FACTOR, DEFAULT_TIGHTENING_RATIO)
+            .expect("default growth parameters are valid")
+    }
+
+    /// Creates a scalable Bloom filter with custom growth parameters
+    /// Returns an error if a parameter is out of range
+    ///
+    /// # Arguments
+    /// * `initial_capacity` - number of items the first stage is sized for (> 0)
+    /// * `desired_false_positive_rate` - upper bound of the false positive rate of the whole filter (0..1)
+    /// * `growth_factor` - capacity of every stage relative to the previous one (>= 1); 2 suits slow, 4 fast growth
+    /// * `tightening_ratio` - false positive rate of every stage relative to the previous one (0..1)
+    pub fn with_growth(initial_capacity: usize, desired_false_positive_rate: f64, growth_factor: f64, tightening_ratio: f64) -> Result<Self, BloomError> {
+        if initial_capacity == 0 {
+            return Err(BloomError::InvalidParameter("initial capacity must be > 0".to_string()));
+        }
+        if !(desired_false_positive_rate > 0.0 && desired_false_positive_rate < 1.0) {
+            return Err(BloomError::InvalidParameter(format!("false positive rate must be between 0 and 1, got {}", desired_false_positive_rate)));
+        }
+        if !(growth_factor >= 1.0 && growth_factor.is_finite()) {
+            return Err(BloomError::InvalidParameter(format!("growth factor must be >= 1, got {}", growth_factor)));
+        }
+        if !(tightening_ratio > 0.0 && tightening_ratio < 1.0) {
+            return Err(BloomError::InvalidParameter(format!("tightening ratio must be between 0 and 1, got {}", tightening_ratio)));
+        }
+        let mut scalable_filter = ScalableBloomFilterRS {
+            stages: Vec::new(),
+            initial_capacity,
+            false_positive_rate: desired_false_positive_rate,
+            growth_factor,
+            tightening_ratio,
+        };
+        scalable_filter.add_stage();
+        Ok(scalable_filter)
+    }
+
+    /// Adds bytes to the filter
+    /// Items that the filter already (seems to) contain are skipped, so repeated items don't use up capacity
+    ///
+    /// # Arguments
+    /// * `hash_bytes` - item to insert into the filter
+    pub fn add_bytes(&mut self, hash_bytes: &[u8]) {
+        if self.contains_bytes(hash_bytes) {
+            return;
+        }
+        if self.newest_stage().remaining_capacity() == 0 {
+            self.add_stage();
+        }
+        let newest_stage = self.stages.last_mut().expect("there is always a stage");
+        newest_stage.add_bytes(hash_bytes).expect("stages ignore overflows");
+    }
+
+    /// Hashes an item to the filter
+    ///
+    /// # Arguments
+    /// * `item` - item to insert into the filter
+    pub fn add<T: Serialize>(&mut self, item: &T) {
+        self.add_bytes(&serialization::serialize(item));
+    }
+
+    /// Checks if given bytes may be contained by any of the stages
+    /// Returns boolean: False means that the item definitely isn't contained.
+    /// True means that the item may be contained in the filter
+    ///
+    /// # Arguments
+    /// * `hash_bytes` - bytes to check for membership
+    pub fn contains_bytes(&self, hash_bytes: &[u8]) -> bool {
+        // newer stages are larger and hold most items
+        self.stages.iter().rev().any(|stage| stage.contains_bytes(hash_bytes))
+    }
+
+    /// Checks if a given item may be contained by the filter
+    ///
+    /// # Arguments
+    /// * `item` - item to be hashed and checked for membership
+    pub fn contains<T: Serialize>(&self, item: &T) -> bool {
+        self.contains_bytes(&serialization::serialize(item))
+    }
+
+    /// Clears the filter and shrinks it back to its first stage
+    pub fn clear(&mut self) {
+        self.stages.truncate(1);
+        self.stages[0].clear();
+    }
+
+    /// Calculates the compound false positive rate from the bits that are set in every stage
+    /// This is calculated by `1 - Π(1 - p_i)` over the current false positive rates `p_i` of the stages
+    /// Returns f64: the false positive rate (0..1); never above `get_false_positive_rate_bound` while stages are
+    /// within their capacity
+    pub fn current_false_positive_rate(&self) -> f64 {
+        1.0 - self.stages.iter()
+            .map(|stage| 1.0 - stage.current_false_positive_rate())
+            .product::<f64>()
+    }
+
+    /// Retrieve the upper bound of the compound false positive rate
+    pub fn get_false_positive_rate_bound(&self) -> f64 {
+        self.false_positive_rate
+    }
+
+    /// Retrieve the number of items that were added; repeated items are counted once
+    pub fn get_inserted_count(&self) -> usize {
+        self.stages.iter().map(|stage| stage.get_inserted_count()).sum()
+    }
+
+    /// Retrieve the number of items the filter can hold before it adds another stage
+    pub fn get_capacity(&self) -> usize {
+        self.stages.iter().map(|stage| stage.get_expected_item_count()).sum()
+    }
+
+    /// Retrieve the number of stages
+    pub fn get_stage_count(&self) -> usize {
+        self.stages.len()
+    }
+
+    /// Retrieve the number of bits in all stages together
+    pub fn get_bit_count(&self) -> usize {
+        self.stages.iter().map(|stage| stage.get_bit_count()).sum()
+    }
+
+    /// Retrieve the stages, oldest first
+    pub fn get_stages(&self) -> &[BloomFilterRS] {
+        &self.stages
+    }
+
+    /// Retrieve the growth factor
+    pub fn get_growth_factor(&self) -> f64 {
+        self.growth_factor
+    }
+
+    /// Retrieve the tightening ratio
+    pub fn get_tightening_ratio(&self) -> f64 {
+        self.tightening_ratio
+    }
+
+    /// Serializes the filter: magic number, version, growth parameters and every stage as written by
+    /// `BloomFilterRS::to_bytes`, each preceded by its length
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = ByteWriter::with_capacity(46 + self.get_bit_count() / 8);
+        writer.write_bytes(MAGIC);
+        writer.write_u16(FORMAT_VERSION);
+        writer.write_u64(self.initial_capacity as u64);
+        writer.write_f64(self.false_positive_rate);
+        writer.write_f64(self.growth_factor);
+        writer.write_f64(self.tightening_ratio);
+        writer.write_u64(self.stages.len() as u64);
+        for stage in &self.stages {
+            let stage_bytes = stage.to_bytes();
+            writer.write_u64(stage_bytes.len() as u64);
+            writer.write_bytes(&stage_bytes);
+        }
+        writer.into_bytes()
+    }
+
+    /// Deserializes a filter written by `to_bytes`
+    /// Returns an error if the payload is truncated, has trailing bytes, invalid growth parameters or
+    /// a stage that cannot be loaded
+    ///
+    /// # Arguments
+    /// * `bytes` - serialized scalable Bloom filter
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BloomError> {
+        let mut reader = ByteReader::new(bytes);
+        if reader.read_bytes(MAGIC.len())? != MAGIC {
+            return Err(BloomError::InvalidMagic);
+        }
+        let version = reader.read_u16()?;
+        if version != FORMAT_VERSION {
+            return Err(BloomError::UnsupportedVersion(version));
+        }
+        let initial_capacity = reader.read_usize()?;
+        let false_positive_rate = reader.read_f64()?;
+        let growth_factor = reader.read_f64()?;
+        let tightening_ratio = reader.read_f64()?;
+        let mut scalable_filter = ScalableBloomFilterRS::with_growth(initial_capacity, false_positive_rate, growth_factor, tightening_ratio)
+            .map_err(|err| BloomError::InvalidPayload(err.to_string()))?;
+
+        let stage_count = reader.read_usize()?;
+        if stage_count == 0 {
+            return Err(BloomError::InvalidPayload("a scalable filter has at least one stage".to_string()));
+        }
+        scalable_filter.stages.clear();
+        for _ in 0..stage_count {
+            let stage_len = reader.read_usize()?;
+            scalable_filter.stages.push(BloomFilterRS::from_bytes(reader.read_bytes(stage_len)?)?);
+        }
+        reader.finish()?;
+        Ok(scalable_filter)
+    }
+
+    /// The stage that receives new items
+    fn newest_stage(&self) -> &BloomFilterRS {
+        self.stages.last().expect("there is always a stage")
+    }
+
+    /// Appends a stage sized for the next capacity and false positive rate
+    fn add_stage(&mut self) {
+        let stage_index = self.stages.len() as i32;
+        let capacity = (self.initial_capacity as f64 * self.growth_factor.powi(stage_index)).ceil() as usize;
+        let false_positive_rate = self.false_positive_rate * (1.0 - self.tightening_ratio) * self.tightening_ratio.powi(stage_index);
+        self.stages.push(BloomFilterRS::new(capacity, false_positive_rate));
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grows_beyond_initial_capacity() {
+        let mut bloom_filter = ScalableBloomFilterRS::new(100, 0.01);
+        assert_eq!(bloom_filter.get_stage_count(), 1);
+        for i in 0..10_000 {
+            bloom_filter.add(&i);
+        }
+        assert!((0..10_000).all(|i| bloom_filter.contains(&i)), "Scalable filter should have no false negatives");
+        // 100 + 200 + ... + 3200 < 10000 <= 100 + ... + 6400
+        assert_eq!(bloom_filter.get_stage_count(), 7);
+        assert!(bloom_filter.get_capacity() >= 10_000);
+    }
+
+    #[test]
+    fn test_repeated_items_use_no_capacity() {
+        let mut bloom_filter = ScalableBloomFilterRS::new(10, 0.01);
+        for _ in 0..100 {
+            bloom_filter.add(&"same");
+        }
+        assert_eq!(bloom_filter.get_inserted_count(), 1);
+        assert_eq!(bloom_filter.get_stage_count(), 1);
+    }
+
+    #[test]
+    fn test_false_positive_rate_stays_bounded() {
+        let mut bloom_filter = ScalableBloomFilterRS::new(1_000, 0.01);
+        for i in 0..50_000 {
+            bloom_filter.add(&i);
+        }
+        let false_positives = (50_000..150_000).filter(|i| bloom_filter.contains(i)).count();
+        let false_positive_rate = false_positives as f64 / 100_000.0;
+        assert!(false_positive_rate < 0.01, "FPR {} should stay below 1%", false_positive_rate);
+        assert!(bloom_filter.current_false_positive_rate() < 0.01);
+
+        // a plain filter sized for the first stage is useless by now
+        let mut plain = BloomFilterRS::new(1_000, 0.01);
+        for i in 0..50_000 {
+            plain.add(&i).unwrap();
+        }
+        assert!(plain.current_false_positive_rate() > 0.9);
+    }
+
+    #[test]
+    fn test_stage_parameters() {
+        let mut bloom_filter = ScalableBloomFilterRS::with_growth(100, 0.01, 4.0, 0.5).unwrap();
+        for i in 0..450 {
+            bloom_filter.add(&i);
+        }
+        let stages = bloom_filter.get_stages();
+        assert_eq!(stages.len(), 2);
+        assert_eq!(stages[0].get_expected_item_count(), 100);
+        assert_eq!(stages[1].get_expected_item_count(), 400);
+        // stage 1 targets 0.01 * 0.5 * 0.5, so it needs more bits per item than stage 0 (0.01 * 0.5)
+        let bits_per_item = |stage: &BloomFilterRS| stage.get_bit_count() as f64 / stage.get_expected_item_count() as f64;
+        assert!(bits_per_item(&stages[1]) > bits_per_item(&stages[0]));
+    }
+
+    #[test]
+    fn test_rejects_invalid_parameters() {
+        assert!(ScalableBloomFilterRS::with_growth(0, 0.01, 2.0, 0.85).is_err());
+        assert!(ScalableBloomFilterRS::with_growth(100, 1.0, 2.0, 0.85).is_err());
+        assert!(ScalableBloomFilterRS::with_growth(100, 0.01, 0.5, 0.85).is_err());
+        assert!(ScalableBloomFilterRS::with_growth(100, 0.01, 2.0, 1.0).is_err());
+    }
+
+    #[test]
+    fn test_clear_keeps_one_stage() {
+        let mut bloom_filter = ScalableBloomFilterRS::new(10, 0.01);
+        for i in 0..100 {
+            bloom_filter.add(&i);
+        }
+        bloom_filter.clear();
+        assert_eq!(bloom_filter.get_stage_count(), 1);
+        assert_eq!(bloom_filter.get_inserted_count(), 0);
+        assert!(!(0..100).any(|i| bloom_filter.contains(&i)));
+    }
+
+    #[test]
+    fn test_serialization_roundtrip() {
+        let mut bloom_filter = ScalableBloomFilterRS::with_growth(50, 0.02, 3.0, 0.8).unwrap();
+        for i in 0..1_000 {
+            bloom_filter.add(&i);
+        }
+        let bytes = bloom_filter.to_bytes();
+        let mut restored = ScalableBloomFilterRS::from_bytes(&bytes).unwrap();
+        assert_eq!(restored.get_stage_count(), bloom_filter.get_stage_count());
+        assert_eq!(restored.get_inserted_count(), bloom_filter.get_inserted_count());
+        assert_eq!(restored.get_growth_factor(), 3.0);
+        assert!((0..1_000).all(|i| restored.contains(&i)));
+        assert_eq!(restored.to_bytes(), bytes);
+
+        // keeps growing where it left off
+        for i in 1_000..5_000 {
+            restored.add(&i);
+        }
+        assert!(restored.get_stage_count() > bloom_filter.get_stage_count());
+    }
+
+    #[test]
+    fn test_rejects_invalid_payloads() {
+        let bytes = ScalableBloomFilterRS::new(10, 0.01).to_bytes();
+        assert_eq!(ScalableBloomFilterRS::from_bytes(b"BLMF").err(), Some(BloomError::InvalidMagic));
+        assert!(matches!(ScalableBloomFilterRS::from_bytes(&bytes[..bytes.len() - 1]), Err(BloomError::Truncated { .. })));
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(matches!(ScalableBloomFilterRS::from_bytes(&trailing), Err(BloomError::InvalidPayload(_))));
+        let mut no_stages = bytes[..46].to_vec();
+        no_stages[38..46].copy_from_slice(&0u64.to_le_bytes());
+        assert!(matches!(ScalableBloomFilterRS::from_bytes(&no_stages), Err(BloomError::InvalidPayload(_))));
+    }
+}
@@ -1,6 +1,154 @@
-use bincode;
 use serde::Serialize;
+use bitvec::prelude::*;
+use crate::error::BloomError;
+
 pub fn serialize<T: Serialize>(value: &T) -> Vec<u8> {
     // serde_json::to_vec(value).expect("Failed to serialize value")
     bincode::serialize(value).expect("Failed to serialize value")
 }
+
+
+/// Packs a bit array into bytes; bit `i` ends up in byte `i / 8` at position `i % 8` (LSB first)
+/// Returns exactly `ceil(len / 8)` bytes
+pub fn pack_bits(bits: &BitVec) -> Vec<u8> {
+    let mut packed: Vec<u8> = bits.as_raw_slice()
+        .iter()
+        .flat_map(|word| word.to_le_bytes())
+        .collect();
+    packed.truncate(bits.len().div_ceil(8));
+    packed
+}
+
+/// Unpacks bytes written by `pack_bits` into a bit array of `len` bits
+///
+/// # Arguments
+/// * `packed` - bytes produced by `pack_bits`; must hold exactly `ceil(len / 8)` bytes
+/// * `len` - number of bits to unpack
+pub fn unpack_bits(packed: &[u8], len: usize) -> Result<BitVec, BloomError> {
+    let expected = len.div_ceil(8);
+    if packed.len() != expected {
+        return Err(BloomError::InvalidPayload(format!(
+            "expected {} bytes of bit data, got {}", expected, packed.len()
+        )));
+    }
+
+    let word_size = std::mem::size_of::<usize>();
+    let words: Vec<usize> = packed
+        .chunks(word_size)
+        .map(|chunk| {
+            let mut word = [0u8; std::mem::size_of::<usize>()];
+            word[..chunk.len()].copy_from_slice(chunk);
+            usize::from_le_bytes(word)
+        })
+        .collect();
+
+    let mut bits = BitVec::from_vec(words);
+    bits.truncate(len);
+    bits.set_uninitialized(false);
+    Ok(bits)
+}
+
+
+/// Appends little-endian encoded values to a byte buffer
+pub struct ByteWriter {
+    buffer: Vec<u8>,
+}
+
+impl ByteWriter {
+    pub fn with_capacity(capacity: usize) -> Self {
+        ByteWriter { buffer: Vec::with_capacity(capacity) }
+    }
+
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    pub fn write_u8(&mut self, value: u8) {
+        self.buffer.push(value);
+    }
+
+    pub fn write_u16(&mut self, value: u16) {
+        self.buffer.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn write_u64(&mut self, value: u64) {
+        self.buffer.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn write_f64(&mut self, value: f64) {
+        self.write_u64(value.to_bits());
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.buffer
+    }
+}
+
+
+/// Reads little-endian encoded values from a byte slice; every read checks the remaining length
+pub struct ByteReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        ByteReader { bytes, position: 0 }
+    }
+
+    /// Reads the next `len` bytes
+    /// Returns `BloomError::Truncated` if fewer than `len` bytes are left
+    pub fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], BloomError> {
+        let end = self.position.checked_add(len).filter(|end| *end <= self.bytes.len());
+        match end {
+            Some(end) => {
+                let slice = &self.bytes[self.position..end];
+                self.position = end;
+                Ok(slice)
+            }
+            None => Err(BloomError::Truncated {
+                expected: self.position.saturating_add(len),
+                actual: self.bytes.len(),
+            }),
+        }
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, BloomError> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    pub fn read_u16(&mut self) -> Result<u16, BloomError> {
+        let bytes = self.read_bytes(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    pub fn read_u64(&mut self) -> Result<u64, BloomError> {
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(self.read_bytes(8)?);
+        Ok(u64::from_le_bytes(buf))
+    }
+
+    pub fn read_f64(&mut self) -> Result<f64, BloomError> {
+        Ok(f64::from_bits(self.read_u64()?))
+    }
+
+    /// Reads a u64 that must fit in a usize on this platform
+    pub fn read_usize(&mut self) -> Result<usize, BloomError> {
+        let value = self.read_u64()?;
+        usize::try_from(value)
+            .map_err(|_| BloomError::InvalidPayload(format!("value {} does not fit in usize", value)))
+    }
+
+    /// Number of bytes that have not been read yet
+    pub fn remaining(&self) -> usize {
+        self.bytes.len() - self.position
+    }
+
+    /// Errors if there are bytes left after the last read
+    pub fn finish(&self) -> Result<(), BloomError> {
+        match self.remaining() {
+            0 => Ok(()),
+            left => Err(BloomError::InvalidPayload(format!("{} unexpected trailing bytes", left))),
+        }
+    }
+}
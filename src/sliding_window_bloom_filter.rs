@@ -0,0 +1,355 @@
+//! Sliding-window Bloom filter in Rust
+//! A ring of `BloomFilterRS` generations. New items go into the newest generation and lookups check every live
+//! generation. Advancing the window clears the oldest generation and reuses it as the newest, so items expire
+//! between `generation_count - 1` and `generation_count` rotations after they were added. The window is advanced
+//! manually with `advance`, or by a `Clock` once every `generation_duration`.
+
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use crate::bloom_filter::BloomFilterRS;
+use crate::error::BloomError;
+use crate::serialization;
+
+
+/// A source of time for a timed `SlidingWindowBloomFilterRS`
+pub trait Clock: Send + Sync {
+    /// Returns the time elapsed since a fixed, arbitrary starting point; must never decrease
+    fn now(&self) -> Duration;
+}
+
+/// Monotonic wall clock time, measured from the moment the clock was created
+#[derive(Debug, Clone, Copy)]
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        SystemClock { start: Instant::now() }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        SystemClock::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+/// A clock that only moves when told to; clones share the same time, so a test can keep one clone and hand the
+/// other to the filter
+#[derive(Debug, Clone, Default)]
+pub struct ManualClock {
+    nanos: Arc<AtomicU64>,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        ManualClock::default()
+    }
+
+    /// Moves the clock forward
+    ///
+    /// # Arguments
+    /// * `duration` - time to add
+    pub fn advance(&self, duration: Duration) {
+        self.nanos.fetch_add(duration.as_nanos() as u64, Ordering::SeqCst);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        Duration::from_nanos(self.nanos.load(Ordering::SeqCst))
+    }
+}
+
+
+/// How the window of a `SlidingWindowBloomFilterRS` moves
+#[derive(Clone)]
+pub enum Rotation {
+    /// Only `advance` moves the window
+    Manual,
+    /// The window moves once per `generation_duration` of `clock` time, and on `advance`
+    Timed { generation_duration: Duration, clock: Arc<dyn Clock> },
+}
+
+
+/// A Bloom Filter that forgets items after a number of generations
+#[derive(Clone)]
+pub struct SlidingWindowBloomFilterRS {
+    /// The generations, newest first
+    generations: VecDeque<BloomFilterRS>,
+    /// How the window moves
+    rotation: Rotation,
+    /// Clock time at which the newest generation started; only used by `Rotation::Timed`
+    generation_started: Duration,
+}
+
+impl SlidingWindowBloomFilterRS {
+    /// Creates a sliding-window filter that only moves when `advance` is called
+    /// Returns an error if `generation_count` is 0 or the false positive rate is outside (0, 1)
+    ///
+    /// # Arguments
+    /// * `expected_items_per_generation` - Estimated number of items added during one generation
+    /// * `desired_false_positive_rate` - Desired/accepted false positive rate of every generation
+    /// * `generation_count` - number of generations an item is remembered for (at most)
+    pub fn new(expected_items_per_generation: usize, desired_false_positive_rate: f64, generation_count: usize) -> Result<Self, BloomError> {
+        SlidingWindowBloomFilterRS::with_rotation(expected_items_per_generation, desired_false_positive_rate, generation_count, Rotation::Manual)
+    }
+
+    /// Creates a sliding-window filter that moves once per `generation_duration` of wall clock time
+    /// Returns an error if `generation_count` or `generation_duration` is 0 or the false positive rate is outside (0, 1)
+    ///
+    /// # Arguments
+    /// * `expected_items_per_generation` - Estimated number of items added during one generation
+    /// * `desired_false_positive_rate` - Desired/accepted false positive rate of every generation
+    /// * `generation_count` - number of generations in the window
+    /// * `generation_duration` - time span of one generation; the window is `generation_count` times as long
+    pub fn with_window(expected_items_per_generation: usize, desired_false_positive_rate: f64, generation_count: usize, generation_duration: Duration) -> Result<Self, BloomError> {
+        let rotation = Rotation::Timed { generation_duration, clock: Arc::new(SystemClock::new()) };
+        SlidingWindowBloomFilterRS::with_rotation(expected_items_per_generation, desired_false_positive_rate, generation_count, rotation)
+    }
+
+    /// Creates a sliding-window filter that moves as given by `rotation`, e.g. timed by a `ManualClock`
+    /// Returns an error if `generation_count` or the generation duration is 0 or the false positive rate is outside (0, 1)
+    ///
+    /// # Arguments
+    /// * `expected_items_per_generation` - Estimated number of items added during one generation
+    /// * `desired_false_positive_rate` - Desired/accepted false positive rate of every generation
+    /// * `generation_count` - number of generations in the window
+    /// * `rotation` - what moves the window
+    pub fn with_rotation(expected_items_per_generation: usize, desired_false_positive_rate: f64, generation_count: usize, rotation: Rotation) -> Result<Self, BloomError> {
+        if generation_count == 0 {
+            return Err(BloomError::InvalidParameter("a sliding window needs at least one generation".to_string()));
+        }
+        if !(desired_false_positive_rate > 0.0 && desired_false_positive_rate < 1.0) {
+            return Err(BloomError::InvalidParameter(format!("false positive rate must be between 0 and 1, got {}", desired_false_positive_rate)));
+        }
+        let generation_started = match &rotation {
+            Rotation::Manual => Duration::ZERO,
+            Rotation::Timed { generation_duration, .. } if generation_duration.is_zero() => {
+                return Err(BloomError::InvalidParameter("generation duration must be > 0".to_string()));
+            }
+            Rotation::Timed { clock, .. } => clock.now(),
+        };
+        let generations = (0..generation_count)
+            .map(|_| BloomFilterRS::new(expected_items_per_generation, desired_false_positive_rate))
+            .collect();
+        Ok(SlidingWindowBloomFilterRS { generations, rotation, generation_started })
+    }
+
+    /// Adds bytes to the newest generation, after expiring the generations whose time is up
+    ///
+    /// # Arguments
+    /// * `hash_bytes` - item to insert into the filter
+    pub fn add_bytes(&mut self, hash_bytes: &[u8]) {
+        self.expire();
+        self.generations[0].add_bytes(hash_bytes).expect("generations ignore overflows");
+    }
+
+    /// Hashes an item to the newest generation
+    ///
+    /// # Arguments
+    /// * `item` - item to insert into the filter
+    pub fn add<T: Serialize>(&mut self, item: &T) {
+        self.add_bytes(&serialization::serialize(item));
+    }
+
+    /// Checks if given bytes may be contained by one of the live generations
+    /// Generations whose time is up are skipped even if `add` didn't get to expire them yet
+    /// Returns boolean: False means that the item definitely isn't contained (or expired).
+    /// True means that the item may have been added within the window
+    ///
+    /// # Arguments
+    /// * `hash_bytes` - bytes to check for membership
+    pub fn contains_bytes(&self, hash_bytes: &[u8]) -> bool {
+        self.live_generations().any(|generation| generation.contains_bytes(hash_bytes))
+    }
+
+    /// Checks if a given item may be contained by one of the live generations
+    ///
+    /// # Arguments
+    /// * `item` - item to be hashed and checked for membership
+    pub fn contains<T: Serialize>(&self, item: &T) -> bool {
+        self.contains_bytes(&serialization::serialize(item))
+    }
+
+    /// Moves the window by one generation: the oldest generation is cleared and becomes the newest
+    /// For a timed filter the new generation starts now
+    pub fn advance(&mut self) {
+        self.expire();
+        self.rotate();
+        if let Rotation::Timed { clock, .. } = &self.rotation {
+            self.generation_started = clock.now();
+        }
+    }
+
+    /// Expires the generations whose time is up; does nothing for a manual filter
+    /// `add_bytes` calls this, so it's only needed to release the items of an idle filter
+    pub fn expire(&mut self) {
+        let (elapsed_generations, into_generation) = self.elapsed_generations();
+        for _ in 0..elapsed_generations.min(self.generations.len()) {
+            self.rotate();
+        }
+        if let Rotation::Timed { clock, .. } = &self.rotation {
+            if elapsed_generations > 0 {
+                self.generation_started = clock.now().saturating_sub(into_generation);
+            }
+        }
+    }
+
+    /// Clears all generations
+    pub fn clear(&mut self) {
+        self.generations.iter_mut().for_each(|generation| generation.clear());
+        if let Rotation::Timed { clock, .. } = &self.rotation {
+            self.generation_started = clock.now();
+        }
+    }
+
+    /// Calculates the false positive rate of the live generations together from their set bits
+    /// This is calculated by `1 - Π(1 - p_i)` over the current false positive rates `p_i` of the generations
+    pub fn current_false_positive_rate(&self) -> f64 {
+        1.0 - self.live_generations()
+            .map(|generation| 1.0 - generation.current_false_positive_rate())
+            .product::<f64>()
+    }
+
+    /// Retrieve the number of generations
+    pub fn get_generation_count(&self) -> usize {
+        self.generations.len()
+    }
+
+    /// Retrieve the number of bits in all generations together
+    pub fn get_bit_count(&self) -> usize {
+        self.generations.iter().map(|generation| generation.get_bit_count()).sum()
+    }
+
+    /// Retrieve how the window moves
+    pub fn get_rotation(&self) -> &Rotation {
+        &self.rotation
+    }
+
+    /// The generations that haven't expired yet, newest first
+    fn live_generations(&self) -> impl Iterator<Item = &BloomFilterRS> {
+        let (elapsed_generations, _) = self.elapsed_generations();
+        let live = self.generations.len().saturating_sub(elapsed_generations);
+        self.generations.iter().take(live)
+    }
+
+    /// Number of whole generations that passed since the newest generation started, and the time since the
+    /// start of the last of them
+    fn elapsed_generations(&self) -> (usize, Duration) {
+        match &self.rotation {
+            Rotation::Manual => (0, Duration::ZERO),
+            Rotation::Timed { generation_duration, clock } => {
+                let elapsed = clock.now().saturating_sub(self.generation_started).as_nanos();
+                let generation_nanos = generation_duration.as_nanos();
+                let elapsed_generations = usize::try_from(elapsed / generation_nanos).unwrap_or(usize::MAX);
+                (elapsed_generations, Duration::from_nanos((elapsed % generation_nanos) as u64))
+            }
+        }
+    }
+
+    /// Clears the oldest generation and moves it to the front
+    fn rotate(&mut self) {
+        let mut oldest = self.generations.pop_back().expect("there is always a generation");
+        oldest.clear();
+        self.generations.push_front(oldest);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_items_expire_after_manual_rotations() {
+        let mut bloom_filter = SlidingWindowBloomFilterRS::new(100, 0.01, 3).unwrap();
+        bloom_filter.add(&"old");
+        bloom_filter.advance();
+        bloom_filter.add(&"new");
+        bloom_filter.advance();
+        assert!(bloom_filter.contains(&"old") && bloom_filter.contains(&"new"));
+        bloom_filter.advance();
+        assert!(!bloom_filter.contains(&"old"), "Item should expire after 3 rotations");
+        assert!(bloom_filter.contains(&"new"));
+        bloom_filter.advance();
+        assert!(!bloom_filter.contains(&"new"));
+    }
+
+    #[test]
+    fn test_timed_rotation() {
+        let clock = ManualClock::new();
+        let rotation = Rotation::Timed { generation_duration: Duration::from_secs(60), clock: Arc::new(clock.clone()) };
+        let mut bloom_filter = SlidingWindowBloomFilterRS::with_rotation(100, 0.01, 5, rotation).unwrap();
+        bloom_filter.add(&"event");
+
+        clock.advance(Duration::from_secs(4 * 60 + 59));
+        assert!(bloom_filter.contains(&"event"));
+
+        // expired on lookup, without an add in between
+        clock.advance(Duration::from_secs(1));
+        assert!(!bloom_filter.contains(&"event"));
+        assert_eq!(bloom_filter.current_false_positive_rate(), 0.0);
+    }
+
+    #[test]
+    fn test_add_expires_old_generations() {
+        let clock = ManualClock::new();
+        let rotation = Rotation::Timed { generation_duration: Duration::from_secs(10), clock: Arc::new(clock.clone()) };
+        let mut bloom_filter = SlidingWindowBloomFilterRS::with_rotation(100, 0.01, 2, rotation).unwrap();
+        bloom_filter.add(&1);
+        clock.advance(Duration::from_secs(15));
+        bloom_filter.add(&2);
+        // 1 is one generation old, 2 was added 5s into the current generation
+        assert!(bloom_filter.contains(&1) && bloom_filter.contains(&2));
+        clock.advance(Duration::from_secs(5));
+        assert!(!bloom_filter.contains(&1));
+        assert!(bloom_filter.contains(&2));
+        clock.advance(Duration::from_secs(10));
+        assert!(!bloom_filter.contains(&2));
+
+        // a long pause clears everything, however many generations passed
+        bloom_filter.add(&3);
+        clock.advance(Duration::from_secs(1_000_000));
+        bloom_filter.expire();
+        assert!(!bloom_filter.contains(&3));
+    }
+
+    #[test]
+    fn test_clear_and_false_positive_rate() {
+        let mut bloom_filter = SlidingWindowBloomFilterRS::new(1_000, 0.01, 4).unwrap();
+        assert_eq!(bloom_filter.current_false_positive_rate(), 0.0);
+        for i in 0..1_000 {
+            bloom_filter.add(&i);
+        }
+        let one_generation = bloom_filter.current_false_positive_rate();
+        bloom_filter.advance();
+        for i in 1_000..2_000 {
+            bloom_filter.add(&i);
+        }
+        assert!(bloom_filter.current_false_positive_rate() > one_generation);
+        bloom_filter.clear();
+        assert!(!(0..2_000).any(|i| bloom_filter.contains(&i)));
+        assert_eq!(bloom_filter.get_bit_count(), 4 * BloomFilterRS::new(1_000, 0.01).get_bit_count());
+    }
+
+    #[test]
+    fn test_rejects_invalid_parameters() {
+        assert!(SlidingWindowBloomFilterRS::new(100, 0.01, 0).is_err());
+        assert!(SlidingWindowBloomFilterRS::with_window(100, 0.01, 3, Duration::ZERO).is_err());
+        for desired_false_positive_rate in [0.0, 1.0, f64::NAN] {
+            assert!(matches!(SlidingWindowBloomFilterRS::new(100, desired_false_positive_rate, 3), Err(BloomError::InvalidParameter(_))));
+        }
+        assert!(SlidingWindowBloomFilterRS::with_window(100, 0.01, 3, Duration::from_secs(1)).is_ok());
+    }
+}
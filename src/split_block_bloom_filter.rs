@@ -0,0 +1,441 @@
+//! Split-block Bloom filter (SBBF) as defined by the Apache Parquet format
+//! The filter is an array of 256 bit blocks of eight 32 bit words. An item is hashed once with xxHash64 (seed 0);
+//! the upper 32 bits of the hash pick the block and the lower 32 bits, multiplied by eight salt constants, set one
+//! bit in every word of that block. Parquet hashes the plain encoding of a value: little-endian bytes for numbers
+//! and the raw bytes (without length prefix) for byte arrays and strings.
+//!
+//! `to_bytes`/`from_bytes` read and write the bitset exactly as Parquet stores it (words little-endian, block after
+//! block); `to_parquet_bytes`/`from_parquet_bytes` add the Thrift `BloomFilterHeader` that precedes the bitset in a
+//! Parquet file.
+
+use xxhash_rust::xxh64::xxh64;
+use crate::error::BloomError;
+use crate::serialization::ByteReader;
+
+
+/// Number of bytes in a block
+pub const BLOCK_BYTES: usize = 32;
+/// Smallest bitset the Parquet writers produce
+pub const MIN_BYTES: usize = BLOCK_BYTES;
+/// Largest bitset the Parquet writers produce (128 MiB)
+pub const MAX_BYTES: usize = 128 * 1024 * 1024;
+/// The salt constants of the Parquet specification; word `i` of a block sets bit `(x * SALT[i]) >> 27`
+pub const SALT: [u32; 8] = [
+    0x47b6137b, 0x44974d91, 0x8824ad5b, 0xa2b7289d,
+    0x705495c7, 0x2df1424b, 0x9efc4947, 0x5c6bfb31,
+];
+
+
+/// Calculates the bitset size in bytes for a split-block Bloom filter
+/// This is calculated by `m = -8 * n / ln(1 - p^(1/8))` bits, the formula the Parquet writers use, rounded up to a
+/// power of two number of bytes between `MIN_BYTES` and `MAX_BYTES`
+///
+/// # Arguments
+/// * `expected_number_of_items` - Estimated number of distinct values that the filter should accommodate
+/// * `desired_false_pos_rate` - Desired/accepted false positive rate
+pub fn calc_split_block_number_of_bytes(expected_number_of_items: usize, desired_false_pos_rate: f64) -> usize {
+    let num_of_bits = -8.0 * expected_number_of_items as f64 / (1.0 - desired_false_pos_rate.powf(1.0 / 8.0)).ln();
+    let num_of_bytes = (num_of_bits / 8.0).ceil();
+    if num_of_bytes.is_nan() || num_of_bytes >= MAX_BYTES as f64 {
+        return MAX_BYTES;
+    }
+    (num_of_bytes as usize).max(MIN_BYTES).next_power_of_two()
+}
+
+
+/// Parquet-compatible split-block Bloom filter
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SplitBlockBloomFilterRS {
+    /// The bitset; every block holds eight 32 bit words
+    blocks: Vec<[u32; 8]>,
+}
+
+impl SplitBlockBloomFilterRS {
+    /// Creates an empty filter sized with `calc_split_block_number_of_bytes`
+    ///
+    /// # Arguments
+    /// * `expected_number_of_items` - Estimated number of distinct values that the filter should accommodate
+    /// * `desired_false_positive_rate` - Desired/accepted false positive rate
+    pub fn new(expected_number_of_items: usize, desired_false_positive_rate: f64) -> Self {
+        let num_of_bytes = calc_split_block_number_of_bytes(expected_number_of_items, desired_false_positive_rate);
+        SplitBlockBloomFilterRS {
+            blocks: vec![[0; 8]; num_of_bytes / BLOCK_BYTES],
+        }
+    }
+
+    /// Creates an empty filter with a bitset of exactly `num_of_bytes` bytes
+    /// Returns an error if `num_of_bytes` is not a positive multiple of `BLOCK_BYTES`
+    ///
+    /// # Arguments
+    /// * `num_of_bytes` - size of the bitset
+    pub fn with_num_bytes(num_of_bytes: usize) -> Result<Self, BloomError> {
+        if num_of_bytes == 0 || !num_of_bytes.is_multiple_of(BLOCK_BYTES) {
+            return Err(BloomError::InvalidParameter(format!(
+                "the bitset size must be a positive multiple of {} bytes, got {}", BLOCK_BYTES, num_of_bytes
+            )));
+        }
+        Ok(SplitBlockBloomFilterRS {
+            blocks: vec![[0; 8]; num_of_bytes / BLOCK_BYTES],
+        })
+    }
+
+    /// Hashes the plain encoding of a value the way Parquet does: xxHash64 with seed 0
+    ///
+    /// # Arguments
+    /// * `value_bytes` - plain encoded value
+    pub fn hash_bytes(value_bytes: &[u8]) -> u64 {
+        xxh64(value_bytes, 0)
+    }
+
+    /// Inserts a hash produced by `hash_bytes` (or by a Parquet writer)
+    ///
+    /// # Arguments
+    /// * `hash` - 64 bit xxHash64 of the value
+    pub fn insert_hash(&mut self, hash: u64) {
+        let block_index = self.block_index(hash);
+        let mask = Self::mask(hash as u32);
+        for (word, bit) in self.blocks[block_index].iter_mut().zip(mask) {
+            *word |= bit;
+        }
+    }
+
+    /// Checks if a hash may have been inserted
+    /// Returns boolean: False means that the value definitely isn't contained.
+    /// True means that the value may be contained in the filter
+    ///
+    /// # Arguments
+    /// * `hash` - 64 bit xxHash64 of the value
+    pub fn check_hash(&self, hash: u64) -> bool {
+        let block = &self.blocks[self.block_index(hash)];
+        block.iter().zip(Self::mask(hash as u32)).all(|(word, bit)| word & bit != 0)
+    }
+
+    /// Adds the plain encoding of a value to the filter
+    ///
+    /// # Arguments
+    /// * `value_bytes` - plain encoded value
+    pub fn add_bytes(&mut self, value_bytes: &[u8]) {
+        self.insert_hash(Self::hash_bytes(value_bytes));
+    }
+
+    /// Checks if the plain encoding of a value may be contained by the filter
+    ///
+    /// # Arguments
+    /// * `value_bytes` - plain encoded value
+    pub fn contains_bytes(&self, value_bytes: &[u8]) -> bool {
+        self.check_hash(Self::hash_bytes(value_bytes))
+    }
+
+    /// Clears the filter
+    pub fn clear(&mut self) {
+        self.blocks.fill([0; 8]);
+    }
+
+    /// Calculates the false positive rate after `number_of_items` distinct values were added
+    /// A block with `i` values misses a bit in a given word with probability `(1 - 1/32)^i`; the number of values
+    /// per block is approximated by its mean
+    ///
+    /// # Arguments
+    /// * `number_of_items` - number of distinct values in the filter
+    pub fn false_positive_rate(&self, number_of_items: usize) -> f64 {
+        let items_per_block = number_of_items as f64 / self.blocks.len() as f64;
+        (1.0 - (1.0 - 1.0 / 32.0_f64).powf(items_per_block)).powi(8)
+    }
+
+    /// Retrieve the size of the bitset in bytes
+    pub fn get_byte_count(&self) -> usize {
+        self.blocks.len() * BLOCK_BYTES
+    }
+
+    /// Retrieve the number of 256 bit blocks
+    pub fn get_block_count(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Serializes the bitset in the layout of the Parquet specification: 32 bit words in little-endian order,
+    /// eight per block, blocks in order
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.blocks.iter()
+            .flat_map(|block| block.iter().flat_map(|word| word.to_le_bytes()))
+            .collect()
+    }
+
+    /// Loads a bitset written by `to_bytes` or read from a Parquet file (without its header)
+    /// Returns an error if the number of bytes is not a positive multiple of `BLOCK_BYTES`
+    ///
+    /// # Arguments
+    /// * `bytes` - the bitset
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BloomError> {
+        if bytes.is_empty() || !bytes.len().is_multiple_of(BLOCK_BYTES) {
+            return Err(BloomError::InvalidPayload(format!(
+                "a split-block bitset is a positive multiple of {} bytes, got {}", BLOCK_BYTES, bytes.len()
+            )));
+        }
+        let blocks = bytes.chunks_exact(BLOCK_BYTES)
+            .map(|block| {
+                let mut words = [0u32; 8];
+                for (word, word_bytes) in words.iter_mut().zip(block.chunks_exact(4)) {
+                    *word = u32::from_le_bytes([word_bytes[0], word_bytes[1], word_bytes[2], word_bytes[3]]);
+                }
+                words
+            })
+            .collect();
+        Ok(SplitBlockBloomFilterRS { blocks })
+    }
+
+    /// Serializes the filter the way it is embedded in a Parquet file: the Thrift compact encoded
+    /// `BloomFilterHeader` (SPLIT_BLOCK, XXHASH, UNCOMPRESSED) followed by the bitset
+    pub fn to_parquet_bytes(&self) -> Vec<u8> {
+        let bitset = self.to_bytes();
+        let mut bytes = Vec::with_capacity(bitset.len() + 20);
+        // field 1: numBytes (i32)
+        bytes.push(thrift::field_header(1, thrift::TYPE_I32));
+        thrift::write_varint(&mut bytes, thrift::zigzag(bitset.len() as i32));
+        // fields 2, 3 and 4: algorithm, hash and compression; unions whose first member is an empty struct
+        for _ in 0..3 {
+            bytes.push(thrift::field_header(1, thrift::TYPE_STRUCT));
+            bytes.push(thrift::field_header(1, thrift::TYPE_STRUCT));
+            bytes.push(thrift::TYPE_STOP);
+            bytes.push(thrift::TYPE_STOP);
+        }
+        bytes.push(thrift::TYPE_STOP);
+        bytes.extend_from_slice(&bitset);
+        bytes
+    }
+
+    /// Loads a filter from the bytes at the bloom filter offset of a Parquet column chunk: a `BloomFilterHeader`
+    /// followed by the bitset. Bytes after the bitset are ignored, so a reader can pass a generously sized slice
+    /// Returns an error if the header is malformed, describes another algorithm, hash or compression, or the
+    /// bitset is truncated
+    ///
+    /// # Arguments
+    /// * `bytes` - header and bitset
+    pub fn from_parquet_bytes(bytes: &[u8]) -> Result<Self, BloomError> {
+        let mut reader = ByteReader::new(bytes);
+        let num_of_bytes = thrift::read_header(&mut reader)?;
+        SplitBlockBloomFilterRS::from_bytes(reader.read_bytes(num_of_bytes)?)
+    }
+
+    /// Picks the block from the upper 32 bits of the hash
+    fn block_index(&self, hash: u64) -> usize {
+        (((hash >> 32) * self.blocks.len() as u64) >> 32) as usize
+    }
+
+    /// Derives the bit to set in each of the eight words of a block
+    fn mask(key: u32) -> [u32; 8] {
+        SALT.map(|salt| 1 << (key.wrapping_mul(salt) >> 27))
+    }
+}
+
+
+/// The subset of the Thrift compact protocol needed for the Parquet `BloomFilterHeader`
+mod thrift {
+    use crate::error::BloomError;
+    use crate::serialization::ByteReader;
+
+    pub const TYPE_STOP: u8 = 0;
+    pub const TYPE_I32: u8 = 5;
+    pub const TYPE_STRUCT: u8 = 12;
+
+    /// Short form field header; `delta` is the difference with the previous field id (1..=15)
+    pub fn field_header(delta: u8, field_type: u8) -> u8 {
+        (delta << 4) | field_type
+    }
+
+    pub fn zigzag(value: i32) -> u64 {
+        ((value << 1) ^ (value >> 31)) as u32 as u64
+    }
+
+    pub fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+        while value >= 0x80 {
+            bytes.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        bytes.push(value as u8);
+    }
+
+    fn read_varint(reader: &mut ByteReader) -> Result<u64, BloomError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = reader.read_u8()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(BloomError::InvalidPayload("varint in bloom filter header is too long".to_string()))
+    }
+
+    /// Reads a field header and returns `(field id, type)`; `None` at the end of a struct
+    fn read_field(reader: &mut ByteReader, last_id: i16) -> Result<Option<(i16, u8)>, BloomError> {
+        let byte = reader.read_u8()?;
+        let field_type = byte & 0x0f;
+        if field_type == TYPE_STOP {
+            return Ok(None);
+        }
+        let field_id = match byte >> 4 {
+            // long form: the field id follows as a zigzag varint
+            0 => {
+                let encoded = read_varint(reader)?;
+                ((encoded >> 1) as i64 ^ -((encoded & 1) as i64)) as i16
+            }
+            delta => last_id + delta as i16,
+        };
+        Ok(Some((field_id, field_type)))
+    }
+
+    /// Reads one of the `algorithm`, `hash` and `compression` unions; only their first member (an empty struct) is
+    /// supported: SPLIT_BLOCK, XXHASH and UNCOMPRESSED
+    fn read_union(reader: &mut ByteReader, name: &str) -> Result<(), BloomError> {
+        match read_field(reader, 0)? {
+            Some((1, TYPE_STRUCT)) => {}
+            Some((member, _)) => {
+                return Err(BloomError::InvalidPayload(format!("unsupported bloom filter {} (union member {})", name, member)));
+            }
+            None => return Err(BloomError::InvalidPayload(format!("bloom filter {} is empty", name))),
+        }
+        if read_field(reader, 0)?.is_some() || read_field(reader, 1)?.is_some() {
+            return Err(BloomError::InvalidPayload(format!("unexpected fields in bloom filter {}", name)));
+        }
+        Ok(())
+    }
+
+    /// Reads a `BloomFilterHeader` and returns its `numBytes`
+    pub fn read_header(reader: &mut ByteReader) -> Result<usize, BloomError> {
+        let mut num_of_bytes = None;
+        let mut seen = [false; 3];
+        let mut last_id = 0;
+        while let Some((field_id, field_type)) = read_field(reader, last_id)? {
+            match (field_id, field_type) {
+                (1, TYPE_I32) => {
+                    let encoded = read_varint(reader)?;
+                    let value = ((encoded >> 1) as i64) ^ -((encoded & 1) as i64);
+                    num_of_bytes = Some(usize::try_from(value).map_err(|_| {
+                        BloomError::InvalidPayload(format!("negative bitset size {}", value))
+                    })?);
+                }
+                (2, TYPE_STRUCT) => read_union(reader, "algorithm")?,
+                (3, TYPE_STRUCT) => read_union(reader, "hash")?,
+                (4, TYPE_STRUCT) => read_union(reader, "compression")?,
+                _ => {
+                    return Err(BloomError::InvalidPayload(format!(
+                        "unexpected field {} of type {} in bloom filter header", field_id, field_type
+                    )));
+                }
+            }
+            if (2..=4).contains(&field_id) {
+                seen[field_id as usize - 2] = true;
+            }
+            last_id = field_id;
+        }
+        match (num_of_bytes, seen) {
+            (Some(num_of_bytes), [true, true, true]) => Ok(num_of_bytes),
+            _ => Err(BloomError::InvalidPayload("bloom filter header misses required fields".to_string())),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_and_contains() {
+        let mut filter = SplitBlockBloomFilterRS::new(1000, 0.01);
+        for i in 0..1000_i64 {
+            filter.add_bytes(&i.to_le_bytes());
+        }
+        assert!((0..1000_i64).all(|i| filter.contains_bytes(&i.to_le_bytes())), "SBBF should have no false negatives");
+        filter.clear();
+        assert!(!(0..1000_i64).any(|i| filter.contains_bytes(&i.to_le_bytes())));
+    }
+
+    #[test]
+    fn test_hash_is_xxhash64_with_seed_zero() {
+        assert_eq!(SplitBlockBloomFilterRS::hash_bytes(b""), 0xef46db3751d8e999);
+    }
+
+    #[test]
+    fn test_insert_sets_one_bit_per_word_of_one_block() {
+        let mut filter = SplitBlockBloomFilterRS::with_num_bytes(4 * BLOCK_BYTES).unwrap();
+        let hash: u64 = 0xc000_0000_1234_5678;
+        filter.insert_hash(hash);
+        // upper half 0xc0000000 of 4 blocks lands in block 3
+        assert!(filter.blocks[..3].iter().all(|block| *block == [0; 8]));
+        for (i, word) in filter.blocks[3].iter().enumerate() {
+            assert_eq!(*word, 1 << (0x1234_5678_u32.wrapping_mul(SALT[i]) >> 27));
+        }
+        assert!(filter.check_hash(hash));
+    }
+
+    #[test]
+    fn test_bitset_layout_is_little_endian_words() {
+        let mut filter = SplitBlockBloomFilterRS::with_num_bytes(BLOCK_BYTES).unwrap();
+        filter.insert_hash(0);
+        // key 0 sets bit 0 of every word
+        let expected: Vec<u8> = (0..8).flat_map(|_| [1, 0, 0, 0]).collect();
+        assert_eq!(filter.to_bytes(), expected);
+        assert_eq!(SplitBlockBloomFilterRS::from_bytes(&expected).unwrap(), filter);
+    }
+
+    #[test]
+    fn test_parquet_header() {
+        let filter = SplitBlockBloomFilterRS::with_num_bytes(1024).unwrap();
+        let bytes = filter.to_parquet_bytes();
+        // numBytes = 1024 (zigzag varint 0x80 0x10), then SPLIT_BLOCK, XXHASH and UNCOMPRESSED
+        let header = [0x15, 0x80, 0x10, 0x1c, 0x1c, 0, 0, 0x1c, 0x1c, 0, 0, 0x1c, 0x1c, 0, 0, 0];
+        assert_eq!(&bytes[..header.len()], &header);
+        assert_eq!(bytes.len(), header.len() + 1024);
+
+        // trailing bytes after the bitset are ignored
+        let mut padded = bytes.clone();
+        padded.extend_from_slice(&[0xff; 7]);
+        assert_eq!(SplitBlockBloomFilterRS::from_parquet_bytes(&padded).unwrap(), filter);
+    }
+
+    #[test]
+    fn test_parquet_bytes_roundtrip() {
+        let mut filter = SplitBlockBloomFilterRS::new(500, 0.01);
+        for value in ["a", "bb", "ccc"] {
+            filter.add_bytes(value.as_bytes());
+        }
+        let restored = SplitBlockBloomFilterRS::from_parquet_bytes(&filter.to_parquet_bytes()).unwrap();
+        assert_eq!(restored, filter);
+        assert!(restored.contains_bytes(b"bb"));
+    }
+
+    #[test]
+    fn test_rejects_invalid_payloads() {
+        let bytes = SplitBlockBloomFilterRS::with_num_bytes(64).unwrap().to_parquet_bytes();
+        assert!(matches!(
+            SplitBlockBloomFilterRS::from_parquet_bytes(&bytes[..bytes.len() - 1]),
+            Err(BloomError::Truncated { .. })
+        ));
+        // a hash union with member 2 is not XXHASH
+        let mut other_hash = bytes.clone();
+        other_hash[7..9].copy_from_slice(&[0x1c, 0x2c]);
+        assert!(matches!(
+            SplitBlockBloomFilterRS::from_parquet_bytes(&other_hash),
+            Err(BloomError::InvalidPayload(_))
+        ));
+        assert!(SplitBlockBloomFilterRS::from_bytes(&[0; 33]).is_err());
+        assert!(SplitBlockBloomFilterRS::with_num_bytes(0).is_err());
+    }
+
+    #[test]
+    fn test_sizing() {
+        assert_eq!(calc_split_block_number_of_bytes(0, 0.01), MIN_BYTES);
+        assert_eq!(calc_split_block_number_of_bytes(usize::MAX, 0.01), MAX_BYTES);
+        let num_of_bytes = calc_split_block_number_of_bytes(10_000, 0.01);
+        assert!(num_of_bytes.is_power_of_two());
+
+        let mut filter = SplitBlockBloomFilterRS::new(10_000, 0.01);
+        assert!(filter.false_positive_rate(10_000) <= 0.01);
+        for i in 0..10_000_i64 {
+            filter.add_bytes(&i.to_le_bytes());
+        }
+        let false_positives = (10_000..110_000_i64).filter(|i| filter.contains_bytes(&i.to_le_bytes())).count();
+        assert!((false_positives as f64 / 100_000.0) < 0.01);
+    }
+}
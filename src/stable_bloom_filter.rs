@@ -0,0 +1,297 @@
+//! Stable Bloom filter in Rust (Deng & Rafiei, "Approximately Detecting Duplicates for Streaming Data using
+//! Stable Bloom Filters", 2006)
+//! Like the counting filter it keeps small counters (cells) instead of bits. Every insert first decrements `P`
+//! randomly chosen cells and then sets the item's `K` cells to `Max`. Old items fade out as their cells are
+//! decremented, so the fraction of non-zero cells, and with it the false positive rate, converges to a fixed
+//! "stable point" however long the stream is. The price is false negatives for items that were added long ago.
+
+use serde::Serialize;
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use crate::counting_bloom_filter::{CounterWidth, PackedCounters};
+use crate::error::BloomError;
+use crate::hashing::{self, reduce, splitmix64, AnyHasher, IndexMode};
+use crate::serialization;
+
+
+/// Calculates the false positive rate a stable Bloom filter converges to
+/// This is calculated by `(1 - (1 / (1 + 1 / (P * (1/K - 1/m))))^Max)^K`
+///
+/// # Arguments
+/// * `num_of_cells` - number of cells (m)
+/// * `count_of_hashes` - number of cells set per item (K)
+/// * `max_value` - value the cells of an item are set to (Max)
+/// * `decrements_per_insert` - number of random cells decremented per insert (P)
+pub fn stable_point_false_positive_rate(num_of_cells: usize, count_of_hashes: usize, max_value: u32, decrements_per_insert: usize) -> f64 {
+    let k = count_of_hashes as f64;
+    let decay = decrements_per_insert as f64 * (1.0 / k - 1.0 / num_of_cells as f64);
+    let zero_fraction = (1.0 / (1.0 + 1.0 / decay)).powi(max_value as i32);
+    (1.0 - zero_fraction).powf(k)
+}
+
+/// Calculates the number of cells to decrement per insert so that the stable point false positive rate is at most
+/// the desired rate; the inverse of `stable_point_false_positive_rate`, rounded up
+///
+/// # Arguments
+/// * `num_of_cells` - number of cells (m)
+/// * `count_of_hashes` - number of cells set per item (K)
+/// * `max_value` - value the cells of an item are set to (Max)
+/// * `desired_false_positive_rate` - false positive rate at the stable point
+pub fn calc_optimal_decrements(num_of_cells: usize, count_of_hashes: usize, max_value: u32, desired_false_positive_rate: f64) -> usize {
+    let k = count_of_hashes as f64;
+    let zero_fraction = 1.0 - desired_false_positive_rate.powf(1.0 / k);
+    let decay = 1.0 / zero_fraction.powf(1.0 / max_value as f64) - 1.0;
+    let decrements = 1.0 / (decay * (1.0 / k - 1.0 / num_of_cells as f64));
+    (decrements.ceil() as usize).max(1)
+}
+
+
+/// A Bloom Filter for unbounded streams whose false positive rate stays at a fixed level
+pub struct StableBloomFilterRS {
+    cells: PackedCounters,
+    /// Number of cells each item sets
+    count_of_hashes: usize,
+    /// The value the cells of an item are set to
+    max_value: u32,
+    /// Number of random cells that are decremented before every insert
+    decrements_per_insert: usize,
+    /// The hash function used to derive the cell indices
+    hasher: AnyHasher,
+    /// State of the random generator that picks the cells to decrement
+    random_state: u64,
+}
+
+impl StableBloomFilterRS {
+    /// Creates a stable Bloom filter that converges to the desired false positive rate
+    /// The number of hashes is `ceil(log2(1 / p))` and the number of decrements follows from
+    /// `calc_optimal_decrements`
+    /// Returns an error if a parameter is out of range
+    ///
+    /// # Arguments
+    /// * `num_of_cells` - number of cells; more cells remember more items
+    /// * `max_value` - value the cells of an item are set to; higher values let items fade out more gradually
+    /// * `desired_false_positive_rate` - false positive rate at the stable point
+    pub fn new(num_of_cells: usize, max_value: u32, desired_false_positive_rate: f64) -> Result<Self, BloomError> {
+        if !(desired_false_positive_rate > 0.0 && desired_false_positive_rate < 1.0) {
+            return Err(BloomError::InvalidParameter(format!("false positive rate must be between 0 and 1, got {}", desired_false_positive_rate)));
+        }
+        let count_of_hashes = ((1.0 / desired_false_positive_rate).log2().ceil() as usize).max(1);
+        let decrements_per_insert = calc_optimal_decrements(num_of_cells, count_of_hashes, max_value, desired_false_positive_rate);
+        StableBloomFilterRS::with_parameters(num_of_cells, count_of_hashes, max_value, decrements_per_insert)
+    }
+
+    /// Creates a stable Bloom filter with explicit parameters
+    /// Returns an error if a parameter is out of range
+    ///
+    /// # Arguments
+    /// * `num_of_cells` - number of cells (m); must exceed `count_of_hashes`
+    /// * `count_of_hashes` - number of cells set per item (K, > 0)
+    /// * `max_value` - value the cells of an item are set to (Max, > 0)
+    /// * `decrements_per_insert` - number of random cells decremented per insert (P, > 0)
+    pub fn with_parameters(num_of_cells: usize, count_of_hashes: usize, max_value: u32, decrements_per_insert: usize) -> Result<Self, BloomError> {
+        if count_of_hashes == 0 || num_of_cells <= count_of_hashes {
+            return Err(BloomError::InvalidParameter(format!(
+                "need more cells than hashes and at least one hash, got {} cells and {} hashes", num_of_cells, count_of_hashes
+            )));
+        }
+        if max_value == 0 || decrements_per_insert == 0 {
+            return Err(BloomError::InvalidParameter("max value and decrements per insert must be > 0".to_string()));
+        }
+        let counter_width = [CounterWidth::Four, CounterWidth::Eight, CounterWidth::Sixteen, CounterWidth::ThirtyTwo]
+            .into_iter()
+            .find(|width| width.max_value() >= max_value)
+            .expect("32 bit counters hold any u32");
+        Ok(StableBloomFilterRS {
+            cells: PackedCounters::new(num_of_cells, counter_width),
+            count_of_hashes,
+            max_value,
+            decrements_per_insert,
+            hasher: AnyHasher::default(),
+            random_state: RandomState::new().hash_one(0),
+        })
+    }
+
+    /// Seeds the random generator that picks the cells to decrement, to make the filter deterministic
+    ///
+    /// # Arguments
+    /// * `seed` - any value; filters with the same seed and inserts end up identical
+    pub fn set_seed(&mut self, seed: u64) {
+        self.random_state = seed;
+    }
+
+    /// Adds bytes to the filter: decrements `P` random cells, then sets the item's cells to `Max`
+    ///
+    /// # Arguments
+    /// * `hash_bytes` - item to insert into the filter
+    pub fn add_bytes(&mut self, hash_bytes: &[u8]) {
+        for _ in 0..self.decrements_per_insert {
+            let index = reduce(splitmix64(&mut self.random_state), self.cells.len());
+            let cell = self.cells.get(index);
+            if cell > 0 {
+                self.cells.set(index, cell - 1);
+            }
+        }
+        for index in self.indices(hash_bytes) {
+            self.cells.set(index, self.max_value);
+        }
+    }
+
+    /// Hashes an item to the filter
+    ///
+    /// # Arguments
+    /// * `item` - item to insert into the filter
+    pub fn add<T: Serialize>(&mut self, item: &T) {
+        self.add_bytes(&serialization::serialize(item));
+    }
+
+    /// Checks if given bytes were added recently
+    /// Returns boolean: False means that the item wasn't added, or was added so long ago that it faded out.
+    /// True means that the item may have been added
+    ///
+    /// # Arguments
+    /// * `hash_bytes` - bytes to check for membership
+    pub fn contains_bytes(&self, hash_bytes: &[u8]) -> bool {
+        self.indices(hash_bytes).all(|index| self.cells.get(index) > 0)
+    }
+
+    /// Checks if a given item was added recently
+    ///
+    /// # Arguments
+    /// * `item` - item to be hashed and checked for membership
+    pub fn contains<T: Serialize>(&self, item: &T) -> bool {
+        self.contains_bytes(&serialization::serialize(item))
+    }
+
+    /// Resets all cells to zero
+    pub fn clear(&mut self) {
+        self.cells.clear();
+    }
+
+    /// Calculates the false positive rate this filter converges to with `stable_point_false_positive_rate`
+    pub fn stable_false_positive_rate(&self) -> f64 {
+        stable_point_false_positive_rate(self.cells.len(), self.count_of_hashes, self.max_value, self.decrements_per_insert)
+    }
+
+    /// Calculates the false positive rate from the cells that are currently non-zero: `(non-zero cells / cells) ^ K`
+    pub fn current_false_positive_rate(&self) -> f64 {
+        let non_zero_cells = self.cells.iter().filter(|cell| *cell > 0).count();
+        (non_zero_cells as f64 / self.cells.len() as f64).powf(self.count_of_hashes as f64)
+    }
+
+    /// Retrieve the number of cells
+    pub fn get_cell_count(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// Retrieve the number of cells each item sets
+    pub fn get_hash_count(&self) -> usize {
+        self.count_of_hashes
+    }
+
+    /// Retrieve the value the cells of an item are set to
+    pub fn get_max_value(&self) -> u32 {
+        self.max_value
+    }
+
+    /// Retrieve the number of random cells decremented per insert
+    pub fn get_decrements_per_insert(&self) -> usize {
+        self.decrements_per_insert
+    }
+
+    /// Retrieve the number of bits per cell
+    pub fn get_cell_width(&self) -> CounterWidth {
+        self.cells.width()
+    }
+
+    fn indices<'a>(&self, hash_bytes: &'a [u8]) -> impl Iterator<Item = usize> + 'a {
+        hashing::indices(self.hasher, IndexMode::Rehash, hash_bytes, self.count_of_hashes, self.cells.len())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seeded(num_of_cells: usize, max_value: u32, desired_false_positive_rate: f64) -> StableBloomFilterRS {
+        let mut stable_filter = StableBloomFilterRS::new(num_of_cells, max_value, desired_false_positive_rate).unwrap();
+        stable_filter.set_seed(42);
+        stable_filter
+    }
+
+    #[test]
+    fn test_recent_items_are_contained() {
+        let mut stable_filter = seeded(10_000, 3, 0.01);
+        for i in 0..20_000 {
+            stable_filter.add(&i);
+            assert!(stable_filter.contains(&i), "The last item is always contained");
+        }
+    }
+
+    #[test]
+    fn test_old_items_fade_out() {
+        let mut stable_filter = seeded(1_000, 1, 0.01);
+        stable_filter.add(&"first");
+        for i in 0..10_000 {
+            stable_filter.add(&i);
+        }
+        assert!(!stable_filter.contains(&"first"));
+    }
+
+    #[test]
+    fn test_converges_to_stable_false_positive_rate() {
+        let mut stable_filter = seeded(5_000, 3, 0.02);
+        let stable_false_positive_rate = stable_filter.stable_false_positive_rate();
+        assert!(stable_false_positive_rate <= 0.02);
+        for i in 0..100_000 {
+            stable_filter.add(&i);
+        }
+        let current = stable_filter.current_false_positive_rate();
+        assert!((current - stable_false_positive_rate).abs() < 0.005, "current {} vs stable {}", current, stable_false_positive_rate);
+
+        let false_positives = (1_000_000..1_050_000).filter(|i| stable_filter.contains(i)).count();
+        let false_positive_rate = false_positives as f64 / 50_000.0;
+        assert!((false_positive_rate - stable_false_positive_rate).abs() < 0.005, "measured {} vs stable {}", false_positive_rate, stable_false_positive_rate);
+    }
+
+    #[test]
+    fn test_optimal_decrements_meet_the_target() {
+        for (max_value, desired_false_positive_rate) in [(1, 0.01_f64), (3, 0.01), (7, 0.001), (15, 0.1)] {
+            let count_of_hashes = ((1.0 / desired_false_positive_rate).log2().ceil()) as usize;
+            let decrements = calc_optimal_decrements(10_000, count_of_hashes, max_value, desired_false_positive_rate);
+            assert!(stable_point_false_positive_rate(10_000, count_of_hashes, max_value, decrements) <= desired_false_positive_rate);
+            if decrements > 1 {
+                assert!(stable_point_false_positive_rate(10_000, count_of_hashes, max_value, decrements - 1) > desired_false_positive_rate);
+            }
+        }
+    }
+
+    #[test]
+    fn test_cells_fit_the_max_value() {
+        assert_eq!(seeded(100, 3, 0.01).get_cell_width(), CounterWidth::Four);
+        assert_eq!(seeded(100, 15, 0.01).get_cell_width(), CounterWidth::Four);
+        assert_eq!(seeded(100, 16, 0.01).get_cell_width(), CounterWidth::Eight);
+        assert_eq!(seeded(100, 70_000, 0.01).get_cell_width(), CounterWidth::ThirtyTwo);
+    }
+
+    #[test]
+    fn test_seed_makes_filter_deterministic() {
+        let mut first = seeded(1_000, 3, 0.01);
+        let mut second = seeded(1_000, 3, 0.01);
+        for i in 0..5_000 {
+            first.add(&i);
+            second.add(&i);
+        }
+        assert!(first.cells.iter().eq(second.cells.iter()));
+        first.clear();
+        assert_eq!(first.current_false_positive_rate(), 0.0);
+    }
+
+    #[test]
+    fn test_rejects_invalid_parameters() {
+        assert!(StableBloomFilterRS::new(1_000, 3, 0.0).is_err());
+        assert!(StableBloomFilterRS::new(1_000, 0, 0.01).is_err());
+        assert!(StableBloomFilterRS::with_parameters(3, 3, 1, 1).is_err());
+        assert!(StableBloomFilterRS::with_parameters(100, 3, 1, 0).is_err());
+    }
+}
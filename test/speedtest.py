@@ -3,7 +3,7 @@ import time
 import timeit
 from typing import List
 
-from bloomlib import BloomFilter
+from bloomlib import BloomFilter, BlockedBloomFilter
 from test.utils.timing import display_times, Timing, performance_check
 from test.utils.utils_for_testing import random_str, Timer
 
@@ -54,6 +54,41 @@ def test_time_contains():
         Timing(name=f'contains unknown int', times=[t * 1_000 for t in t_contains_int_not_exists], size=None),
     ], name=f"{LANGUAGE} Contains (#{len(string_list)})", decimals=9)
 
+def test_time_index_modes():
+    """ Compares hashing once per index with double hashing (one hash per item) """
+    elem_count = 100_000
+    string_list = [random_str(16) for _ in range(elem_count)]
+    number = 3
+    repeat = 5
+    for fpr in [0.05, 0.0001]:
+        timings = []
+        for double_hashing in [False, True]:
+            bloom = BloomFilter(expected_number_of_items=elem_count, desired_false_positive_rate=fpr, double_hashing=double_hashing)
+            mode = "double hashing" if double_hashing else "rehash"
+            t_add: [float] = timeit.repeat(stmt=f"func(items)", globals={'func': bloom.add_bulk, 'items': string_list}, number=number, repeat=repeat)
+            t_contains: [float] = timeit.repeat(stmt=f"[func(s) for s in items]", globals={'func': bloom.contains, 'items': string_list}, number=number, repeat=repeat)
+            timings.append(Timing(name=f'add_bulk {mode}', times=[t * 1_000 for t in t_add], size=None))
+            timings.append(Timing(name=f'contains {mode}', times=[t * 1_000 for t in t_contains], size=None))
+
+        print('\n')
+        display_times(timings, name=f"{LANGUAGE} index modes (#{elem_count}, k={bloom.get_number_of_hashes()})", decimals=9)
+
+def test_time_blocked_lookups():
+    """ Compares lookups in a standard and a cache-line blocked filter that is too large for the CPU caches """
+    elem_count = 2_000_000
+    string_list = [random_str(16) for _ in range(elem_count)]
+    number = 1
+    repeat = 5
+    timings = []
+    for name, bloom in [("standard", BloomFilter(expected_number_of_items=elem_count, desired_false_positive_rate=0.001, double_hashing=True)),
+                        ("blocked", BlockedBloomFilter(expected_number_of_items=elem_count, desired_false_positive_rate=0.001))]:
+        bloom.add_bulk(string_list)
+        t_contains: [float] = timeit.repeat(stmt=f"[func(s) for s in items]", globals={'func': bloom.contains, 'items': string_list}, number=number, repeat=repeat)
+        timings.append(Timing(name=f'contains {name}', times=[t * 1_000 for t in t_contains], size=bloom.get_number_of_bits()))
+
+    print('\n')
+    display_times(timings, name=f"{LANGUAGE} blocked lookups (#{elem_count})", decimals=9)
+
 def test_false_positive_rate():
 
     elem_count = 10_000
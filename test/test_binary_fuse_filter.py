@@ -0,0 +1,55 @@
+import pickle
+import pytest
+from bloomlib import BinaryFuseFilter
+
+
+def test_contains():
+    fuse = BinaryFuseFilter(items=[1, "een", b"bytes", 2.5])
+    assert fuse.contains(item=1)
+    assert fuse.contains(item="een")
+    assert fuse.contains(item=b"bytes")
+    assert fuse.contains(item=2.5)
+    assert len(fuse) == 4
+
+def test_false_positive_rate():
+    fuse = BinaryFuseFilter(items=range(100_000))
+    assert all(fuse.contains(i) for i in range(100_000))
+    false_positives = sum(fuse.contains(i) for i in range(100_000, 300_000))
+    assert abs(false_positives / 200_000 - fuse.estimate_false_positive_rate()) < 0.001
+    assert fuse.bits_per_item() < 9.6
+
+@pytest.mark.parametrize("fingerprint_bits", [8, 16, 32])
+def test_fingerprint_bits(fingerprint_bits):
+    fuse = BinaryFuseFilter(items=range(1_000), fingerprint_bits=fingerprint_bits)
+    assert fuse.get_fingerprint_bits() == fingerprint_bits
+    assert fuse.estimate_false_positive_rate() == 0.5 ** fingerprint_bits
+    assert all(fuse.contains(i) for i in range(1_000))
+
+def test_invalid_parameters():
+    with pytest.raises(ValueError):
+        BinaryFuseFilter(items=range(10), fingerprint_bits=12)
+    with pytest.raises(ValueError):
+        BinaryFuseFilter(items=range(10), max_attempts=0)
+    with pytest.raises(TypeError):
+        BinaryFuseFilter(items=3)
+
+def test_duplicates_and_empty():
+    fuse = BinaryFuseFilter(items=[1, 1, 2, 2, 3])
+    assert fuse.get_item_count() == 3
+    empty = BinaryFuseFilter(items=[])
+    assert len(empty) == 0
+    assert not empty.contains(1)
+
+def test_serialization(tmp_path):
+    fuse = BinaryFuseFilter(items=range(500), fingerprint_bits=16)
+    restored = BinaryFuseFilter.from_bytes(fuse.to_bytes())
+    assert restored.to_bytes() == fuse.to_bytes()
+    assert restored.get_fingerprint_bits() == 16
+    path = str(tmp_path / "filter.bin")
+    fuse.save(path)
+    assert all(BinaryFuseFilter.load(path).contains(i) for i in range(500))
+    unpickled = pickle.loads(pickle.dumps(fuse))
+    assert len(unpickled) == 500
+    assert unpickled.contains(499)
+    with pytest.raises(ValueError):
+        BinaryFuseFilter.from_bytes(b"not a filter")
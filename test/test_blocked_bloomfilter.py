@@ -0,0 +1,39 @@
+import pytest
+from bloomlib import BlockedBloomFilter, BloomFilter
+
+
+def test_can_add_and_contains():
+    bloom = BlockedBloomFilter(expected_number_of_items=10, desired_false_positive_rate=0.05)
+    bloom.add(1)
+    bloom.add("een")
+    assert bloom.contains(item=1)
+    assert bloom.contains(item="een")
+    assert not bloom.contains(item=1111)
+
+def test_add_bulk_and_clear():
+    bloom = BlockedBloomFilter(expected_number_of_items=100, desired_false_positive_rate=0.05)
+    bloom.add_bulk(items=range(100))
+    assert all(bloom.contains(i) for i in range(100))
+    bloom.clear()
+    assert not any(bloom.contains(i) for i in range(100))
+    with pytest.raises(Exception):
+        bloom.add_bulk(items=3)
+
+def test_sized_in_whole_blocks():
+    bloom = BlockedBloomFilter(expected_number_of_items=10_000, desired_false_positive_rate=0.01)
+    assert bloom.get_number_of_bits() == bloom.get_number_of_blocks() * 512
+    assert bloom.get_number_of_bits() > BloomFilter(10_000, 0.01).get_number_of_bits()
+    assert bloom.estimate_false_positive_rate() <= 0.01
+
+def test_false_positive_rate():
+    bloom = BlockedBloomFilter(expected_number_of_items=10_000, desired_false_positive_rate=0.01)
+    bloom.add_bulk(range(10_000))
+    false_positives = sum(bloom.contains(i) for i in range(10_000, 110_000))
+    assert false_positives / 100_000 < 0.013
+
+def test_invalid_parameters():
+    for desired_false_positive_rate in [0.0, 1.0, 1e-12]:
+        with pytest.raises(ValueError):
+            BlockedBloomFilter(expected_number_of_items=10, desired_false_positive_rate=desired_false_positive_rate)
+    with pytest.raises(ValueError):
+        BlockedBloomFilter(expected_number_of_items=0, desired_false_positive_rate=0.01)
@@ -1,5 +1,10 @@
 import dataclasses
 import datetime
+import os
+import pickle
+import subprocess
+import sys
+import warnings
 
 import pytest
 from bloomlib import BloomFilter
@@ -72,9 +77,11 @@ def test_can_add_all_types():
     bloom.add(_time)
     assert bloom.contains(item=_time)
     assert not bloom.contains(item=datetime.time(hour=3, minute=3, second=3))
-    bloom.add(_class_instance)
-    assert bloom.contains(item=_class_instance)
-    assert not bloom.contains(item=Person(name="other"))
+    # instances are only hashable by identity, which differs between processes
+    with pytest.raises(TypeError):
+        bloom.add(_class_instance)
+    with pytest.raises(TypeError):
+        bloom.contains(item=_class_instance)
 
     # bloom.add(_dataclass_instance)
     # assert bloom.contains(item=_dataclass_instance)
@@ -135,5 +142,313 @@ def test_add_bulk_accepts_all_iterables():
         bloom.add_bulk(items=3)    # int
 
 
+def test_to_bytes_and_from_bytes():
+    bloom = BloomFilter(expected_number_of_items=100, desired_false_positive_rate=0.05)
+    bloom.add_bulk(items=range(100))
+
+    restored = BloomFilter.from_bytes(bloom.to_bytes())
+    assert all(restored.contains(i) for i in range(100))
+    assert restored.get_number_of_bits() == bloom.get_number_of_bits()
+    assert restored.get_number_of_hashes() == bloom.get_number_of_hashes()
+    assert restored.to_bytes() == bloom.to_bytes()
+
+def test_from_bytes_rejects_invalid_payload():
+    bloom = BloomFilter(expected_number_of_items=100, desired_false_positive_rate=0.05)
+    data = bloom.to_bytes()
+    with pytest.raises(ValueError):
+        BloomFilter.from_bytes(data[:-1])
+    with pytest.raises(ValueError):
+        BloomFilter.from_bytes(b"not a bloom filter")
+
+def test_can_pickle():
+    bloom = BloomFilter(expected_number_of_items=100, desired_false_positive_rate=0.05)
+    bloom.add_bulk(items=["een", "twee", "drie"])
+
+    restored = pickle.loads(pickle.dumps(bloom))
+    assert isinstance(restored, BloomFilter)
+    assert restored.contains("een")
+    assert restored.contains("drie")
+    assert not restored.contains("vier")
+    assert restored.to_bytes() == bloom.to_bytes()
+
+def test_save_and_load(tmp_path):
+    path = str(tmp_path / "filter.bloom")
+    bloom = BloomFilter(expected_number_of_items=100, desired_false_positive_rate=0.05)
+    bloom.add(42)
+    bloom.save(path)
+
+    restored = BloomFilter.load(path)
+    assert restored.contains(42)
+    assert not restored.contains(43)
+
+def test_items_survive_a_fresh_process(tmp_path):
+    # str() of a set and __hash__ of bytes and None follow the per-process hash seed, so check another seed
+    path = str(tmp_path / "filter.bloom")
+    items = [b"key-1", None, "een", 42, 0.5, True, frozenset(["een", "twee"]), {"een", "twee", "drie"}, ("een", 1)]
+    bloom = BloomFilter(expected_number_of_items=100, desired_false_positive_rate=0.01)
+    bloom.add_bulk(items)
+    bloom.save(path)
+
+    script = (
+        "import sys\n"
+        "from bloomlib import BloomFilter\n"
+        f"bloom = BloomFilter.load({path!r})\n"
+        f"sys.exit(0 if all(bloom.contains(item) for item in {items!r}) else 1)\n"
+    )
+    env = dict(os.environ, PYTHONHASHSEED="12345", PYTHONPATH=os.pathsep.join(sys.path))
+    assert subprocess.run([sys.executable, "-c", script], env=env).returncode == 0
+
+def test_bytes_and_none_have_their_own_encoding():
+    bloom = BloomFilter(expected_number_of_items=100, desired_false_positive_rate=0.01)
+    bloom.add(b"een")
+    bloom.add(None)
+    assert bloom.contains(b"een") and bloom.contains(bytearray(b"een"))
+    assert not bloom.contains("een")
+    assert not bloom.contains(b"twee")
+    assert not bloom.contains("None")
+    assert not bloom.contains(False)
+
+def test_union_operator():
+    monday = BloomFilter(expected_number_of_items=100, desired_false_positive_rate=0.01)
+    tuesday = BloomFilter(expected_number_of_items=100, desired_false_positive_rate=0.01)
+    monday.add("een")
+    tuesday.add("twee")
+
+    both = monday | tuesday
+    assert both.contains("een") and both.contains("twee")
+    assert not monday.contains("twee")
+
+    monday |= tuesday
+    assert monday.contains("twee")
+    assert monday.to_bytes() == both.to_bytes()
+
+def test_intersection_operator():
+    first = BloomFilter(expected_number_of_items=100, desired_false_positive_rate=0.01)
+    second = BloomFilter(expected_number_of_items=100, desired_false_positive_rate=0.01)
+    first.add_bulk(items=["shared", "first"])
+    second.add_bulk(items=["shared", "second"])
+
+    shared = first & second
+    assert shared.contains("shared")
+    assert not shared.contains("first")
+    assert not shared.contains("second")
+
+    first &= second
+    assert first.to_bytes() == shared.to_bytes()
+    first &= first
+    assert first.to_bytes() == shared.to_bytes()
+
+def test_set_operators_reject_incompatible_filters():
+    small = BloomFilter(expected_number_of_items=100, desired_false_positive_rate=0.01)
+    large = BloomFilter(expected_number_of_items=1_000, desired_false_positive_rate=0.01)
+    with pytest.raises(ValueError):
+        small | large
+    with pytest.raises(ValueError):
+        small &= large
+    with pytest.raises(TypeError):
+        small | 3
+
+def test_estimate_cardinality():
+    bloom = BloomFilter(expected_number_of_items=10_000, desired_false_positive_rate=0.01)
+    assert len(bloom) == 0
+    bloom.add_bulk(items=range(5_000))
+    assert abs(bloom.estimate_cardinality() - 5_000) < 5_000 * 0.03
+    assert abs(len(bloom) - 5_000) < 5_000 * 0.03
+
+def test_filter_is_always_truthy():
+    bloom = BloomFilter(expected_number_of_items=10_000, desired_false_positive_rate=0.01)
+    assert bloom
+    saturated = BloomFilter(expected_number_of_items=1, desired_false_positive_rate=0.5)
+    saturated.add_bulk(items=range(1_000))
+    with pytest.raises(OverflowError):
+        len(saturated)
+    assert saturated
+
+def test_estimate_union_and_intersection_size():
+    first = BloomFilter(expected_number_of_items=10_000, desired_false_positive_rate=0.01)
+    second = BloomFilter(expected_number_of_items=10_000, desired_false_positive_rate=0.01)
+    first.add_bulk(items=range(0, 4_000))
+    second.add_bulk(items=range(2_000, 6_000))
+    assert abs(first.estimate_union_size(second) - 6_000) < 6_000 * 0.03
+    assert abs(first.estimate_intersection_size(second) - 2_000) < 2_000 * 0.1
+    with pytest.raises(ValueError):
+        first.estimate_union_size(BloomFilter(expected_number_of_items=10, desired_false_positive_rate=0.01))
+
+def test_current_false_positive_rate_and_fill_ratio():
+    desired_fp_rate = 0.01
+    bloom = BloomFilter(expected_number_of_items=10_000, desired_false_positive_rate=desired_fp_rate)
+    assert bloom.fill_ratio() == 0
+    assert bloom.current_false_positive_rate() == 0
+    assert not bloom.is_saturated()
+
+    bloom.add_bulk(items=range(10_000))
+    assert abs(bloom.fill_ratio() - 0.5) < 0.02
+    assert abs(bloom.current_false_positive_rate() - desired_fp_rate) < desired_fp_rate * 0.2
+    assert not bloom.is_saturated()
+
+    bloom.add_bulk(items=range(10_000, 30_000))
+    assert bloom.current_false_positive_rate() > desired_fp_rate * 10
+    assert bloom.is_saturated()
+    assert bloom.is_saturated(threshold=0.8)
+    assert not bloom.is_saturated(threshold=0.95)
+
+def test_inserted_count_and_remaining_capacity():
+    bloom = BloomFilter(expected_number_of_items=10, desired_false_positive_rate=0.01)
+    assert bloom.get_inserted_count() == 0
+    assert bloom.remaining_capacity() == 10
+    bloom.add_bulk(items=[1, 2, 3, 3])
+    assert bloom.get_inserted_count() == 4
+    assert bloom.remaining_capacity() == 6
+    bloom.clear()
+    assert bloom.get_inserted_count() == 0
+
+def test_count_new_items_only():
+    bloom = BloomFilter(expected_number_of_items=10, desired_false_positive_rate=0.01, count_new_items_only=True)
+    bloom.add_bulk(items=[1, 2, 3, 3, 3])
+    assert bloom.get_inserted_count() == 3
+
+def test_inserted_count_survives_serialization():
+    bloom = BloomFilter(expected_number_of_items=10, desired_false_positive_rate=0.01)
+    bloom.add_bulk(items=[1, 2, 3])
+    assert BloomFilter.from_bytes(bloom.to_bytes()).get_inserted_count() == 3
+    assert pickle.loads(pickle.dumps(bloom)).get_inserted_count() == 3
+
+def test_overflow_policy_error():
+    bloom = BloomFilter(expected_number_of_items=3, desired_false_positive_rate=0.01, overflow_policy="error")
+    bloom.add_bulk(items=[1, 2, 3])
+    with pytest.raises(ValueError):
+        bloom.add(4)
+    assert not bloom.contains(4)
+
+def test_overflow_policy_warn():
+    bloom = BloomFilter(expected_number_of_items=3, desired_false_positive_rate=0.01, overflow_policy="warn")
+    with warnings.catch_warnings(record=True) as caught:
+        warnings.simplefilter("always")
+        bloom.add_bulk(items=[1, 2, 3])
+        assert len(caught) == 0
+        bloom.add_bulk(items=[4, 5])
+    assert len(caught) == 1
+    assert issubclass(caught[0].category, RuntimeWarning)
+    assert bloom.contains(4) and bloom.contains(5)
+
+def test_overflow_policy_warn_as_error():
+    bloom = BloomFilter(expected_number_of_items=1, desired_false_positive_rate=0.01, overflow_policy="warn")
+    bloom.add(1)
+    with warnings.catch_warnings():
+        warnings.simplefilter("error")
+        with pytest.raises(RuntimeWarning):
+            bloom.add(2)
+
+def test_overflow_policy_survives_serialization():
+    bloom = BloomFilter(expected_number_of_items=3, desired_false_positive_rate=0.01, overflow_policy="error")
+    bloom.add_bulk(items=[1, 2, 3])
+    restored = pickle.loads(pickle.dumps(bloom))
+    with pytest.raises(ValueError):
+        restored.add(4)
+    with pytest.raises(ValueError):
+        BloomFilter.from_bytes(bloom.to_bytes()).add(4)
+
+    bloom = BloomFilter(expected_number_of_items=3, desired_false_positive_rate=0.01, overflow_policy="warn")
+    bloom.add_bulk(items=[1, 2, 3])
+    restored = pickle.loads(pickle.dumps(bloom))
+    with warnings.catch_warnings(record=True) as caught:
+        warnings.simplefilter("always")
+        restored.add(4)
+    assert len(caught) == 1
+    assert issubclass(caught[0].category, RuntimeWarning)
+
+def test_invalid_overflow_policy():
+    with pytest.raises(ValueError):
+        BloomFilter(expected_number_of_items=3, desired_false_positive_rate=0.01, overflow_policy="explode")
+
+def test_double_hashing():
+    bloom = BloomFilter(expected_number_of_items=1000, desired_false_positive_rate=0.01, double_hashing=True)
+    bloom.add_bulk(items=range(1000))
+    assert all(bloom.contains(i) for i in range(1000))
+    false_positives = sum(bloom.contains(i) for i in range(1000, 101_000))
+    assert false_positives / 100_000 < 0.02
+
+def test_double_hashing_survives_serialization():
+    bloom = BloomFilter(expected_number_of_items=100, desired_false_positive_rate=0.01, double_hashing=True)
+    bloom.add_bulk(items=["a", "b", "c"])
+    restored = pickle.loads(pickle.dumps(bloom))
+    assert all(restored.contains(s) for s in ["a", "b", "c"])
+    assert restored.to_bytes() == bloom.to_bytes()
+    with pytest.raises(ValueError):
+        bloom | BloomFilter(expected_number_of_items=100, desired_false_positive_rate=0.01)
+
+@pytest.mark.parametrize("hash_algorithm", ["murmur3_32", "murmur3_128", "xxh3", "siphash13", "fnv1a", "crc32c"])
+def test_hash_algorithms(hash_algorithm):
+    bloom = BloomFilter(expected_number_of_items=1000, desired_false_positive_rate=0.01, hash_algorithm=hash_algorithm)
+    assert bloom.get_hash_algorithm() == hash_algorithm
+    bloom.add_bulk(items=[f"item{i}" for i in range(1000)])
+    assert all(bloom.contains(f"item{i}") for i in range(1000))
+
+    restored = BloomFilter.from_bytes(bloom.to_bytes())
+    assert restored.get_hash_algorithm() == hash_algorithm
+    assert all(restored.contains(f"item{i}") for i in range(1000))
+
+def test_default_hash_algorithm():
+    assert BloomFilter(expected_number_of_items=10, desired_false_positive_rate=0.01).get_hash_algorithm() == "murmur3_128"
+
+def test_invalid_hash_algorithm():
+    with pytest.raises(ValueError):
+        BloomFilter(expected_number_of_items=10, desired_false_positive_rate=0.01, hash_algorithm="md5")
+
+def test_set_operators_reject_other_hash_algorithm():
+    bloom_murmur = BloomFilter(expected_number_of_items=10, desired_false_positive_rate=0.01)
+    bloom_xxh3 = BloomFilter(expected_number_of_items=10, desired_false_positive_rate=0.01, hash_algorithm="xxh3")
+    with pytest.raises(ValueError):
+        bloom_murmur | bloom_xxh3
+
+def test_seeded_filter():
+    bloom = BloomFilter(expected_number_of_items=100, desired_false_positive_rate=0.01, seed=b"0123456789abcdef")
+    assert bloom.get_hash_algorithm() == "siphash13"
+    bloom.add_bulk(items=range(100))
+    assert all(bloom.contains(i) for i in range(100))
+
+    same_seed = BloomFilter(expected_number_of_items=100, desired_false_positive_rate=0.01, seed=b"0123456789abcdef")
+    same_seed.add_bulk(items=range(100))
+    other_seed = BloomFilter(expected_number_of_items=100, desired_false_positive_rate=0.01, seed=42)
+    other_seed.add_bulk(items=range(100))
+    assert same_seed.to_bytes() == bloom.to_bytes()
+    assert other_seed.to_bytes() != bloom.to_bytes()
+    with pytest.raises(ValueError):
+        bloom | other_seed
+
+def test_seed_is_not_persisted_by_default():
+    bloom = BloomFilter(expected_number_of_items=100, desired_false_positive_rate=0.01, seed=12345)
+    bloom.add("item")
+    data = bloom.to_bytes()
+    with pytest.raises(ValueError):
+        BloomFilter.from_bytes(data)
+    assert BloomFilter.from_bytes(data, seed=12345).contains("item")
+
+def test_seeded_filter_can_be_pickled():
+    bloom = BloomFilter(expected_number_of_items=100, desired_false_positive_rate=0.01, seed=12345)
+    bloom.add("item")
+    restored = pickle.loads(pickle.dumps(bloom))
+    assert restored.contains("item")
+    # the pickle carries the seed, but the restored filter still leaves it out of to_bytes
+    assert restored.to_bytes() == bloom.to_bytes()
+    with pytest.raises(ValueError):
+        BloomFilter.from_bytes(restored.to_bytes())
+
+def test_seed_can_be_persisted(tmp_path):
+    bloom = BloomFilter(expected_number_of_items=100, desired_false_positive_rate=0.01, seed=12345, persist_seed=True)
+    bloom.add("item")
+    assert BloomFilter.from_bytes(bloom.to_bytes()).contains("item")
+    assert pickle.loads(pickle.dumps(bloom)).contains("item")
 
+    path = str(tmp_path / "seeded.bloom")
+    bloom.save(path)
+    assert BloomFilter.load(path, seed=12345).contains("item")
+    with pytest.raises(ValueError):
+        BloomFilter.load(path, seed=54321)
 
+def test_invalid_seed():
+    for seed in [b"too short", -1, 2**128, "a string", 0, bytes(16)]:
+        with pytest.raises(ValueError):
+            BloomFilter(expected_number_of_items=10, desired_false_positive_rate=0.01, seed=seed)
+    with pytest.raises(ValueError):
+        BloomFilter(expected_number_of_items=10, desired_false_positive_rate=0.01, seed=1, hash_algorithm="xxh3")
@@ -0,0 +1,55 @@
+import pickle
+import pytest
+from bloomlib import BloomierFilter
+
+
+def test_returns_every_value():
+    shards = {f"user-{i}": i % 300 for i in range(10_000)}
+    bf = BloomierFilter(shards)
+    assert all(bf[key] == value for key, value in shards.items())
+    assert all(bf.get(key) == value for key, value in shards.items())
+    assert len(bf) == 10_000
+    assert bf.get_value_bits() == 16
+    assert bf.bits_per_item() < 16 * 1.3
+
+def test_value_bits():
+    assert BloomierFilter({"a": 255}).get_value_bits() == 8
+    assert BloomierFilter({"a": 2**20}).get_value_bits() == 32
+    assert BloomierFilter([("a", 1), ("b", 2)], value_bits=32)["b"] == 2
+    with pytest.raises(ValueError):
+        BloomierFilter({"a": 256}, value_bits=8)
+    with pytest.raises(ValueError):
+        BloomierFilter({"a": 1}, value_bits=12)
+    with pytest.raises(OverflowError):
+        BloomierFilter({"a": -1})
+
+def test_pairs_and_mixed_keys():
+    bf = BloomierFilter([(1, 10), ("one", 11), (1.5, 12), (1, 10)])
+    assert len(bf) == 3
+    assert (bf[1], bf["one"], bf[1.5]) == (10, 11, 12)
+    with pytest.raises(ValueError):
+        BloomierFilter([("a", 1), ("a", 2)])
+    with pytest.raises(TypeError):
+        BloomierFilter(5)
+
+def test_read_only_mapping():
+    bf = BloomierFilter({"a": 1})
+    with pytest.raises(TypeError):
+        bf["a"] = 2
+    with pytest.raises(TypeError):
+        "a" in bf
+    with pytest.raises(TypeError):
+        list(bf)
+    assert len(BloomierFilter({})) == 0
+
+def test_serialization(tmp_path):
+    shards = {f"user-{i}": i % 7 for i in range(1000)}
+    bf = BloomierFilter(shards)
+    restored = BloomierFilter.from_bytes(bf.to_bytes())
+    assert all(restored[key] == value for key, value in shards.items())
+    path = str(tmp_path / "filter.bin")
+    bf.save(path)
+    assert BloomierFilter.load(path).to_bytes() == bf.to_bytes()
+    assert pickle.loads(pickle.dumps(bf)).to_bytes() == bf.to_bytes()
+    with pytest.raises(ValueError):
+        BloomierFilter.from_bytes(b"not a filter")
@@ -0,0 +1,100 @@
+import pytest
+from bloomlib import CountingBloomFilter
+
+
+def test_can_add_and_contains():
+    bloom = CountingBloomFilter(expected_number_of_items=10, desired_false_positive_rate=0.05)
+    bloom.add(1)
+    bloom.add("een")
+    assert bloom.contains(item=1)
+    assert bloom.contains(item="een")
+    assert not bloom.contains(item=1111)
+
+def test_can_remove():
+    bloom = CountingBloomFilter(expected_number_of_items=10, desired_false_positive_rate=0.05)
+    bloom.add("een")
+    bloom.add("twee")
+    bloom.remove("een")
+    assert not bloom.contains(item="een")
+    assert bloom.contains(item="twee")
+
+def test_item_added_twice_needs_two_removes():
+    bloom = CountingBloomFilter(expected_number_of_items=10, desired_false_positive_rate=0.05)
+    bloom.add("een")
+    bloom.add("een")
+    bloom.remove("een")
+    assert bloom.contains(item="een")
+    bloom.remove("een")
+    assert not bloom.contains(item="een")
+
+def test_add_bulk_and_clear():
+    bloom = CountingBloomFilter(expected_number_of_items=100, desired_false_positive_rate=0.05)
+    bloom.add_bulk(items=range(100))
+    assert all(bloom.contains(i) for i in range(100))
+    bloom.clear()
+    assert not any(bloom.contains(i) for i in range(100))
+    with pytest.raises(Exception):
+        bloom.add_bulk(items=3)
+
+def test_sized_like_bloom_filter():
+    from bloomlib import BloomFilter
+    bloom = BloomFilter(expected_number_of_items=1_000, desired_false_positive_rate=0.01)
+    counting = CountingBloomFilter(expected_number_of_items=1_000, desired_false_positive_rate=0.01)
+    assert counting.get_number_of_counters() == bloom.get_number_of_bits()
+    assert counting.get_number_of_hashes() == bloom.get_number_of_hashes()
+
+def test_to_bloom_filter_is_identical():
+    from bloomlib import BloomFilter
+    bloom = BloomFilter(expected_number_of_items=1_000, desired_false_positive_rate=0.01)
+    counting = CountingBloomFilter(expected_number_of_items=1_000, desired_false_positive_rate=0.01)
+    bloom.add_bulk(items=range(1_000))
+    counting.add_bulk(items=range(1_000))
+    assert counting.to_bloom_filter().to_bytes() == bloom.to_bytes()
+
+def test_remove_reports_items_that_were_never_added():
+    bloom = CountingBloomFilter(expected_number_of_items=10, desired_false_positive_rate=0.01)
+    bloom.add("een")
+    assert not bloom.remove("twee")
+    assert bloom.remove("een")
+    assert not bloom.remove("een")
+
+def test_counter_bits():
+    assert CountingBloomFilter(expected_number_of_items=10, desired_false_positive_rate=0.05).get_counter_bits() == 4
+    for bits in (4, 8, 16, 32):
+        assert CountingBloomFilter(expected_number_of_items=10, desired_false_positive_rate=0.05, counter_bits=bits).get_counter_bits() == bits
+    with pytest.raises(ValueError):
+        CountingBloomFilter(expected_number_of_items=10, desired_false_positive_rate=0.05, counter_bits=3)
+
+def test_counters_saturate():
+    bloom = CountingBloomFilter(expected_number_of_items=10, desired_false_positive_rate=0.05, counter_bits=4)
+    for _ in range(20):
+        bloom.add("een")
+    assert bloom.get_number_of_saturated_counters() > 0
+    for _ in range(20):
+        bloom.remove("een")
+    assert bloom.contains("een")
+
+def test_estimate_count():
+    bloom = CountingBloomFilter(expected_number_of_items=100, desired_false_positive_rate=0.01, counter_bits=8)
+    for _ in range(5):
+        bloom.add("vijf")
+    bloom.add("een")
+    assert bloom.estimate_count("vijf") == 5
+    assert bloom.estimate_count("een") == 1
+    assert bloom.estimate_count("nul") == 0
+
+def test_conservative_update():
+    bloom = CountingBloomFilter(expected_number_of_items=100, desired_false_positive_rate=0.01, counter_bits=8, conservative_update=True)
+    for i in range(50):
+        for _ in range(i % 4):
+            bloom.add(i)
+    assert all(bloom.estimate_count(i) >= i % 4 for i in range(50))
+    with pytest.raises(ValueError):
+        bloom.remove(1)
+
+def test_invalid_parameters_raise_value_error():
+    for desired_false_positive_rate in [0.0, 1.0, -0.5, float("nan")]:
+        with pytest.raises(ValueError):
+            CountingBloomFilter(expected_number_of_items=10, desired_false_positive_rate=desired_false_positive_rate)
+    with pytest.raises(ValueError):
+        CountingBloomFilter(expected_number_of_items=0, desired_false_positive_rate=0.01)
@@ -0,0 +1,70 @@
+import pickle
+import pytest
+from bloomlib import CuckooFilter
+
+
+def test_can_add_and_contains():
+    cuckoo = CuckooFilter(expected_number_of_items=1_000, desired_false_positive_rate=0.01)
+    cuckoo.add(1)
+    cuckoo.add("een")
+    assert cuckoo.contains(item=1)
+    assert cuckoo.contains(item="een")
+    assert not cuckoo.contains(item=1111)
+    assert len(cuckoo) == 2
+
+def test_remove():
+    cuckoo = CuckooFilter(expected_number_of_items=1_000, desired_false_positive_rate=0.01)
+    cuckoo.add_bulk(range(100))
+    assert cuckoo.remove(5)
+    assert not cuckoo.contains(5)
+    assert not cuckoo.remove(5)
+    assert all(cuckoo.contains(i) for i in range(100) if i != 5)
+    assert cuckoo.get_inserted_count() == 99
+
+def test_false_positive_rate():
+    cuckoo = CuckooFilter(expected_number_of_items=10_000, desired_false_positive_rate=0.01)
+    assert cuckoo.estimate_false_positive_rate() <= 0.01
+    cuckoo.add_bulk(range(10_000))
+    false_positives = sum(cuckoo.contains(i) for i in range(10_000, 60_000))
+    assert false_positives / 50_000 < 0.01
+
+def test_full_filter_raises():
+    cuckoo = CuckooFilter(expected_number_of_items=100, desired_false_positive_rate=0.01, max_kicks=50, seed=1)
+    with pytest.raises(ValueError):
+        cuckoo.add_bulk(range(1_000))
+    assert cuckoo.load_factor() > 0.8
+    assert len(cuckoo) <= cuckoo.get_capacity()
+
+def test_parameters():
+    cuckoo = CuckooFilter(expected_number_of_items=1_000, desired_false_positive_rate=0.01, fingerprint_bits=12, bucket_size=2)
+    assert cuckoo.get_fingerprint_bits() == 12
+    assert cuckoo.get_bucket_size() == 2
+    assert cuckoo.get_capacity() == cuckoo.get_number_of_buckets() * 2
+    assert cuckoo.get_number_of_bits() == cuckoo.get_capacity() * 12
+    with pytest.raises(ValueError):
+        CuckooFilter(expected_number_of_items=1_000, desired_false_positive_rate=0.01, fingerprint_bits=33)
+    with pytest.raises(ValueError):
+        CuckooFilter(expected_number_of_items=1_000, desired_false_positive_rate=0.01, bucket_size=9)
+
+def test_serialization(tmp_path):
+    cuckoo = CuckooFilter(expected_number_of_items=1_000, desired_false_positive_rate=0.001)
+    cuckoo.add_bulk(range(500))
+    restored = CuckooFilter.from_bytes(cuckoo.to_bytes())
+    assert restored.to_bytes() == cuckoo.to_bytes()
+    path = str(tmp_path / "filter.bin")
+    cuckoo.save(path)
+    assert all(CuckooFilter.load(path).contains(i) for i in range(500))
+    unpickled = pickle.loads(pickle.dumps(cuckoo))
+    assert len(unpickled) == 500
+    with pytest.raises(ValueError):
+        CuckooFilter.from_bytes(b"not a filter")
+
+def test_add_bulk_and_clear():
+    cuckoo = CuckooFilter(expected_number_of_items=1_000, desired_false_positive_rate=0.05)
+    cuckoo.add_bulk(items=range(10))
+    assert all(cuckoo.contains(i) for i in range(10))
+    cuckoo.clear()
+    assert not any(cuckoo.contains(i) for i in range(10))
+    assert len(cuckoo) == 0
+    with pytest.raises(Exception):
+        cuckoo.add_bulk(items=3)
@@ -0,0 +1,67 @@
+import pickle
+import pytest
+from bloomlib import InvertibleBloomFilter
+
+
+def test_insert_delete_and_list():
+    iblt = InvertibleBloomFilter(expected_difference=20)
+    iblt.insert(b"een")
+    iblt.insert(b"twee")
+    iblt.delete(b"drie")
+    inserted, deleted = iblt.list_entries()
+    assert sorted(inserted) == [b"een", b"twee"]
+    assert deleted == [b"drie"]
+    iblt.delete(b"een")
+    iblt.delete(b"twee")
+    iblt.insert(b"drie")
+    assert iblt.is_empty()
+
+def test_subtract_lists_the_difference():
+    shared = [f"key-{i}".encode() for i in range(10_000)]
+    only_left = [f"left-{i}".encode() for i in range(30)]
+    only_right = [f"right-{i}".encode() for i in range(20)]
+    left = InvertibleBloomFilter(expected_difference=50)
+    right = InvertibleBloomFilter(expected_difference=50)
+    for key in shared + only_left:
+        left.insert(key)
+    for key in shared + only_right:
+        right.insert(key)
+    inserted, deleted = left.subtract(right).list_entries()
+    assert sorted(inserted) == sorted(only_left)
+    assert sorted(deleted) == sorted(only_right)
+
+def test_too_large_difference_raises():
+    iblt = InvertibleBloomFilter(expected_difference=5)
+    for i in range(200):
+        iblt.insert(str(i).encode())
+    with pytest.raises(ValueError):
+        iblt.list_entries()
+
+def test_invalid_arguments():
+    with pytest.raises(TypeError):
+        InvertibleBloomFilter(expected_difference=5).insert("not bytes")
+    with pytest.raises(ValueError):
+        InvertibleBloomFilter(expected_difference=5, number_of_hashes=1)
+    with pytest.raises(ValueError):
+        InvertibleBloomFilter(expected_difference=5).subtract(InvertibleBloomFilter(expected_difference=500))
+
+def test_clear_and_parameters():
+    iblt = InvertibleBloomFilter(expected_difference=100, number_of_hashes=4)
+    assert iblt.get_number_of_hashes() == 4
+    assert iblt.get_number_of_cells() >= 150
+    iblt.insert(b"key")
+    iblt.clear()
+    assert iblt.is_empty()
+
+def test_serialization(tmp_path):
+    iblt = InvertibleBloomFilter(expected_difference=20)
+    iblt.insert(b"een")
+    iblt.delete(b"twee")
+    restored = InvertibleBloomFilter.from_bytes(iblt.to_bytes())
+    assert restored.to_bytes() == iblt.to_bytes()
+    path = str(tmp_path / "filter.bin")
+    iblt.save(path)
+    assert InvertibleBloomFilter.load(path).list_entries() == ([b"een"], [b"twee"])
+    assert pickle.loads(pickle.dumps(iblt)).list_entries() == ([b"een"], [b"twee"])
+    with pytest.raises(ValueError):
+        InvertibleBloomFilter.from_bytes(b"not a filter")
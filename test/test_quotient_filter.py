@@ -0,0 +1,76 @@
+import pickle
+import pytest
+from bloomlib import QuotientFilter
+
+
+def test_can_add_and_contains():
+    quotient = QuotientFilter(expected_number_of_items=1_000, desired_false_positive_rate=0.01)
+    quotient.add(1)
+    quotient.add("een")
+    assert quotient.contains(item=1)
+    assert quotient.contains(item="een")
+    assert not quotient.contains(item=1111)
+    assert len(quotient) == 2
+
+def test_remove():
+    quotient = QuotientFilter(expected_number_of_items=1_000, desired_false_positive_rate=0.01)
+    quotient.add_bulk(range(100))
+    assert quotient.remove(5)
+    assert not quotient.contains(5)
+    assert not quotient.remove(5)
+    assert all(quotient.contains(i) for i in range(100) if i != 5)
+    assert quotient.get_inserted_count() == 99
+
+def test_false_positive_rate():
+    quotient = QuotientFilter(expected_number_of_items=10_000, desired_false_positive_rate=0.01)
+    quotient.add_bulk(range(10_000))
+    assert quotient.current_false_positive_rate() <= 0.01
+    false_positives = sum(quotient.contains(i) for i in range(10_000, 60_000))
+    assert false_positives / 50_000 < 0.01
+
+def test_double_when_full():
+    quotient = QuotientFilter(expected_number_of_items=100, desired_false_positive_rate=0.01)
+    slots = quotient.get_number_of_slots()
+    quotient.add_bulk(range(slots))
+    with pytest.raises(ValueError):
+        quotient.add(slots)
+    remainder_bits = quotient.get_remainder_bits()
+    quotient.double()
+    assert quotient.get_number_of_slots() == 2 * slots
+    assert quotient.get_remainder_bits() == remainder_bits - 1
+    quotient.add(slots)
+    assert all(quotient.contains(i) for i in range(slots + 1))
+
+def test_merge():
+    first = QuotientFilter(expected_number_of_items=1_000, desired_false_positive_rate=0.01)
+    second = QuotientFilter(expected_number_of_items=1_000, desired_false_positive_rate=0.01)
+    first.add_bulk(range(1_000))
+    second.add_bulk(range(1_000, 2_000))
+    merged = first.merge(second)
+    assert len(merged) == 2_000
+    assert all(merged.contains(i) for i in range(2_000))
+    with pytest.raises(ValueError):
+        first.merge(QuotientFilter(expected_number_of_items=1_000, desired_false_positive_rate=0.0001))
+
+def test_serialization(tmp_path):
+    quotient = QuotientFilter(expected_number_of_items=1_000, desired_false_positive_rate=0.001)
+    quotient.add_bulk(range(500))
+    restored = QuotientFilter.from_bytes(quotient.to_bytes())
+    assert restored.to_bytes() == quotient.to_bytes()
+    path = str(tmp_path / "filter.bin")
+    quotient.save(path)
+    assert all(QuotientFilter.load(path).contains(i) for i in range(500))
+    unpickled = pickle.loads(pickle.dumps(quotient))
+    assert len(unpickled) == 500
+    with pytest.raises(ValueError):
+        QuotientFilter.from_bytes(b"not a filter")
+
+def test_add_bulk_and_clear():
+    quotient = QuotientFilter(expected_number_of_items=1_000, desired_false_positive_rate=0.05)
+    quotient.add_bulk(items=range(10))
+    assert all(quotient.contains(i) for i in range(10))
+    quotient.clear()
+    assert not any(quotient.contains(i) for i in range(10))
+    assert len(quotient) == 0
+    with pytest.raises(Exception):
+        quotient.add_bulk(items=3)
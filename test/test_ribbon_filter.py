@@ -0,0 +1,45 @@
+import pickle
+import pytest
+from bloomlib import RibbonFilter
+
+
+def test_contains():
+    ribbon = RibbonFilter(items=[1, "een", b"bytes", 2.5], desired_false_positive_rate=0.01)
+    assert ribbon.contains(item=1)
+    assert ribbon.contains(item="een")
+    assert ribbon.contains(item=b"bytes")
+    assert ribbon.contains(item=2.5)
+    assert len(ribbon) == 4
+
+def test_false_positive_rate_and_size():
+    ribbon = RibbonFilter(items=range(100_000), desired_false_positive_rate=0.01)
+    assert ribbon.get_result_bits() == 7
+    assert all(ribbon.contains(i) for i in range(100_000))
+    false_positives = sum(ribbon.contains(i) for i in range(100_000, 300_000))
+    assert abs(false_positives / 200_000 - ribbon.estimate_false_positive_rate()) < 0.002
+    assert ribbon.bits_per_item() < 7 * 1.06
+
+def test_invalid_parameters():
+    with pytest.raises(ValueError):
+        RibbonFilter(items=range(10), desired_false_positive_rate=0)
+    with pytest.raises(ValueError):
+        RibbonFilter(items=range(10), desired_false_positive_rate=1.5)
+    with pytest.raises(TypeError):
+        RibbonFilter(items=3, desired_false_positive_rate=0.01)
+
+def test_empty():
+    ribbon = RibbonFilter(items=[], desired_false_positive_rate=0.01)
+    assert len(ribbon) == 0
+
+def test_serialization(tmp_path):
+    ribbon = RibbonFilter(items=range(500), desired_false_positive_rate=0.001)
+    restored = RibbonFilter.from_bytes(ribbon.to_bytes())
+    assert restored.to_bytes() == ribbon.to_bytes()
+    path = str(tmp_path / "filter.bin")
+    ribbon.save(path)
+    assert all(RibbonFilter.load(path).contains(i) for i in range(500))
+    unpickled = pickle.loads(pickle.dumps(ribbon))
+    assert len(unpickled) == 500
+    assert unpickled.contains(499)
+    with pytest.raises(ValueError):
+        RibbonFilter.from_bytes(b"not a filter")
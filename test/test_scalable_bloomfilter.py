@@ -0,0 +1,63 @@
+import pickle
+
+import pytest
+from bloomlib import ScalableBloomFilter
+
+
+def test_can_add_and_contains():
+    bloom = ScalableBloomFilter(initial_capacity=10, desired_false_positive_rate=0.05)
+    bloom.add(1)
+    bloom.add("een")
+    assert bloom.contains(item=1)
+    assert bloom.contains(item="een")
+    assert not bloom.contains(item=1111)
+
+def test_grows_as_items_are_added():
+    bloom = ScalableBloomFilter(initial_capacity=100, desired_false_positive_rate=0.01)
+    assert bloom.get_number_of_stages() == 1
+    bloom.add_bulk(range(10_000))
+    assert bloom.get_number_of_stages() > 1
+    assert bloom.get_capacity() >= 10_000
+    assert all(bloom.contains(i) for i in range(10_000))
+    false_positives = sum(bloom.contains(i) for i in range(10_000, 110_000))
+    assert false_positives / 100_000 < 0.01
+    assert bloom.current_false_positive_rate() < 0.01
+
+def test_growth_parameters():
+    slow = ScalableBloomFilter(100, 0.01, growth_factor=2.0)
+    fast = ScalableBloomFilter(100, 0.01, growth_factor=4.0, tightening_ratio=0.5)
+    slow.add_bulk(range(5_000))
+    fast.add_bulk(range(5_000))
+    assert fast.get_number_of_stages() < slow.get_number_of_stages()
+    with pytest.raises(ValueError):
+        ScalableBloomFilter(100, 0.01, growth_factor=0.5)
+    with pytest.raises(ValueError):
+        ScalableBloomFilter(100, 0.01, tightening_ratio=1.5)
+    with pytest.raises(ValueError):
+        ScalableBloomFilter(0, 0.01)
+
+def test_add_bulk_and_clear():
+    bloom = ScalableBloomFilter(initial_capacity=10, desired_false_positive_rate=0.05)
+    bloom.add_bulk(items=range(100))
+    assert all(bloom.contains(i) for i in range(100))
+    bloom.clear()
+    assert bloom.get_number_of_stages() == 1
+    assert bloom.get_inserted_count() == 0
+    with pytest.raises(Exception):
+        bloom.add_bulk(items=3)
+
+def test_serialization(tmp_path):
+    bloom = ScalableBloomFilter(initial_capacity=10, desired_false_positive_rate=0.01)
+    bloom.add_bulk(range(500))
+    restored = ScalableBloomFilter.from_bytes(bloom.to_bytes())
+    assert restored.get_number_of_stages() == bloom.get_number_of_stages()
+    assert all(restored.contains(i) for i in range(500))
+
+    path = str(tmp_path / "scalable.bloom")
+    bloom.save(path)
+    assert ScalableBloomFilter.load(path).to_bytes() == bloom.to_bytes()
+
+    unpickled = pickle.loads(pickle.dumps(bloom))
+    assert unpickled.to_bytes() == bloom.to_bytes()
+    with pytest.raises(ValueError):
+        ScalableBloomFilter.from_bytes(b"nope")
@@ -0,0 +1,98 @@
+import pytest
+from bloomlib import SlidingWindowBloomFilter
+
+
+class FakeClock:
+    def __init__(self):
+        self.seconds = 0.0
+
+    def __call__(self):
+        return self.seconds
+
+
+def test_can_add_and_contains():
+    bloom = SlidingWindowBloomFilter(expected_items_per_generation=10, desired_false_positive_rate=0.05, generations=3)
+    bloom.add(1)
+    bloom.add("een")
+    assert bloom.contains(item=1)
+    assert bloom.contains(item="een")
+    assert not bloom.contains(item=1111)
+
+def test_manual_advance_expires_items():
+    bloom = SlidingWindowBloomFilter(expected_items_per_generation=10, desired_false_positive_rate=0.05, generations=2)
+    bloom.add("old")
+    bloom.advance()
+    bloom.add("new")
+    assert bloom.contains("old") and bloom.contains("new")
+    bloom.advance()
+    assert not bloom.contains("old")
+    assert bloom.contains("new")
+
+def test_clock_expires_items():
+    clock = FakeClock()
+    bloom = SlidingWindowBloomFilter(100, 0.01, generations=5, generation_seconds=60, clock=clock)
+    bloom.add("event")
+    clock.seconds = 299
+    assert bloom.contains("event")
+    clock.seconds = 300
+    assert not bloom.contains("event")
+    bloom.add("later")
+    assert bloom.contains("later")
+
+def test_clock_errors_are_raised():
+    def broken_clock():
+        raise RuntimeError("no time")
+    with pytest.raises(RuntimeError):
+        SlidingWindowBloomFilter(100, 0.01, generations=2, generation_seconds=1, clock=broken_clock)
+
+def test_invalid_parameters():
+    with pytest.raises(ValueError):
+        SlidingWindowBloomFilter(100, 0.01, generations=0)
+    with pytest.raises(ValueError):
+        SlidingWindowBloomFilter(10, 0.0, generations=3)
+    with pytest.raises(ValueError):
+        SlidingWindowBloomFilter(10, 1.0, generations=3)
+    with pytest.raises(ValueError):
+        SlidingWindowBloomFilter(100, 0.01, generations=2, generation_seconds=0)
+    with pytest.raises(ValueError):
+        SlidingWindowBloomFilter(100, 0.01, generations=2, clock=FakeClock())
+
+def test_add_bulk_and_clear():
+    bloom = SlidingWindowBloomFilter(expected_items_per_generation=100, desired_false_positive_rate=0.05, generations=2)
+    bloom.add_bulk(items=range(100))
+    assert all(bloom.contains(i) for i in range(100))
+    assert bloom.get_number_of_generations() == 2
+    assert bloom.current_false_positive_rate() > 0
+    bloom.clear()
+    assert not any(bloom.contains(i) for i in range(100))
+    with pytest.raises(Exception):
+        bloom.add_bulk(items=3)
+
+def test_clock_errors_abort_the_operation():
+    clock = FakeClock()
+    bloom = SlidingWindowBloomFilter(100, 0.01, generations=2, generation_seconds=60, clock=clock)
+    bloom.add("kept")
+    clock.seconds = None
+    with pytest.raises(TypeError):
+        bloom.add("lost")
+    with pytest.raises(TypeError):
+        bloom.advance()
+    with pytest.raises(TypeError):
+        bloom.contains("kept")
+    clock.seconds = 1.0
+    assert bloom.contains("kept")
+    assert not bloom.contains("lost"), "A failed add should not store the item"
+
+def test_clock_error_is_raised_once():
+    calls = []
+    def flaky_clock():
+        calls.append(None)
+        if len(calls) == 2:
+            raise RuntimeError("no time")
+        return 0.0
+    bloom = SlidingWindowBloomFilter(100, 0.01, generations=2, generation_seconds=1, clock=flaky_clock)
+    with pytest.raises(RuntimeError):
+        bloom.advance()
+    bloom.advance()
+    bloom.add("item")
+    assert bloom.contains("item")
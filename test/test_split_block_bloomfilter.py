@@ -0,0 +1,70 @@
+import struct
+
+import pytest
+from bloomlib import SplitBlockBloomFilter
+
+
+def test_can_add_and_contains():
+    bloom = SplitBlockBloomFilter(expected_number_of_items=10, desired_false_positive_rate=0.05)
+    bloom.add(b"raw")
+    bloom.add("een")
+    bloom.add(1)
+    bloom.add(2.5)
+    assert bloom.contains(b"raw")
+    assert bloom.contains("een")
+    assert bloom.contains(1)
+    assert bloom.contains(2.5)
+    assert not bloom.contains("twee")
+
+def test_values_are_plain_encoded():
+    bloom = SplitBlockBloomFilter(expected_number_of_items=10, desired_false_positive_rate=0.05)
+    bloom.add(42)
+    bloom.add("een")
+    assert bloom.contains(struct.pack("<q", 42))
+    assert bloom.contains("een".encode())
+    with pytest.raises(TypeError):
+        bloom.add(True)
+    with pytest.raises(TypeError):
+        bloom.add([1, 2])
+
+def test_add_bulk_and_clear():
+    bloom = SplitBlockBloomFilter(expected_number_of_items=100, desired_false_positive_rate=0.05)
+    bloom.add_bulk(items=range(100))
+    assert all(bloom.contains(i) for i in range(100))
+    bloom.clear()
+    assert not any(bloom.contains(i) for i in range(100))
+    with pytest.raises(Exception):
+        bloom.add_bulk(items=3)
+
+def test_hashes_can_be_inserted_directly():
+    bloom = SplitBlockBloomFilter(expected_number_of_items=10, desired_false_positive_rate=0.05)
+    bloom.add_hash(0xc000_0000_1234_5678)
+    assert bloom.contains_hash(0xc000_0000_1234_5678)
+
+def test_size_is_power_of_two():
+    bloom = SplitBlockBloomFilter(expected_number_of_items=10_000, desired_false_positive_rate=0.01)
+    size = bloom.get_number_of_bytes()
+    assert size & (size - 1) == 0
+    assert bloom.get_number_of_blocks() == size // 32
+    assert bloom.false_positive_rate(10_000) <= 0.01
+
+def test_bitset_roundtrip():
+    bloom = SplitBlockBloomFilter(expected_number_of_items=100, desired_false_positive_rate=0.01)
+    bloom.add_bulk(["a", "b", "c"])
+    data = bloom.to_bytes()
+    assert len(data) == bloom.get_number_of_bytes()
+    restored = SplitBlockBloomFilter.from_bytes(data)
+    assert all(restored.contains(v) for v in ["a", "b", "c"])
+    with pytest.raises(ValueError):
+        SplitBlockBloomFilter.from_bytes(data[:-1])
+
+def test_parquet_roundtrip():
+    bloom = SplitBlockBloomFilter(expected_number_of_items=100, desired_false_positive_rate=0.01)
+    bloom.add_bulk(["a", "b", "c"])
+    data = bloom.to_parquet_bytes()
+    assert data.endswith(bloom.to_bytes())
+    # extra bytes after the bitset are ignored, as when reading a fixed size range from a file
+    restored = SplitBlockBloomFilter.from_parquet_bytes(data + b"\x00" * 16)
+    assert restored.to_bytes() == bloom.to_bytes()
+    with pytest.raises(ValueError):
+        SplitBlockBloomFilter.from_parquet_bytes(data[:-1])
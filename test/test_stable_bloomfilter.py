@@ -0,0 +1,46 @@
+import pytest
+from bloomlib import StableBloomFilter
+
+
+def test_can_add_and_contains():
+    bloom = StableBloomFilter(number_of_cells=1_000, desired_false_positive_rate=0.01)
+    bloom.add(1)
+    bloom.add("een")
+    assert bloom.contains(item=1)
+    assert bloom.contains(item="een")
+    assert not bloom.contains(item=1111)
+
+def test_false_positive_rate_stays_stable():
+    bloom = StableBloomFilter(number_of_cells=10_000, desired_false_positive_rate=0.02, seed=7)
+    assert bloom.stable_false_positive_rate() <= 0.02
+    bloom.add_bulk(range(200_000))
+    assert abs(bloom.current_false_positive_rate() - bloom.stable_false_positive_rate()) < 0.005
+    false_positives = sum(bloom.contains(i) for i in range(1_000_000, 1_050_000))
+    assert false_positives / 50_000 < 0.03
+
+def test_old_items_fade_out():
+    bloom = StableBloomFilter(number_of_cells=1_000, desired_false_positive_rate=0.01, max_value=1, seed=7)
+    bloom.add("first")
+    bloom.add_bulk(range(10_000))
+    assert not bloom.contains("first")
+    assert bloom.contains(9_999)
+
+def test_parameters():
+    bloom = StableBloomFilter(number_of_cells=1_000, desired_false_positive_rate=0.01, max_value=7)
+    assert bloom.get_number_of_cells() == 1_000
+    assert bloom.get_number_of_hashes() == 7
+    assert bloom.get_max_value() == 7
+    assert bloom.get_decrements_per_insert() >= 1
+    with pytest.raises(ValueError):
+        StableBloomFilter(number_of_cells=1_000, desired_false_positive_rate=0.01, max_value=0)
+    with pytest.raises(ValueError):
+        StableBloomFilter(number_of_cells=1_000, desired_false_positive_rate=1.5)
+
+def test_add_bulk_and_clear():
+    bloom = StableBloomFilter(number_of_cells=10_000, desired_false_positive_rate=0.05)
+    bloom.add_bulk(items=range(10))
+    assert all(bloom.contains(i) for i in range(10))
+    bloom.clear()
+    assert not any(bloom.contains(i) for i in range(10))
+    with pytest.raises(Exception):
+        bloom.add_bulk(items=3)